@@ -0,0 +1,129 @@
+use std::cmp::Ordering;
+
+use super::{manhattan_dist, movement_path, movement_range, Blocked, BattleSummary, Coords, Occupancy, Ruleset, SimUnit, SupportBonus, Walls};
+
+/// Where a unit moves and who it attacks from there
+#[derive(Debug, PartialEq)]
+pub struct Plan {
+    pub destination: Coords,
+    /// Index into the units the plan was made from
+    pub target: Option<usize>,
+}
+
+/// How good an attack looks. More kill chance wins, then a better trade, then less risk.
+struct AttackScore {
+    kill: f64,
+    trade: f64,
+    death: f64,
+}
+
+fn compare_attacks(a: &AttackScore, b: &AttackScore) -> Ordering {
+    a.kill.total_cmp(&b.kill)
+        .then(a.trade.total_cmp(&b.trade))
+        .then(b.death.total_cmp(&a.death))
+}
+
+/// Takes the best attack in reach, judged by the forecast odds: the likeliest kill,
+/// then the most damage dealt over taken. With nothing in reach the unit walks toward
+/// the nearest unit on the other side. Ties go to the lowest coords so runs repeat.
+pub fn plan_turn<W: Walls + ?Sized>(rules: &Ruleset, actor: usize, units: &[SimUnit], walls: &W) -> Plan {
+    let unit = &units[actor];
+    let mut tiles: Vec<Coords> = movement_range(unit.coords, unit.stats.mov, unit.unit_type, units, walls)
+        .into_iter()
+        .collect();
+    tiles.sort_by_key(|c| (c.x, c.y));
+
+    let hostiles: Vec<usize> = (0..units.len())
+        .filter(|i| units[*i].is_alive() && units[*i].unit_type != unit.unit_type)
+        .collect();
+
+    let mut best: Option<(AttackScore, Plan)> = None;
+    for tile in tiles.iter() {
+        for target in hostiles.iter() {
+            let defender = &units[*target];
+            let dist = manhattan_dist(*tile, defender.coords);
+            if !unit.weapon.within_range(dist) { continue }
+
+            let odds = BattleSummary::new(
+                rules,
+                &unit.stats,
+                &unit.weapon,
+                &defender.stats,
+                &defender.weapon,
+                dist,
+                SupportBonus::default(),
+                SupportBonus::default(),
+            ).odds();
+            let score = AttackScore {
+                kill: odds.kill,
+                trade: odds.expected_damage - odds.expected_taken,
+                death: odds.death,
+            };
+
+            if best.as_ref().is_none_or(|(b, _)| compare_attacks(&score, b) == Ordering::Greater) {
+                best = Some((score, Plan { destination: *tile, target: Some(*target) }));
+            }
+        }
+    }
+    if let Some((_, plan)) = best {
+        return plan;
+    }
+
+    let nearest = hostiles.iter()
+        .map(|i| units[*i].coords)
+        .min_by_key(|c| (manhattan_dist(unit.coords, *c), c.x, c.y));
+    let destination = match nearest {
+        Some(goal) => tiles.iter()
+            .min_by_key(|c| (manhattan_dist(**c, goal), c.x, c.y))
+            .copied()
+            .unwrap_or(unit.coords),
+        None => unit.coords,
+    };
+    Plan { destination, target: None }
+}
+
+/// The tiles a unit walks through to reach its planned destination, start included
+pub fn plan_path<W: Walls + ?Sized, O: Occupancy + ?Sized>(
+    unit: &SimUnit,
+    destination: Coords,
+    units: &O,
+    walls: &W,
+) -> Vec<Coords> {
+    let blocked = Blocked { walls, units, unit_type: unit.unit_type };
+    movement_path(destination, unit.coords, &blocked, unit.stats.mov)
+        .unwrap_or_else(|| vec![unit.coords])
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use crate::{manhattan_dist, Coords, Grid, Ruleset, SimUnit, UnitStats, UnitType, Weapon, WeaponRange};
+    #[allow(unused_imports)]
+    use super::{plan_turn, Plan};
+
+    #[test]
+    fn test_plan_prefers_the_kill() {
+        let grid = Grid::new(8, 8);
+        let stats = UnitStats { mov: 2, skill: 50, ..Default::default() };
+        let units = vec![
+            SimUnit::new(stats.clone(), Weapon::default(), Coords::new(2, 2), UnitType::Enemy),
+            SimUnit::new(UnitStats { hp: 20, ..Default::default() }, Weapon::default(), Coords::new(4, 2), UnitType::Player),
+            SimUnit::new(UnitStats { hp: 1, ..Default::default() }, Weapon::default(), Coords::new(2, 4), UnitType::Player),
+        ];
+
+        let plan = plan_turn(&Ruleset::default(), 0, &units, &grid);
+        assert_eq!(plan.target, Some(2));
+        assert_eq!(manhattan_dist(plan.destination, Coords::new(2, 4)), 1);
+    }
+
+    #[test]
+    fn test_plan_walks_toward_hostiles() {
+        let grid = Grid::new(10, 3);
+        let bow = Weapon { range: WeaponRange::Ranged { min: 2, max: 2 }, ..Default::default() };
+        let units = vec![
+            SimUnit::new(UnitStats { mov: 2, ..Default::default() }, bow, Coords::new(0, 1), UnitType::Enemy),
+            SimUnit::new(UnitStats::default(), Weapon::default(), Coords::new(9, 1), UnitType::Player),
+        ];
+
+        assert_eq!(plan_turn(&Ruleset::default(), 0, &units, &grid), Plan { destination: Coords::new(2, 1), target: None });
+    }
+}
@@ -0,0 +1,329 @@
+use rand::Rng;
+
+use super::{Battle, BattleOutcome, BattleSummary, Coords, Grid, Ruleset, SimUnit, SupportBonus, UnitStats, UnitType, Weapon, WeaponRange};
+
+// An open arena, wide enough that a bow gets a shot in before a sword closes
+const ARENA_WIDTH: i32 = 8;
+const ARENA_HEIGHT: i32 = 5;
+
+/// A unit with the weapon it fights with
+#[derive(Clone, Debug)]
+pub struct Loadout {
+    pub stats: UnitStats,
+    pub weapon: Weapon,
+}
+
+impl Loadout {
+    pub fn new(stats: UnitStats, weapon: Weapon) -> Self {
+        Self { stats, weapon }
+    }
+}
+
+/// Totals over every duel one loadout fought
+#[derive(Debug, Default, PartialEq)]
+pub struct MatchupStats {
+    pub unit: String,
+    pub weapon: String,
+    pub duels: u32,
+    pub wins: u32,
+    pub losses: u32,
+    /// Summed over the duels that were won
+    pub turns_to_kill: u32,
+    pub damage_dealt: u32,
+    pub damage_taken: u32,
+}
+
+impl MatchupStats {
+    pub fn win_rate(&self) -> f64 {
+        ratio(self.wins, self.duels)
+    }
+
+    pub fn loss_rate(&self) -> f64 {
+        ratio(self.losses, self.duels)
+    }
+
+    /// None when no duel was won
+    pub fn avg_turns_to_kill(&self) -> Option<f64> {
+        (self.wins > 0).then(|| ratio(self.turns_to_kill, self.wins))
+    }
+
+    pub fn avg_damage_dealt(&self) -> f64 {
+        ratio(self.damage_dealt, self.duels)
+    }
+
+    pub fn avg_damage_taken(&self) -> f64 {
+        ratio(self.damage_taken, self.duels)
+    }
+}
+
+fn ratio(count: u32, total: u32) -> f64 {
+    if total == 0 { 0.0 } else { count as f64 / total as f64 }
+}
+
+/// One fight's numbers for a loadout, averaged over the opponents
+#[derive(Debug, Default, PartialEq)]
+pub struct DamageStats {
+    pub unit: String,
+    pub weapon: String,
+    pub dmg: f64,
+    pub hit: f64,
+    pub crit: f64,
+    pub double_rate: f64,
+    pub expected_damage: f64,
+    pub expected_taken: f64,
+}
+
+/// Plays out one duel in the arena, unit against opponent. The unit moves first.
+pub fn duel<R: Rng>(rules: &Ruleset, unit: &Loadout, opponent: &Loadout, rng: &mut R) -> BattleOutcome {
+    let row = ARENA_HEIGHT / 2;
+    let mut battle = Battle::new(Grid::new(ARENA_WIDTH, ARENA_HEIGHT), vec![
+        SimUnit::new(unit.stats.clone(), unit.weapon.clone(), Coords::new(0, row), UnitType::Player),
+        SimUnit::new(opponent.stats.clone(), opponent.weapon.clone(), Coords::new(ARENA_WIDTH - 1, row), UnitType::Enemy),
+    ]);
+    battle.rules = rules.clone();
+    battle.run(rng)
+}
+
+/// Every unit with every weapon, dueling each opponent trials times
+pub fn run_matchups<R: Rng>(
+    rules: &Ruleset,
+    units: &[UnitStats],
+    weapons: &[Weapon],
+    opponents: &[Loadout],
+    trials: u32,
+    rng: &mut R,
+) -> Vec<MatchupStats> {
+    let mut results = Vec::new();
+    for stats in units {
+        for weapon in weapons {
+            let loadout = Loadout::new(stats.clone(), weapon.clone());
+            let mut matchup = MatchupStats {
+                unit: stats.name.clone(),
+                weapon: weapon.get_name(),
+                ..Default::default()
+            };
+
+            for opponent in opponents {
+                for _ in 0..trials {
+                    let outcome = duel(rules, &loadout, opponent, rng);
+                    matchup.duels += 1;
+                    matchup.damage_dealt += outcome.player_damage;
+                    matchup.damage_taken += outcome.enemy_damage;
+                    match outcome.winner {
+                        Some(UnitType::Player) => {
+                            matchup.wins += 1;
+                            matchup.turns_to_kill += outcome.turns;
+                        },
+                        Some(UnitType::Enemy) => matchup.losses += 1,
+                        None => {},
+                    }
+                }
+            }
+            results.push(matchup);
+        }
+    }
+    results
+}
+
+/// The forecast for every unit and weapon attacking each opponent from its closest range
+pub fn damage_table(rules: &Ruleset, units: &[UnitStats], weapons: &[Weapon], opponents: &[Loadout]) -> Vec<DamageStats> {
+    let mut results = Vec::new();
+    for stats in units {
+        for weapon in weapons {
+            let mut row = DamageStats {
+                unit: stats.name.clone(),
+                weapon: weapon.get_name(),
+                ..Default::default()
+            };
+
+            for opponent in opponents {
+                let summary = BattleSummary::new(
+                    rules,
+                    stats,
+                    weapon,
+                    &opponent.stats,
+                    &opponent.weapon,
+                    closest_range(weapon),
+                    SupportBonus::default(),
+                    SupportBonus::default(),
+                );
+                let odds = summary.odds();
+                row.dmg += summary.attacker.dmg as f64;
+                row.hit += summary.attacker.hit.min(100) as f64;
+                row.crit += summary.attacker.crit.min(100) as f64;
+                row.double_rate += if summary.attacker.is_double { 1.0 } else { 0.0 };
+                row.expected_damage += odds.expected_damage;
+                row.expected_taken += odds.expected_taken;
+            }
+
+            let count = opponents.len().max(1) as f64;
+            row.dmg /= count;
+            row.hit /= count;
+            row.crit /= count;
+            row.double_rate /= count;
+            row.expected_damage /= count;
+            row.expected_taken /= count;
+            results.push(row);
+        }
+    }
+    results
+}
+
+fn closest_range(weapon: &Weapon) -> u32 {
+    match weapon.range {
+        WeaponRange::Melee(_) => 1,
+        WeaponRange::Ranged { min, .. } => min.max(1),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Csv,
+    Markdown,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(Format::Csv),
+            "markdown" | "md" => Some(Format::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// Rows of text under a header, written out as CSV or a markdown table
+#[derive(Debug, Default)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Self { headers: headers.iter().map(|h| h.to_string()).collect(), rows: Vec::new() }
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Csv => self.to_csv(),
+            Format::Markdown => self.to_markdown(),
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let line = |cells: &[String]| cells.iter().map(|c| csv_cell(c)).collect::<Vec<_>>().join(",");
+        let mut out = line(&self.headers);
+        for row in self.rows.iter() {
+            out.push('\n');
+            out.push_str(&line(row));
+        }
+        out.push('\n');
+        out
+    }
+
+    fn to_markdown(&self) -> String {
+        let line = |cells: &[String]| format!("| {} |", cells.join(" | "));
+        let mut out = line(&self.headers);
+        out.push('\n');
+        out.push_str(&line(&vec!["---".to_string(); self.headers.len()]));
+        for row in self.rows.iter() {
+            out.push('\n');
+            out.push_str(&line(row));
+        }
+        out.push('\n');
+        out
+    }
+}
+
+fn csv_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+pub fn matchup_table(matchups: &[MatchupStats]) -> Table {
+    let mut table = Table::new(&["unit", "weapon", "duels", "win %", "loss %", "avg turns to kill", "avg dmg dealt", "avg dmg taken"]);
+    for m in matchups {
+        table.rows.push(vec![
+            m.unit.clone(),
+            m.weapon.clone(),
+            m.duels.to_string(),
+            format!("{:.1}", m.win_rate() * 100.0),
+            format!("{:.1}", m.loss_rate() * 100.0),
+            m.avg_turns_to_kill().map_or("-".to_string(), |t| format!("{:.2}", t)),
+            format!("{:.2}", m.avg_damage_dealt()),
+            format!("{:.2}", m.avg_damage_taken()),
+        ]);
+    }
+    table
+}
+
+pub fn damage_stats_table(rows: &[DamageStats]) -> Table {
+    let mut table = Table::new(&["unit", "weapon", "dmg", "hit", "crit", "double %", "expected dmg", "expected taken"]);
+    for r in rows {
+        table.rows.push(vec![
+            r.unit.clone(),
+            r.weapon.clone(),
+            format!("{:.2}", r.dmg),
+            format!("{:.1}", r.hit),
+            format!("{:.1}", r.crit),
+            format!("{:.1}", r.double_rate * 100.0),
+            format!("{:.2}", r.expected_damage),
+            format!("{:.2}", r.expected_taken),
+        ]);
+    }
+    table
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use rand::{rngs::StdRng, SeedableRng};
+    #[allow(unused_imports)]
+    use crate::{Ruleset, UnitStats, Weapon, WeaponCollection};
+    #[allow(unused_imports)]
+    use super::{damage_table, run_matchups, Format, Loadout, Table};
+
+    #[test]
+    fn test_matchups_are_seeded() {
+        let units = vec![UnitStats::player()];
+        let catalogue = ron::de::from_bytes::<WeaponCollection>(include_bytes!("../../assets/rouge/catalogue.weapons.ron")).unwrap();
+        let weapons = catalogue.weapons[..3].to_vec();
+        let opponents = vec![Loadout::new(UnitStats::enemy(), catalogue.weapons[0].clone())];
+
+        let first = run_matchups(&Ruleset::default(), &units, &weapons, &opponents, 20, &mut StdRng::seed_from_u64(3));
+        let second = run_matchups(&Ruleset::default(), &units, &weapons, &opponents, 20, &mut StdRng::seed_from_u64(3));
+        assert_eq!(first, second);
+        assert_eq!(first.len(), 3);
+        assert!(first.iter().all(|m| m.duels == 20 && m.wins + m.losses <= 20));
+        // A 3 HP enemy loses most duels to a player with a common weapon
+        assert!(first.iter().all(|m| m.win_rate() > 0.5));
+    }
+
+    #[test]
+    fn test_damage_table_averages_opponents() {
+        let sword = Weapon { attack: 5, hit: 90, ..Default::default() };
+        let opponents = vec![
+            Loadout::new(UnitStats { def: 0, ..UnitStats::enemy() }, Weapon::default()),
+            Loadout::new(UnitStats { def: 2, ..UnitStats::enemy() }, Weapon::default()),
+        ];
+        let rows = damage_table(&Ruleset::default(), &[UnitStats::player()], &[sword], &opponents);
+
+        assert_eq!(rows.len(), 1);
+        // Player atk 3 + 5 against 0 and 2 def
+        assert_eq!(rows[0].dmg, 7.0);
+    }
+
+    #[test]
+    fn test_table_formats() {
+        let mut table = Table::new(&["unit", "win %"]);
+        table.rows.push(vec!["Scooby, Jr".to_string(), "50.0".to_string()]);
+
+        assert_eq!(table.render(Format::Csv), "unit,win %\n\"Scooby, Jr\",50.0\n");
+        assert_eq!(table.render(Format::Markdown), "| unit | win % |\n| --- | --- |\n| Scooby, Jr | 50.0 |\n");
+        assert_eq!(Format::parse("md"), Some(Format::Markdown));
+        assert_eq!(Format::parse("xml"), None);
+    }
+}
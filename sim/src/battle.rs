@@ -0,0 +1,180 @@
+use rand::Rng;
+
+use super::{manhattan_dist, plan_turn, resolve_fight, BattleSummary, Coords, Grid, Occupancy, Ruleset, SupportBonus, UnitStats, UnitType, Weapon};
+
+/// Full turns before a simulated battle is called a draw
+pub const TURN_LIMIT: u32 = 50;
+
+/// A unit on a simulated map, with its equipped weapon. stats.hp is its current HP.
+#[derive(Clone, Debug)]
+pub struct SimUnit {
+    pub stats: UnitStats,
+    pub weapon: Weapon,
+    pub coords: Coords,
+    pub unit_type: UnitType,
+}
+
+impl SimUnit {
+    pub fn new(stats: UnitStats, weapon: Weapon, coords: Coords, unit_type: UnitType) -> Self {
+        Self { stats, weapon, coords, unit_type }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.stats.hp > 0
+    }
+}
+
+// Dead units stay in the list so indices hold, but are off the map
+impl Occupancy for [SimUnit] {
+    fn contains(&self, coords: &Coords) -> bool {
+        self.iter().any(|unit| unit.is_alive() && unit.coords == *coords)
+    }
+
+    fn is_hostile(&self, coords: &Coords, unit_type: &UnitType) -> bool {
+        self.iter().any(|unit| unit.is_alive() && unit.coords == *coords && unit.unit_type != *unit_type)
+    }
+}
+
+/// HP each side lost in one fight
+#[derive(Debug, Default, PartialEq)]
+pub struct FightDamage {
+    pub dealt: u32,
+    pub taken: u32,
+}
+
+/// How a simulated battle ended
+#[derive(Debug, Default, PartialEq)]
+pub struct BattleOutcome {
+    /// None when the turn limit ran out first
+    pub winner: Option<UnitType>,
+    pub turns: u32,
+    pub fights: u32,
+    /// HP the player side took off the enemy side, counters included
+    pub player_damage: u32,
+    pub enemy_damage: u32,
+}
+
+/// A battle with both sides played by the AI. Players move first, like in the game.
+#[derive(Clone, Debug)]
+pub struct Battle {
+    pub grid: Grid,
+    pub units: Vec<SimUnit>,
+    pub turn_limit: u32,
+    pub rules: Ruleset,
+}
+
+impl Battle {
+    pub fn new(grid: Grid, units: Vec<SimUnit>) -> Self {
+        Self { grid, units, turn_limit: TURN_LIMIT, rules: Ruleset::default() }
+    }
+
+    pub fn run<R: Rng>(&mut self, rng: &mut R) -> BattleOutcome {
+        let mut outcome = BattleOutcome::default();
+
+        for turn in 1..=self.turn_limit {
+            outcome.turns = turn;
+            for side in [UnitType::Player, UnitType::Enemy] {
+                for actor in 0..self.units.len() {
+                    let unit = &self.units[actor];
+                    if !unit.is_alive() || unit.unit_type != side { continue }
+
+                    self.take_turn(actor, rng, &mut outcome);
+                    if let Some(winner) = self.winner() {
+                        outcome.winner = Some(winner);
+                        return outcome;
+                    }
+                }
+            }
+        }
+
+        outcome
+    }
+
+    /// The side left standing, once the other is wiped out
+    pub fn winner(&self) -> Option<UnitType> {
+        let standing = |unit_type: UnitType| self.units.iter().any(|u| u.is_alive() && u.unit_type == unit_type);
+        match (standing(UnitType::Player), standing(UnitType::Enemy)) {
+            (true, false) => Some(UnitType::Player),
+            (false, true) => Some(UnitType::Enemy),
+            _ => None,
+        }
+    }
+
+    fn take_turn<R: Rng>(&mut self, actor: usize, rng: &mut R, outcome: &mut BattleOutcome) {
+        let plan = plan_turn(&self.rules, actor, &self.units, &self.grid);
+        self.units[actor].coords = plan.destination;
+        let Some(target) = plan.target else { return };
+
+        let damage = self.fight(actor, target, rng);
+        outcome.fights += 1;
+        let (player, enemy) = match self.units[actor].unit_type {
+            UnitType::Player => (damage.dealt, damage.taken),
+            UnitType::Enemy => (damage.taken, damage.dealt),
+        };
+        outcome.player_damage += player;
+        outcome.enemy_damage += enemy;
+    }
+
+    /// Rolls one fight the same way the game does and applies it
+    pub fn fight<R: Rng>(&mut self, attacker: usize, defender: usize, rng: &mut R) -> FightDamage {
+        let atk = &self.units[attacker];
+        let def = &self.units[defender];
+        let summary = BattleSummary::new(
+            &self.rules,
+            &atk.stats,
+            &atk.weapon,
+            &def.stats,
+            &def.weapon,
+            manhattan_dist(atk.coords, def.coords),
+            SupportBonus::default(),
+            SupportBonus::default(),
+        );
+        let result = resolve_fight(&summary.roll_attacks(rng), atk.stats.hp, def.stats.hp);
+        let damage = FightDamage {
+            dealt: def.stats.hp - result.defender_hp,
+            taken: atk.stats.hp - result.attacker_hp,
+        };
+
+        self.units[attacker].stats.hp = result.attacker_hp;
+        self.units[defender].stats.hp = result.defender_hp;
+        damage
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use rand::{rngs::StdRng, SeedableRng};
+    #[allow(unused_imports)]
+    use crate::{Coords, Grid, UnitStats, UnitType, Weapon};
+    #[allow(unused_imports)]
+    use super::{Battle, SimUnit};
+
+    #[allow(dead_code)]
+    fn battle() -> Battle {
+        let strong = UnitStats { hp: 20, atk: 6, def: 2, skill: 40, mov: 3, ..Default::default() };
+        Battle::new(Grid::new(8, 8), vec![
+            SimUnit::new(strong, Weapon::default(), Coords::new(0, 0), UnitType::Player),
+            SimUnit::new(UnitStats::enemy(), Weapon::default(), Coords::new(7, 7), UnitType::Enemy),
+            SimUnit::new(UnitStats::enemy(), Weapon::default(), Coords::new(7, 6), UnitType::Enemy),
+        ])
+    }
+
+    #[test]
+    fn test_battle_runs_to_completion() {
+        let mut sim = battle();
+        let outcome = sim.run(&mut StdRng::seed_from_u64(7));
+
+        assert_eq!(outcome.winner, Some(UnitType::Player));
+        assert!(outcome.fights >= 2);
+        // Both enemies start on 3 HP
+        assert_eq!(outcome.player_damage, 6);
+        assert!(sim.units.iter().filter(|u| u.unit_type == UnitType::Enemy).all(|u| !u.is_alive()));
+    }
+
+    #[test]
+    fn test_battle_is_seeded() {
+        let first = battle().run(&mut StdRng::seed_from_u64(11));
+        let second = battle().run(&mut StdRng::seed_from_u64(11));
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,132 @@
+//! Monte Carlo duels between every unit and weapon, for tuning the combat numbers.
+//!
+//! cargo run -p fun-rougelike-sim --bin balance -- --trials 200 --format csv > matchups.csv
+
+use std::process::ExitCode;
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Deserialize;
+
+use fun_rougelike_sim::{
+    damage_stats_table, damage_table, matchup_table, run_matchups, Format, Loadout, Ruleset, UnitStats,
+    WeaponCollection,
+};
+
+const USAGE: &str = "\
+Usage: balance [options]
+
+Duels every unit in the units file, holding every weapon in the catalogue, against the
+opponent holding each catalogue weapon in turn.
+
+Options:
+  --units <file>       Units to test [default: assets/rouge/available.units.ron]
+  --weapons <file>     Weapon catalogue [default: assets/rouge/catalogue.weapons.ron]
+  --rules <file>       Combat rules [default: assets/rouge/classic.rules.ron]
+  --opponent <name>    enemy, elite or the name of a unit in the units file [default: enemy]
+  --trials <n>         Duels per matchup [default: 100]
+  --seed <n>           Seed for the dice [default: 0]
+  --format <format>    csv or markdown [default: markdown]
+  --table <table>      matchups for win rates and turns to kill, damage for the forecast
+                       numbers [default: matchups]
+";
+
+// The part of the game's units.ron file the duels need, named as the file names it
+#[derive(Deserialize)]
+struct UnitCollection {
+    units: Vec<UnitStats>,
+}
+
+
+struct Args {
+    units: String,
+    weapons: String,
+    rules: String,
+    opponent: String,
+    trials: u32,
+    seed: u64,
+    format: Format,
+    damage: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+        units: "assets/rouge/available.units.ron".to_string(),
+        weapons: "assets/rouge/catalogue.weapons.ron".to_string(),
+        rules: "assets/rouge/classic.rules.ron".to_string(),
+        opponent: "enemy".to_string(),
+        trials: 100,
+        seed: 0,
+        format: Format::Markdown,
+        damage: false,
+    };
+
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", flag));
+        match flag.as_str() {
+            "--units" => parsed.units = value()?,
+            "--weapons" => parsed.weapons = value()?,
+            "--rules" => parsed.rules = value()?,
+            "--opponent" => parsed.opponent = value()?,
+            "--trials" => parsed.trials = value()?.parse().map_err(|_| "--trials must be a number".to_string())?,
+            "--seed" => parsed.seed = value()?.parse().map_err(|_| "--seed must be a number".to_string())?,
+            "--format" => {
+                let name = value()?;
+                parsed.format = Format::parse(&name).ok_or(format!("unknown format {}", name))?;
+            },
+            "--table" => match value()?.as_str() {
+                "matchups" => parsed.damage = false,
+                "damage" => parsed.damage = true,
+                other => return Err(format!("unknown table {}", other)),
+            },
+            other => return Err(format!("unknown option {}", other)),
+        }
+    }
+    Ok(parsed)
+}
+
+fn read_ron<T: for<'de> serde::Deserialize<'de>>(path: &str) -> Result<T, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+    ron::de::from_bytes(&bytes).map_err(|e| format!("could not parse {}: {}", path, e))
+}
+
+fn run(args: Args) -> Result<String, String> {
+    let units: UnitCollection = read_ron(&args.units)?;
+    let weapons: WeaponCollection = read_ron(&args.weapons)?;
+    let rules: Ruleset = read_ron(&args.rules)?;
+
+    let opponent = match args.opponent.as_str() {
+        "enemy" => UnitStats::enemy(),
+        "elite" => UnitStats::elite(),
+        name => units.units.iter().find(|u| u.name == name).cloned()
+            .ok_or(format!("no unit named {} in {}", name, args.units))?,
+    };
+    let opponents: Vec<Loadout> = weapons.weapons.iter()
+        .map(|weapon| Loadout::new(opponent.clone(), weapon.clone()))
+        .collect();
+
+    let table = if args.damage {
+        damage_stats_table(&damage_table(&rules, &units.units, &weapons.weapons, &opponents))
+    } else {
+        let mut rng = StdRng::seed_from_u64(args.seed);
+        matchup_table(&run_matchups(&rules, &units.units, &weapons.weapons, &opponents, args.trials, &mut rng))
+    };
+    Ok(table.render(args.format))
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        print!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match parse_args(args.into_iter()).and_then(run) {
+        Ok(output) => {
+            print!("{}", output);
+            ExitCode::SUCCESS
+        },
+        Err(error) => {
+            eprintln!("balance: {}\n\n{}", error, USAGE);
+            ExitCode::from(2)
+        },
+    }
+}
@@ -0,0 +1,550 @@
+use std::fmt;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{Ruleset, UnitStats, Weapon, WeaponEffectiveness};
+
+/// Hit, avoid and crit granted by nearby support partners
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub struct SupportBonus {
+    pub hit: u32,
+    pub avoid: u32,
+    pub crit: u32,
+}
+
+impl SupportBonus {
+    pub fn is_empty(&self) -> bool {
+        *self == SupportBonus::default()
+    }
+
+    pub fn add(&mut self, other: &SupportBonus) {
+        self.hit += other.hit;
+        self.avoid += other.avoid;
+        self.crit += other.crit;
+    }
+}
+
+impl fmt::Display for SupportBonus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "+{}/+{}/+{}", self.hit, self.avoid, self.crit)
+    }
+}
+
+/// One side of a fight with everything its rolls need
+#[derive(Debug, Clone)]
+pub struct ActorSummary {
+    pub hp: u32,
+    pub dmg: u32,
+    pub hit: u32,
+    pub crit: u32,
+    pub is_double: bool,
+    pub support: SupportBonus,
+}
+
+/// Both sides of a fight before anything is rolled. The forecast shows it and the
+/// battle queue rolls from it.
+#[derive(Debug, Clone)]
+pub struct BattleSummary {
+    pub attacker: ActorSummary,
+    pub defender: ActorSummary,
+    /// The defender's HP even when it can't counter, for the odds
+    pub defender_hp: u32,
+    /// The defender's weapon reaches the attacker
+    pub counters: bool,
+    pub crit_multiplier: u32,
+}
+
+/// Chances of how a fight ends, exact over every miss, hit and crit in the attack order
+#[derive(Debug, Default, PartialEq)]
+pub struct FightOdds {
+    pub kill: f64,
+    pub death: f64,
+    /// HP the defender is expected to lose
+    pub expected_damage: f64,
+    /// HP the attacker is expected to lose
+    pub expected_taken: f64,
+}
+
+pub fn percent(chance: f64) -> String {
+    format!("{:.0}%", chance * 100.0)
+}
+
+impl fmt::Display for BattleSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} HP {}\n", self.attacker.hp, self.defender.hp)?;
+        write!(f, "{}", self.attacker.dmg)?;
+        if self.attacker.is_double {
+            write!(f, "x2")?;
+        }
+        write!(f, " DMG ")?;
+        write!(f, "{}", self.defender.hp)?;
+        if self.defender.is_double {
+            write!(f, "x2")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{} HIT {}", self.attacker.hit, self.defender.hit)?;
+        writeln!(f, "{} CRIT {}", self.attacker.crit, self.defender.crit)?;
+        if !self.attacker.support.is_empty() || !self.defender.support.is_empty() {
+            writeln!(f, "{} SUPPORT {}", self.attacker.support, self.defender.support)?;
+        }
+        let odds = self.odds();
+        writeln!(f, "{} KILL {}", percent(odds.kill), percent(odds.death))?;
+        writeln!(f, "{:.1} EXP DMG {:.1}", odds.expected_damage, odds.expected_taken)?;
+        Ok(())
+    }
+}
+
+impl BattleSummary {
+    /// Who strikes when. The battle queue rolls its attacks in this order.
+    pub fn attack_order(&self) -> Vec<Actor> {
+        let mut order = vec![Actor::Attacker];
+        if self.counters {
+            order.push(Actor::Defender);
+        }
+        if self.attacker.is_double {
+            order.push(Actor::Attacker);
+        }
+        if self.counters && self.defender.is_double {
+            order.push(Actor::Defender);
+        }
+        order
+    }
+
+    /// Rolls every strike in attack order. Strikes after a death are still rolled,
+    /// so the rng moves on the same however the fight goes.
+    pub fn roll_attacks<R: Rng>(&self, rng: &mut R) -> Vec<(Actor, Damage)> {
+        self.attack_order().into_iter()
+            .map(|actor| {
+                let striker = match actor {
+                    Actor::Attacker => &self.attacker,
+                    Actor::Defender => &self.defender,
+                };
+                (actor, simulate_attack(striker, self.crit_multiplier, rng))
+            })
+            .collect()
+    }
+
+    pub fn odds(&self) -> FightOdds {
+        let mut odds = FightOdds::default();
+        self.add_odds(&self.attack_order(), self.attacker.hp, self.defender_hp, 1.0, &mut odds);
+        odds
+    }
+
+    /// Walks every branch of the remaining attacks. A fight stops at the first death.
+    fn add_odds(&self, order: &[Actor], atk_hp: u32, def_hp: u32, chance: f64, odds: &mut FightOdds) {
+        let Some((actor, rest)) = order.split_first() else {
+            odds.expected_damage += chance * (self.defender_hp - def_hp) as f64;
+            odds.expected_taken += chance * (self.attacker.hp - atk_hp) as f64;
+            return;
+        };
+
+        let striker = match actor {
+            Actor::Attacker => &self.attacker,
+            Actor::Defender => &self.defender,
+        };
+        let hit = striker.hit.min(100) as f64 / 100.0;
+        let crit = striker.crit.min(100) as f64 / 100.0;
+        let branches = [
+            (1.0 - hit, 0),
+            (hit * (1.0 - crit), striker.dmg),
+            (hit * crit, striker.dmg.saturating_mul(self.crit_multiplier)),
+        ];
+
+        for (branch, damage) in branches {
+            if branch <= 0.0 { continue }
+            let chance = chance * branch;
+            let (atk_hp, def_hp) = match actor {
+                Actor::Attacker => (atk_hp, def_hp.saturating_sub(damage)),
+                Actor::Defender => (atk_hp.saturating_sub(damage), def_hp),
+            };
+
+            if def_hp == 0 {
+                odds.kill += chance;
+                self.add_odds(&[], atk_hp, def_hp, chance, odds);
+            } else if atk_hp == 0 {
+                odds.death += chance;
+                self.add_odds(&[], atk_hp, def_hp, chance, odds);
+            } else {
+                self.add_odds(rest, atk_hp, def_hp, chance, odds);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        rules: &Ruleset,
+        attacker: &UnitStats,
+        attacker_weapon: &Weapon,
+        defender: &UnitStats,
+        defender_weapon: &Weapon,
+        dist: u32,
+        attacker_support: SupportBonus,
+        defender_support: SupportBonus,
+    ) -> Self {
+        let (atk_hit, def_hit) = calculate_accuracy_for_both(rules, attacker, attacker_weapon, defender, defender_weapon);
+        let atk_hit = apply_support_accuracy(atk_hit, &attacker_support, &defender_support);
+        let def_hit = apply_support_accuracy(def_hit, &defender_support, &attacker_support);
+        let (atk_crit, def_crit) = calculate_crit_for_both(rules, attacker, attacker_weapon, defender, defender_weapon);
+        let (atk_dmg, def_dmg) = calculate_damage_for_both(rules, attacker, attacker_weapon, defender, defender_weapon);
+        let (atk_is_double, def_is_double) = is_double_attack_for_both(
+            rules,
+            attacker,
+            attacker_weapon,
+            defender,
+            defender_weapon
+        );
+
+        let attacker_summary = ActorSummary {
+            hp: attacker.hp,
+            dmg: atk_dmg,
+            hit: atk_hit,
+            crit: atk_crit + attacker_support.crit,
+            is_double: atk_is_double,
+            support: attacker_support,
+        };
+
+        // NOTE: This dist is from the units original position, not it's new position where the
+        // dist should be calcualted from
+        let defender_summary = if defender_weapon.within_range(dist) {
+            ActorSummary {
+                hp: defender.hp,
+                dmg: def_dmg,
+                hit: def_hit,
+                crit: def_crit + defender_support.crit,
+                is_double: def_is_double,
+                support: defender_support,
+            }
+        } else {
+            ActorSummary {
+                hp: 0,
+                dmg: 0,
+                hit: 0,
+                crit: 0,
+                is_double: false,
+                support: SupportBonus::default(),
+            }
+        };
+
+        BattleSummary {
+            attacker: attacker_summary,
+            defender: defender_summary,
+            defender_hp: defender.hp,
+            counters: defender_weapon.within_range(dist),
+            crit_multiplier: rules.crit_multiplier,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Actor {
+    Attacker,
+    Defender
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Damage {
+    Hit(u32),
+    Crit(u32),
+    Miss
+}
+
+impl Damage {
+    /// HP the strike takes off
+    pub fn amount(&self) -> u32 {
+        match self {
+            Damage::Hit(x) | Damage::Crit(x) => *x,
+            Damage::Miss => 0,
+        }
+    }
+}
+
+/// A strike that landed before the fight ended, with both sides' HP after it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strike {
+    pub actor: Actor,
+    pub damage: Damage,
+    pub attacker_hp: u32,
+    pub defender_hp: u32,
+}
+
+/// How a rolled fight ended
+#[derive(Debug, Clone, PartialEq)]
+pub struct FightResult {
+    pub attacker_hp: u32,
+    pub defender_hp: u32,
+    /// Who fell, if anyone. The fight stops there.
+    pub death: Option<Actor>,
+    /// The strikes in order, for the game to play back
+    pub strikes: Vec<Strike>,
+}
+
+/// Plays out rolled strikes from the given HP until one side falls
+pub fn resolve_fight(attacks: &[(Actor, Damage)], attacker_hp: u32, defender_hp: u32) -> FightResult {
+    let mut result = FightResult { attacker_hp, defender_hp, death: None, strikes: Vec::new() };
+    for (actor, damage) in attacks {
+        match actor {
+            Actor::Attacker => result.defender_hp = result.defender_hp.saturating_sub(damage.amount()),
+            Actor::Defender => result.attacker_hp = result.attacker_hp.saturating_sub(damage.amount()),
+        }
+        result.strikes.push(Strike {
+            actor: *actor,
+            damage: damage.clone(),
+            attacker_hp: result.attacker_hp,
+            defender_hp: result.defender_hp,
+        });
+        if result.defender_hp == 0 {
+            result.death = Some(Actor::Defender);
+            break;
+        }
+        if result.attacker_hp == 0 {
+            result.death = Some(Actor::Attacker);
+            break;
+        }
+    }
+    result
+}
+
+/// A carrying unit fights with the carry penalty
+pub fn effective_stats(stats: &UnitStats, is_carrying: bool) -> UnitStats {
+    if is_carrying {
+        stats.with_carry_penalty()
+    } else {
+        stats.clone()
+    }
+}
+
+pub fn calculate_damage_for_both(
+    rules: &Ruleset,
+    attacker: &UnitStats,
+    attacker_weapon: &Weapon,
+    defender: &UnitStats,
+    defender_weapon: &Weapon
+) ->(u32, u32) {
+    let atk_damange = calculate_damage(rules, attacker, attacker_weapon, defender, defender_weapon);
+    let def_damange = calculate_damage(rules, defender, defender_weapon, attacker, attacker_weapon);
+    (atk_damange, def_damange)
+}
+
+pub fn calculate_damage(
+    rules: &Ruleset,
+    attacker: &UnitStats,
+    attacker_weapon: &Weapon,
+    defender: &UnitStats,
+    defender_weapon: &Weapon
+) -> u32 {
+    let damage = (attacker.atk + attacker_weapon.attack).saturating_sub(defender.def);
+
+    match attacker_weapon.get_effectivness(defender_weapon) {
+        WeaponEffectiveness::Strong => damage + rules.weapon_damage_bonus,
+        WeaponEffectiveness::Weak => damage.saturating_sub(rules.weapon_damage_bonus),
+        WeaponEffectiveness::Neutral => damage,
+    }
+}
+
+pub fn calculate_accuracy_for_both(
+    rules: &Ruleset,
+    attacker: &UnitStats,
+    attacker_weapon: &Weapon,
+    defender: &UnitStats,
+    defender_weapon: &Weapon
+) -> (u32, u32) {
+    let atk_acc = calculate_accuracy(rules, attacker, attacker_weapon, defender, defender_weapon);
+    let def_acc = calculate_accuracy(rules, defender, defender_weapon, attacker, attacker_weapon);
+    (atk_acc, def_acc)
+}
+
+pub fn calculate_accuracy(
+    rules: &Ruleset,
+    attacker: &UnitStats,
+    attacker_weapon: &Weapon,
+    defender: &UnitStats,
+    defender_weapon: &Weapon
+) -> u32 {
+    let mut atk_accuracy = attacker.accuracy(rules) + attacker_weapon.hit;
+    atk_accuracy = atk_accuracy.saturating_sub(defender.dodge(rules));
+
+    let atk_accuracy = match attacker_weapon.get_effectivness(defender_weapon) {
+        WeaponEffectiveness::Strong => atk_accuracy + rules.weapon_accuracy_bonus,
+        WeaponEffectiveness::Weak => atk_accuracy.saturating_sub(rules.weapon_accuracy_bonus),
+        WeaponEffectiveness::Neutral => atk_accuracy,
+    };
+
+    if atk_accuracy > 100 { 100 } else { atk_accuracy }
+}
+
+pub fn apply_support_accuracy(hit: u32, support: &SupportBonus, opposing_support: &SupportBonus) -> u32 {
+    (hit + support.hit).saturating_sub(opposing_support.avoid).min(100)
+}
+
+pub fn calculate_crit_for_both(
+    rules: &Ruleset,
+    attacker: &UnitStats,
+    attacker_weapon: &Weapon,
+    defender: &UnitStats,
+    defender_weapon: &Weapon
+) -> (u32, u32) {
+    (
+        calculate_crit(rules, attacker, attacker_weapon, defender_weapon),
+        calculate_crit(rules, defender, defender_weapon, attacker_weapon)
+    )
+}
+
+pub fn calculate_crit(
+    rules: &Ruleset,
+    attacker: &UnitStats,
+    attacker_weapon: &Weapon,
+    defender_weapon: &Weapon,
+) -> u32 {
+    let crit = attacker.crit(rules) + attacker_weapon.crit;
+
+    match attacker_weapon.get_effectivness(defender_weapon) {
+        WeaponEffectiveness::Strong => crit + rules.weapon_crit_bonus,
+        WeaponEffectiveness::Weak => crit.saturating_sub(rules.weapon_crit_bonus),
+        WeaponEffectiveness::Neutral => crit,
+    }
+}
+
+pub fn is_double_attack_for_both(
+    rules: &Ruleset,
+    attacker: &UnitStats,
+    attacker_weapon: &Weapon,
+    defender: &UnitStats,
+    defender_weapon: &Weapon
+) -> (bool, bool) {
+    (
+        is_double_attack(rules, attacker, attacker_weapon, defender, defender_weapon),
+        is_double_attack(rules, defender, defender_weapon, attacker, attacker_weapon)
+    )
+}
+
+pub fn is_double_attack(
+    rules: &Ruleset,
+    attacker: &UnitStats,
+    attacker_weapon: &Weapon,
+    defender: &UnitStats,
+    defender_weapon: &Weapon
+) -> bool {
+    let atk_speed = attacker.attack_speed(rules).saturating_sub(attacker_weapon.weight);
+    let def_speed = defender.spd.saturating_sub(defender_weapon.weight);
+    atk_speed.saturating_sub(def_speed) < rules.double_attack_speed
+}
+
+pub fn simulate_attack<R: Rng>(attacker: &ActorSummary, crit_multiplier: u32, rng: &mut R) -> Damage {
+    // NOTE: 0 (inclusive) to 100 (exclusive)
+    // a hit is if hit is greater than rand_number
+    let atk_hit = rng.random_range(0..100);
+    if attacker.hit > atk_hit {
+        let atk_crit = rng.random_range(0..100);
+        if attacker.crit > atk_crit {
+            Damage::Crit(attacker.dmg.saturating_mul(crit_multiplier))
+        } else {
+            Damage::Hit(attacker.dmg)
+        }
+    } else {
+        Damage::Miss
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use crate::{Ruleset, SupportBonus, UnitStats, Weapon, WeaponRange};
+    #[allow(unused_imports)]
+    use super::{resolve_fight, Actor, ActorSummary, BattleSummary, Damage};
+
+    #[allow(dead_code)]
+    fn actor(hp: u32, dmg: u32, hit: u32, crit: u32, is_double: bool) -> ActorSummary {
+        ActorSummary { hp, dmg, hit, crit, is_double, support: SupportBonus::default() }
+    }
+
+    #[allow(dead_code)]
+    fn summary(attacker: ActorSummary, defender: ActorSummary) -> BattleSummary {
+        BattleSummary { defender_hp: defender.hp, attacker, defender, counters: true, crit_multiplier: 3 }
+    }
+
+    #[allow(dead_code)]
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_certain_kill() {
+        let odds = summary(actor(10, 10, 100, 0, false), actor(10, 5, 100, 0, false)).odds();
+        assert!(close(odds.kill, 1.0));
+        assert!(close(odds.death, 0.0));
+        // The defender never gets to counter
+        assert!(close(odds.expected_taken, 0.0));
+        assert!(close(odds.expected_damage, 10.0));
+    }
+
+    #[test]
+    fn test_doubling_needs_both_hits() {
+        // Two 50% swings for 5 against 10 HP, and a counter that can't kill
+        let odds = summary(actor(10, 5, 50, 0, true), actor(10, 1, 100, 0, false)).odds();
+        assert!(close(odds.kill, 0.25));
+        assert!(close(odds.death, 0.0));
+        assert!(close(odds.expected_damage, 5.0));
+        assert!(close(odds.expected_taken, 1.0));
+    }
+
+    #[test]
+    fn test_crits_and_counters() {
+        // Only a crit kills in one strike, otherwise the defender kills back
+        let odds = summary(actor(4, 5, 100, 20, false), actor(15, 4, 50, 0, false)).odds();
+        assert!(close(odds.kill, 0.2));
+        assert!(close(odds.death, 0.8 * 0.5));
+        assert!(close(odds.kill + odds.death, 0.6));
+        assert!(close(odds.expected_damage, 0.2 * 15.0 + 0.8 * 5.0));
+    }
+
+    #[test]
+    fn test_no_counter_out_of_range() {
+        let defender = UnitStats { hp: 10, ..Default::default() };
+        let bow = Weapon { range: WeaponRange::Ranged { min: 2, max: 2 }, ..Default::default() };
+        let summary = BattleSummary::new(
+            &Ruleset::default(),
+            &UnitStats { hp: 10, ..Default::default() }, &bow,
+            &defender, &Weapon::default(),
+            2, SupportBonus::default(), SupportBonus::default(),
+        );
+        assert_eq!(summary.attack_order().len(), if summary.attacker.is_double { 2 } else { 1 });
+        assert!(close(summary.odds().death, 0.0));
+        assert_eq!(summary.defender_hp, 10);
+    }
+
+    #[test]
+    fn test_rules_set_the_numbers() {
+        let stats = UnitStats { hp: 10, atk: 4, skill: 5, spd: 3, ..Default::default() };
+        let rules = Ruleset { accuracy_per_skill: 3, crit_multiplier: 5, ..Default::default() };
+        let summary = BattleSummary::new(
+            &rules,
+            &stats, &Weapon::default(),
+            &UnitStats { hp: 10, ..Default::default() }, &Weapon::default(),
+            1, SupportBonus::default(), SupportBonus::default(),
+        );
+        let classic = BattleSummary::new(
+            &Ruleset::default(),
+            &stats, &Weapon::default(),
+            &UnitStats { hp: 10, ..Default::default() }, &Weapon::default(),
+            1, SupportBonus::default(), SupportBonus::default(),
+        );
+        assert_eq!(summary.attacker.hit, classic.attacker.hit + 5);
+        assert_eq!(summary.attacker.crit, classic.attacker.crit + 5);
+        assert_eq!(summary.crit_multiplier, 5);
+    }
+
+    #[test]
+    fn test_resolve_stops_at_death() {
+        let attacks = [
+            (Actor::Attacker, Damage::Hit(4)),
+            (Actor::Defender, Damage::Crit(9)),
+            (Actor::Attacker, Damage::Hit(4)),
+        ];
+        let result = resolve_fight(&attacks, 9, 10);
+        assert_eq!(result.death, Some(Actor::Attacker));
+        assert_eq!((result.attacker_hp, result.defender_hp), (0, 6));
+        // The third strike never happens
+        assert_eq!(result.strikes.len(), 2);
+        assert_eq!((result.strikes[0].attacker_hp, result.strikes[0].defender_hp), (9, 6));
+        assert_eq!(resolve_fight(&[(Actor::Attacker, Damage::Miss)], 9, 10).death, None);
+    }
+}
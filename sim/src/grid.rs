@@ -0,0 +1,381 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::ops::Add;
+use serde::{Deserialize, Serialize};
+
+use super::WeaponRange;
+
+/// A tile on the level, laid out the same way as the game's grid
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Coords {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Coords {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+
+impl Add for Coords {
+    type Output = Coords;
+
+    fn add(self, rhs: Coords) -> Coords {
+        Coords::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+pub fn manhattan_dist(start: Coords, end: Coords) -> u32 {
+    (end.x - start.x).unsigned_abs() + (end.y - start.y).unsigned_abs()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UnitType {
+    Player,
+    Enemy
+}
+
+impl UnitType {
+    pub fn opponent(&self) -> Self {
+        match self {
+            UnitType::Player => UnitType::Enemy,
+            UnitType::Enemy => UnitType::Player,
+        }
+    }
+}
+
+/// Tiles nothing can stand on or walk through, including everything off the map
+pub trait Walls {
+    fn in_wall(&self, coords: &Coords) -> bool;
+}
+
+/// Where units are standing
+pub trait Occupancy {
+    fn contains(&self, coords: &Coords) -> bool;
+    /// A unit on the other side from unit_type is standing on coords
+    fn is_hostile(&self, coords: &Coords, unit_type: &UnitType) -> bool;
+}
+
+/// A level without the ldtk project, for simulations
+#[derive(Clone, Debug, Default)]
+pub struct Grid {
+    pub width: i32,
+    pub height: i32,
+    pub walls: HashSet<Coords>,
+}
+
+impl Grid {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self { width, height, walls: HashSet::new() }
+    }
+}
+
+impl Walls for Grid {
+    fn in_wall(&self, coords: &Coords) -> bool {
+        coords.x < 0
+            || coords.y < 0
+            || coords.x >= self.width
+            || coords.y >= self.height
+            || self.walls.contains(coords)
+    }
+}
+
+/// Walls plus every unit on the other side, which can't be walked through
+pub struct Blocked<'a, W: ?Sized, O: ?Sized> {
+    pub walls: &'a W,
+    pub units: &'a O,
+    pub unit_type: UnitType,
+}
+
+impl<W: Walls + ?Sized, O: Occupancy + ?Sized> Walls for Blocked<'_, W, O> {
+    fn in_wall(&self, coords: &Coords) -> bool {
+        self.walls.in_wall(coords) || self.units.is_hostile(coords, &self.unit_type)
+    }
+}
+
+pub fn neighbors(center: Coords) -> [Coords; 4] {
+    [
+        center + Coords::new(0, -1),
+        center + Coords::new(0, 1),
+        center + Coords::new(-1, 0),
+        center + Coords::new(1, 0),
+    ]
+}
+
+/// Tiles a unit of the given side can end its move on. Its own side can be walked
+/// through, the other side blocks.
+pub fn movement_range<W: Walls + ?Sized, O: Occupancy + ?Sized>(
+    origin: Coords,
+    mov: u32,
+    unit_type: UnitType,
+    units: &O,
+    walls: &W,
+) -> HashSet<Coords> {
+    let blocked = Blocked { walls, units, unit_type };
+    let mut curr_dist = 0;
+    let mut queue: VecDeque<Coords> = VecDeque::new();
+    let mut range_of_movement: HashSet<Coords> = HashSet::new();
+    // Each tile is walked once, at its shortest distance
+    let mut visited: HashSet<Coords> = HashSet::from([origin]);
+
+    queue.push_back(origin);
+    while curr_dist <= mov {
+        let mut next_queue: VecDeque<Coords> = VecDeque::new();
+
+        while let Some(center) = queue.pop_back() {
+            if !units.contains(&center) || center == origin {
+                range_of_movement.insert(center);
+            }
+
+            for neighbor in neighbors(center) {
+                if !blocked.in_wall(&neighbor) && visited.insert(neighbor) {
+                    next_queue.push_back(neighbor);
+                }
+            }
+        }
+
+        queue = next_queue;
+        curr_dist += 1;
+    }
+
+    range_of_movement
+}
+
+// This is gonna be a very dumb implementation. It's gonna check every box lol
+/// Tiles outside the movement range that can be attacked from somewhere in it
+pub fn attack_range(weapon_range: WeaponRange, movement_range: &HashSet<Coords>) -> HashSet<Coords> {
+    let (min_dist, max_dist) = match weapon_range {
+        WeaponRange::Melee(d) => (0, d),
+        WeaponRange::Ranged { min, max } => (min, max),
+    };
+
+    let mut range_of_attack: HashSet<Coords> = HashSet::new();
+    for coord in movement_range.iter() {
+        range_of_attack.extend(
+            attack_range_from_coord(*coord, min_dist, max_dist).into_iter()
+                .filter(|item| !movement_range.contains(item))
+        );
+    }
+
+    range_of_attack
+}
+
+pub fn attack_range_from_coord(origin: Coords, min_dist: u32, max_dist: u32) -> HashSet<Coords> {
+    let mut curr_dist = 1;
+    let mut queue: VecDeque<Coords> = VecDeque::new();
+    let mut attack_range: HashSet<Coords> = HashSet::new();
+
+    queue.push_back(origin);
+    while curr_dist <= max_dist {
+        let mut next_queue: VecDeque<Coords> = VecDeque::new();
+        while let Some(center) = queue.pop_back() {
+            for neighbor in neighbors(center) {
+                if !attack_range.contains(&neighbor) && neighbor != origin {
+                    next_queue.push_back(neighbor);
+                    if curr_dist >= min_dist {
+                        attack_range.insert(neighbor);
+                    }
+                }
+            }
+        }
+
+        queue = next_queue;
+        curr_dist += 1;
+    }
+
+    attack_range
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+struct PathState {
+    cost: u32,
+    coords: Coords
+}
+
+// Reversed so the BinaryHeap pops the cheapest state first
+impl Ord for PathState {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for PathState {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A* from start to target, at most max_dist steps long
+pub fn movement_path<W: Walls + ?Sized>(
+    target_coords: Coords,
+    start_coords: Coords,
+    walls: &W,
+    max_dist: u32,
+) -> Option<Vec<Coords>> {
+    let mut g_scores: HashMap<Coords, u32> = HashMap::new();
+    let mut came_from: HashMap<Coords, Coords> = HashMap::new();
+    let mut queue: BinaryHeap<PathState> = BinaryHeap::new();
+
+    g_scores.insert(start_coords, 0);
+    queue.push(PathState { cost: 0, coords: start_coords });
+
+    while let Some(PathState { coords: curr, .. }) = queue.pop() {
+        if curr == target_coords {
+            return Some(resolve_path(&came_from, curr));
+        }
+
+        let curr_g_score = g_scores[&curr];
+        if curr_g_score >= max_dist {
+            continue;
+        }
+
+        for next_coord in neighbors(curr) {
+            if walls.in_wall(&next_coord) {
+                continue;
+            }
+
+            // TODO: 1 is the weight of the edge. In our case this should change depending on
+            // the cost of the tile to move into
+            let next_g_score = curr_g_score + 1;
+            if next_g_score < *g_scores.get(&next_coord).unwrap_or(&u32::MAX) {
+                came_from.insert(next_coord, curr);
+                g_scores.insert(next_coord, next_g_score);
+                queue.push(PathState {
+                    cost: next_g_score + manhattan_dist(next_coord, target_coords),
+                    coords: next_coord
+                });
+            }
+        }
+    }
+    None
+}
+
+fn resolve_path(came_from: &HashMap<Coords, Coords>, target: Coords) -> Vec<Coords> {
+    let mut path = vec![target];
+    let mut curr = target;
+    while let Some(prev) = came_from.get(&curr) {
+        curr = *prev;
+        path.push(curr);
+    }
+
+    path.reverse();
+    path
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use std::collections::HashSet;
+    #[allow(unused_imports)]
+    use crate::{SimUnit, UnitStats, UnitType, Weapon};
+    #[allow(unused_imports)]
+    use super::{attack_range_from_coord, movement_path, movement_range, Coords, Grid};
+
+    #[test]
+    fn test_caculate_attack_range_one() {
+        let range = attack_range_from_coord(
+            Coords { x: 2, y: 2 },
+            0,
+            1
+        );
+
+        let test: HashSet<Coords> = HashSet::from_iter(vec![
+            Coords::new(1, 2),
+            Coords::new(2, 1),
+            Coords::new(3, 2),
+            Coords::new(2, 3),
+        ]);
+        assert_eq!(range, test);
+    }
+
+    #[test]
+    fn test_caculate_attack_range_two_min_one() {
+        let range = attack_range_from_coord(
+            Coords::new(2, 2),
+            2,
+            2
+        );
+
+        let test: HashSet<Coords> = HashSet::from_iter(vec![
+            Coords::new(0, 2),
+            Coords::new(2, 0),
+            Coords::new(4, 2),
+            Coords::new(2, 4),
+            Coords::new(3, 3),
+            Coords::new(1, 1),
+            Coords::new(1, 3),
+            Coords::new(3, 1),
+        ]);
+        assert_eq!(range, test);
+    }
+
+    #[test]
+    fn test_caculate_attack_range_two() {
+        let range = attack_range_from_coord(
+            Coords::new(2, 2),
+            0,
+            2
+        );
+
+        let test: HashSet<Coords> = HashSet::from_iter(vec![
+            Coords::new(1, 2),
+            Coords::new(2, 1),
+            Coords::new(3, 2),
+            Coords::new(2, 3),
+            Coords::new(0, 2),
+            Coords::new(2, 0),
+            Coords::new(4, 2),
+            Coords::new(2, 4),
+            Coords::new(3, 3),
+            Coords::new(1, 1),
+            Coords::new(1, 3),
+            Coords::new(3, 1),
+        ]);
+        assert_eq!(range, test);
+    }
+
+    #[test]
+    fn test_caculate_attack_range_two_min_two() {
+        let range = attack_range_from_coord(
+            Coords::new(2, 2),
+            2,
+            2
+        );
+
+        let test: HashSet<Coords> = HashSet::from_iter(vec![
+            Coords::new(0, 2),
+            Coords::new(2, 0),
+            Coords::new(4, 2),
+            Coords::new(2, 4),
+            Coords::new(3, 3),
+            Coords::new(1, 1),
+            Coords::new(1, 3),
+            Coords::new(3, 1),
+        ]);
+        assert_eq!(range, test);
+    }
+
+    #[test]
+    fn test_movement_path_goes_around_walls() {
+        let mut grid = Grid::new(5, 5);
+        grid.walls.extend([Coords::new(1, 0), Coords::new(1, 1), Coords::new(1, 2)]);
+
+        let path = movement_path(Coords::new(2, 0), Coords::new(0, 0), &grid, 10).unwrap();
+        assert_eq!(path.len(), 9);
+        assert_eq!(path.first(), Some(&Coords::new(0, 0)));
+        assert_eq!(path.last(), Some(&Coords::new(2, 0)));
+        assert!(movement_path(Coords::new(2, 0), Coords::new(0, 0), &grid, 7).is_none());
+    }
+
+    #[test]
+    fn test_hostile_units_block_movement() {
+        let unit = |x, unit_type| SimUnit::new(UnitStats { hp: 1, ..Default::default() }, Weapon::default(), Coords::new(x, 4), unit_type);
+        let units = [unit(4, UnitType::Enemy), unit(5, UnitType::Player), unit(3, UnitType::Enemy)];
+
+        let range = movement_range(Coords::new(4, 4), 2, UnitType::Enemy, &units[..], &Grid::new(9, 9));
+        // Allies can be walked through, the other side can't
+        assert!(range.contains(&Coords::new(2, 4)));
+        assert!(!range.contains(&Coords::new(5, 4)));
+        assert!(!range.contains(&Coords::new(6, 4)));
+    }
+}
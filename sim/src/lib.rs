@@ -0,0 +1,21 @@
+//! The rules of a battle as plain functions over plain data. Nothing here needs an
+//! App or a World, so balance tools can run thousands of battles headless while the
+//! game's systems call the same code.
+
+mod ai;
+mod balance;
+mod battle;
+mod combat;
+mod grid;
+mod rules;
+mod units;
+mod weapon;
+
+pub use ai::*;
+pub use balance::*;
+pub use battle::*;
+pub use combat::*;
+pub use grid::*;
+pub use rules::Ruleset;
+pub use units::UnitStats;
+pub use weapon::*;
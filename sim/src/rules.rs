@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+/// The numbers combat runs on, read from a rules.ron file. Missing fields fall back to
+/// the classic rules.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Ruleset {
+    /// Hit added for the weapon triangle advantage, and taken away at a disadvantage
+    pub weapon_accuracy_bonus: u32,
+    pub weapon_damage_bonus: u32,
+    pub weapon_crit_bonus: u32,
+    /// A unit strikes twice when it is at least this much faster
+    pub double_attack_speed: u32,
+    pub crit_multiplier: u32,
+    /// Hit and crit per point of skill
+    pub accuracy_per_skill: u32,
+    /// Attack speed and dodge per point of speed
+    pub attack_speed_per_spd: u32,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self {
+            weapon_accuracy_bonus: 15,
+            weapon_damage_bonus: 1,
+            weapon_crit_bonus: 0,
+            double_attack_speed: 4,
+            crit_multiplier: 3,
+            accuracy_per_skill: 2,
+            attack_speed_per_spd: 2,
+        }
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::Ruleset;
+
+    #[test]
+    fn test_presets_parse() {
+        let classic = ron::de::from_bytes::<Ruleset>(include_bytes!("../../assets/rouge/classic.rules.ron")).unwrap();
+        assert_eq!(classic, Ruleset::default());
+
+        let hardcore = ron::de::from_bytes::<Ruleset>(include_bytes!("../../assets/rouge/hardcore.rules.ron")).unwrap();
+        assert!(hardcore.crit_multiplier > classic.crit_multiplier);
+    }
+
+    #[test]
+    fn test_missing_rules_are_classic() {
+        let rules: Ruleset = ron::de::from_str("(crit_multiplier: 2)").unwrap();
+        assert_eq!(rules.crit_multiplier, 2);
+        assert_eq!(rules.double_attack_speed, Ruleset::default().double_attack_speed);
+    }
+}
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+use super::Ruleset;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UnitStats {
+    pub name: String,
+    pub hp: u32,
+    pub def: u32,
+    pub atk: u32,
+    pub spd: u32,
+    pub skill: u32,
+    pub mov: u32,
+    #[serde(default)]
+    pub xp: u32,
+}
+
+impl Default for UnitStats {
+    fn default() -> Self {
+        UnitStats {
+            name: "".to_string(),
+            hp: 10,
+            def: 0,
+            atk: 3,
+            spd: 2,
+            skill: 3,
+            mov: 8,
+            xp: 0,
+        }
+    }
+}
+
+impl UnitStats {
+    pub fn enemy() -> Self {
+        UnitStats {
+            name: "Enemy".to_string(),
+            hp: 3,
+            def: 0,
+            atk: 1,
+            spd: 2,
+            skill: 3,
+            mov: 1,
+            xp: 0,
+        }
+    }
+
+    /// Tougher enemies found on elite battle nodes
+    pub fn elite() -> Self {
+        UnitStats {
+            name: "Elite".to_string(),
+            hp: 6,
+            def: 1,
+            atk: 2,
+            spd: 3,
+            skill: 4,
+            mov: 1,
+            xp: 0,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn player() -> Self {
+        UnitStats {
+            name: "Player".to_string(),
+            hp: 10,
+            def: 1,
+            atk: 3,
+            spd: 3,
+            skill: 3,
+            mov: 8,
+            xp: 0,
+        }
+    }
+
+    pub fn accuracy(&self, rules: &Ruleset) -> u32 {
+        rules.accuracy_per_skill * self.skill
+    }
+
+    pub fn attack_speed(&self, rules: &Ruleset) -> u32 {
+        rules.attack_speed_per_spd * self.spd
+    }
+
+    pub fn crit(&self, rules: &Ruleset) -> u32 {
+        self.accuracy(rules)
+    }
+
+    pub fn dodge(&self, rules: &Ruleset) -> u32 {
+        self.attack_speed(rules)
+    }
+
+    /// Carrying an ally halves speed and skill in combat
+    pub fn with_carry_penalty(&self) -> UnitStats {
+        UnitStats {
+            spd: self.spd / 2,
+            skill: self.skill / 2,
+            ..self.clone()
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use rand::distr::{Distribution, StandardUniform};
+use rand::seq::IndexedRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Ordered from least to most rare
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Deserialize, Serialize)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Legendary
+}
+
+impl Rarity {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Rarity::Common => "common",
+            Rarity::Uncommon => "uncommon",
+            Rarity::Rare => "rare",
+            Rarity::Legendary => "legendary",
+        }
+    }
+
+    /// Gold a weapon of this rarity costs in the shop
+    pub fn price(&self) -> u32 {
+        match self {
+            Rarity::Common => 100,
+            Rarity::Uncommon => 250,
+            Rarity::Rare => 600,
+            Rarity::Legendary => 1500,
+        }
+    }
+}
+
+impl Distribution<Rarity> for StandardUniform {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Rarity {
+        let x: u32 = rng.random_range(..=100);
+        match x {
+            0..=4 => Rarity::Legendary,
+            5..=19 => Rarity::Rare,
+            20..=49 => Rarity::Uncommon,
+            _ => Rarity::Common,
+        } 
+    }
+}
+
+#[derive(PartialEq, Clone, Debug, Deserialize, Serialize)]
+pub enum WeaponType {
+    Lance,
+    Sword,
+    Axe,
+    Bow,
+    Light,
+    Dark,
+    Natural
+}
+
+// Situations when an effect may take place
+#[derive(PartialEq, Clone, Debug, Deserialize, Serialize)]
+pub enum WeaponEffect {
+    OnAttack,
+    AfterAttack,
+    OnMove,
+    Passive
+}
+
+impl WeaponEffect {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            WeaponEffect::OnAttack => "on attack",
+            WeaponEffect::AfterAttack => "after attack",
+            WeaponEffect::OnMove => "on move",
+            WeaponEffect::Passive => "passive",
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug, Deserialize, Serialize, Copy)]
+pub enum WeaponRange {
+    // Makes contact
+    Melee(u32),
+    // Makes no contact. Min and Max are inclusive.
+    // Range(min: 1, max: 2) is equivalent to Melee(2)
+    Ranged{min: u32, max: u32},
+}
+
+impl WeaponRange {
+    /// Tiles the weapon reaches, like "1-2"
+    pub fn get_name(&self) -> String {
+        let (min, max) = match *self {
+            WeaponRange::Melee(x) => (1, x),
+            WeaponRange::Ranged { min, max } => (min, max),
+        };
+        if min == max { min.to_string() } else { format!("{}-{}", min, max) }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug, Deserialize, Copy)]
+pub enum WeaponEffectiveness {
+    Strong,
+    Weak,
+    Neutral
+}
+
+#[derive(PartialEq, Clone, Debug, Deserialize, Serialize)]
+pub struct Weapon {
+    pub attack: u32,
+    pub hit: u32,
+    pub weight: u32,
+    pub crit: u32,
+    pub range: WeaponRange,
+    pub rarity: Rarity,
+    pub weapon_type: WeaponType,
+    pub weapon_effect: Option<WeaponEffect>,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Weapon {
+            attack: 0,
+            hit: 0,
+            weight: 0,
+            crit: 0,
+            range: WeaponRange::Melee(1),
+            rarity: Rarity::Common,
+            weapon_type: WeaponType::Sword,
+            weapon_effect: None
+        }
+    }
+}
+
+impl Weapon {
+    pub fn get_effectivness(&self, opposing: &Weapon) -> WeaponEffectiveness {
+        match (
+            self.weapon_type.is_weak(&opposing.weapon_type),
+            self.weapon_type.is_strong(&opposing.weapon_type)
+        ) {
+            (false, false) => WeaponEffectiveness::Neutral,
+            (true, false) => WeaponEffectiveness::Weak,
+            (false, true) => WeaponEffectiveness::Strong,
+            (true, true) => unreachable!()
+        }
+    }
+
+    pub fn within_range(&self, dist: u32) -> bool {
+        match self.range {
+            WeaponRange::Melee(x) => {
+                x >= dist
+            },
+            WeaponRange::Ranged { min, max } => {
+                dist >= min && dist <= max
+            },
+        }
+    }
+
+    pub fn price(&self) -> u32 {
+        self.rarity.price()
+    }
+
+    // Weapons sell back for half of what they cost
+    pub fn sell_price(&self) -> u32 {
+        self.price() / 2
+    }
+
+    pub fn get_name(&self) -> String {
+        let rarity = self.rarity.get_name();
+
+        match self.weapon_type {
+            WeaponType::Sword => format!("{}-sword", rarity),
+            WeaponType::Lance => format!("{}-lance", rarity),
+            WeaponType::Axe => format!("{}-axe", rarity),
+            WeaponType::Bow => format!("{}-bow", rarity),
+            WeaponType::Natural => format!("{}-natural", rarity),
+            WeaponType::Light => format!("{}-light", rarity),
+            WeaponType::Dark => format!("{}-dark", rarity),
+        }
+    }
+}
+
+impl WeaponType {
+    pub const ALL: [WeaponType; 7] = [
+        WeaponType::Lance,
+        WeaponType::Sword,
+        WeaponType::Axe,
+        WeaponType::Bow,
+        WeaponType::Light,
+        WeaponType::Dark,
+        WeaponType::Natural,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            WeaponType::Lance => "lance",
+            WeaponType::Sword => "sword",
+            WeaponType::Axe => "axe",
+            WeaponType::Bow => "bow",
+            WeaponType::Light => "light",
+            WeaponType::Dark => "dark",
+            WeaponType::Natural => "natural",
+        }
+    }
+
+    /// Types this one has the advantage over in the weapon triangle
+    pub fn strong_against(&self) -> Vec<WeaponType> {
+        Self::ALL.into_iter().filter(|other| self.is_strong(other)).collect()
+    }
+
+    /// Types that have the advantage over this one
+    pub fn weak_against(&self) -> Vec<WeaponType> {
+        Self::ALL.into_iter().filter(|other| self.is_weak(other)).collect()
+    }
+
+    fn is_weak(&self, weapon_type: &WeaponType) -> bool {
+        match self {
+            WeaponType::Lance => *weapon_type == WeaponType::Axe,
+            WeaponType::Sword => *weapon_type == WeaponType::Lance,
+            WeaponType::Axe => *weapon_type == WeaponType::Sword,
+            WeaponType::Light => *weapon_type == WeaponType::Natural,
+            WeaponType::Dark => *weapon_type == WeaponType::Light,
+            WeaponType::Natural => *weapon_type == WeaponType::Dark,
+            WeaponType::Bow => false,
+        }
+    }
+
+    fn is_strong(&self, weapon_type: &WeaponType) -> bool {
+        match self {
+            WeaponType::Lance => *weapon_type == WeaponType::Sword,
+            WeaponType::Sword => *weapon_type == WeaponType::Axe,
+            WeaponType::Axe => *weapon_type == WeaponType::Lance,
+            WeaponType::Light => *weapon_type == WeaponType::Dark,
+            WeaponType::Dark => *weapon_type == WeaponType::Natural,
+            WeaponType::Natural => *weapon_type == WeaponType::Light,
+            WeaponType::Bow => false,
+        }
+    }
+}
+
+/// Every weapon that can drop, read from a weapons.ron file
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct WeaponCollection {
+    pub weapons: Vec<Weapon>,
+}
+
+impl WeaponCollection {
+    pub fn get_random_weapon(&self) -> Weapon {
+        let rarity: Rarity = rand::random();
+        self.get_random_weapon_by_rarity(rarity)
+    }
+
+    /// Rolls a rarity like get_random_weapon, bumping anything below min up to min
+    pub fn get_random_weapon_at_least(&self, min: &Rarity) -> Weapon {
+        let rarity: Rarity = rand::random();
+        self.get_random_weapon_by_rarity(rarity.max(min.clone()))
+    }
+
+    /// Falls back to any weapon when none has the rarity, and the default weapon when
+    /// the collection is empty
+    pub fn get_random_weapon_by_rarity(&self, rarity: Rarity) -> Weapon {
+        let weapons: Vec<&Weapon> = self.weapons.iter().filter(|w| w.rarity == rarity).collect();
+        let pool = if weapons.is_empty() { self.weapons.iter().collect() } else { weapons };
+        pool.choose(&mut rand::rng()).map(|w| (*w).clone()).unwrap_or_default()
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::{Rarity, Weapon, WeaponCollection, WeaponEffectiveness, WeaponRange, WeaponType};
+
+    #[allow(dead_code)]
+    fn get_default_weapon_of_type(weapon_type: WeaponType) -> Weapon {
+        Weapon {
+            weapon_type,
+            ..Default::default()
+        }
+    }
+
+    macro_rules! effectiveness_test {
+        ($($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (lhs, rhs, expected) = $value;
+                let weapon_lhs = get_default_weapon_of_type(lhs);
+                let weapon_rhs = get_default_weapon_of_type(rhs);
+                assert_eq!(expected, weapon_lhs.get_effectivness(&weapon_rhs));
+            }
+        )*
+        }
+    }
+    effectiveness_test! {
+        test_sw_sw_neutral: (WeaponType::Sword, WeaponType::Sword, WeaponEffectiveness::Neutral),
+        test_sw_lance_neutral: (WeaponType::Sword, WeaponType::Lance, WeaponEffectiveness::Weak),
+        test_light_dark_neutral: (WeaponType::Light, WeaponType::Dark, WeaponEffectiveness::Strong),
+        test_nat_light_neutral: (WeaponType::Natural, WeaponType::Light, WeaponEffectiveness::Strong),
+        test_bow_axe_neutral: (WeaponType::Bow, WeaponType::Axe, WeaponEffectiveness::Neutral),
+        test_axe_dark_neutral: (WeaponType::Axe, WeaponType::Dark, WeaponEffectiveness::Neutral),
+        test_dark_light_neutral: (WeaponType::Dark, WeaponType::Light, WeaponEffectiveness::Weak),
+        test_axe_lance_neutral: (WeaponType::Axe, WeaponType::Lance, WeaponEffectiveness::Strong),
+        test_lance_axe_neutral: (WeaponType::Lance, WeaponType::Axe, WeaponEffectiveness::Weak),
+    }
+
+    #[test]
+    fn test_range_name() {
+        assert_eq!(WeaponRange::Melee(1).get_name(), "1");
+        assert_eq!(WeaponRange::Melee(2).get_name(), "1-2");
+        assert_eq!(WeaponRange::Ranged { min: 2, max: 3 }.get_name(), "2-3");
+    }
+
+    #[test]
+    fn test_catalogue_has_every_rarity() {
+        let catalogue = ron::de::from_bytes::<WeaponCollection>(include_bytes!("../../assets/rouge/catalogue.weapons.ron")).unwrap();
+
+        for rarity in [Rarity::Common, Rarity::Uncommon, Rarity::Rare, Rarity::Legendary] {
+            assert!(catalogue.weapons.iter().any(|w| w.rarity == rarity));
+            assert_eq!(catalogue.get_random_weapon_by_rarity(rarity.clone()).rarity, rarity);
+        }
+    }
+
+    #[test]
+    fn test_empty_collection_rolls_default() {
+        let weapon = WeaponCollection::default().get_random_weapon_at_least(&Rarity::Rare);
+        assert_eq!(weapon, Weapon::default());
+    }
+}
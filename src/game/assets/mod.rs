@@ -2,7 +2,8 @@ use bevy::prelude::*;
 use bevy::log::info;
 use bevy_asset::{io::Reader, Asset, AssetLoader, LoadContext};
 use crate::game::units::UnitStats;
-use crate::game::weapon::Weapon;
+use crate::game::support::SupportPair;
+use crate::game::rules::RulesetAsset;
 use std::marker::PhantomData;
 use ron::de::from_bytes;
 use serde::{Deserialize, Serialize};
@@ -11,13 +12,15 @@ use thiserror::Error;
 
 #[derive(Asset, Debug, TypePath, Deserialize, Serialize)]
 pub struct UnitCollection {
-    pub units: Vec<UnitStats>
+    pub units: Vec<UnitStats>,
+    #[serde(default)]
+    pub supports: Vec<SupportPair>,
 }
 
+/// A weapons.ron file as loaded by the asset server
 #[derive(Asset, Debug, TypePath, Deserialize)]
-pub struct WeaponCollection {
-    weapons: Vec<Weapon>
-}
+#[serde(transparent)]
+pub struct WeaponCollection(pub sim::WeaponCollection);
 
 // Credit: Used a lot of code from bevy_common_assets. Thanks [https://github.com/NiklasEi/bevy_common_assets.git]
 // Interesting, for a struct with a generic param, you need to use it right away
@@ -36,6 +39,7 @@ impl Plugin for GameAssetPlugin
         app
             .init_asset::<WeaponCollection>()
             .init_asset::<UnitCollection>()
+            .init_asset::<RulesetAsset>()
             .register_asset_loader(GameAssetLoader::<UnitCollection> {
                 extensions: vec!["units.ron"],
                 _marker: PhantomData
@@ -43,6 +47,10 @@ impl Plugin for GameAssetPlugin
             .register_asset_loader(GameAssetLoader::<WeaponCollection> {
                 extensions: vec!["weapons.ron"],
                 _marker: PhantomData
+            })
+            .register_asset_loader(GameAssetLoader::<RulesetAsset> {
+                extensions: vec!["rules.ron"],
+                _marker: PhantomData
             });
     }
 }
@@ -84,3 +92,28 @@ where
         &self.extensions
     }
 }
+
+mod test {
+    #[allow(unused_imports)]
+    use super::{UnitCollection, WeaponCollection};
+
+    #[test]
+    fn test_available_units_parse() {
+        let bytes = include_bytes!("../../../assets/rouge/available.units.ron");
+        let collection = ron::de::from_bytes::<UnitCollection>(bytes).unwrap();
+
+        assert!(!collection.units.is_empty());
+        for pair in collection.supports.iter() {
+            assert!(collection.units.iter().any(|u| u.name == pair.units.0));
+            assert!(collection.units.iter().any(|u| u.name == pair.units.1));
+        }
+    }
+
+    #[test]
+    fn test_weapon_catalogue_parses() {
+        let bytes = include_bytes!("../../../assets/rouge/catalogue.weapons.ron");
+        let collection = ron::de::from_bytes::<WeaponCollection>(bytes).unwrap();
+
+        assert!(!collection.0.weapons.is_empty());
+    }
+}
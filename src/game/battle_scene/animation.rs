@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+
+use sim::Damage;
+use crate::game::{GameState, OnLevelScreen, Settings};
+
+// Seconds each part of a fight takes at animation speed 1
+const ATTACK_SECS: f32 = 0.5;
+const CRIT_SECS: f32 = 0.8;
+const FADE_SECS: f32 = 0.6;
+const FLASH_SECS: f32 = 0.2;
+const FLOAT_SECS: f32 = 0.9;
+// In pixels
+const LUNGE_DIST: f32 = 6.0;
+const FLOAT_START: f32 = 10.0;
+const FLOAT_HEIGHT: f32 = 14.0;
+
+const HIT_COLOR: Color = Color::srgb(1.0, 0.45, 0.45);
+const CRIT_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+const MISS_COLOR: Color = Color::srgb(0.7, 0.7, 0.9);
+
+/// Moves a unit toward its target and back
+#[derive(Component)]
+pub struct Lunge {
+    origin: Vec3,
+    offset: Vec3,
+    timer: Timer,
+}
+
+/// Tints a unit's sprite until the timer runs out
+#[derive(Component)]
+pub struct Flash {
+    color: Color,
+    timer: Timer,
+}
+
+/// Damage text that drifts up and fades
+#[derive(Component)]
+pub struct FloatingText {
+    origin: Vec3,
+    timer: Timer,
+}
+
+/// A defeated unit on its way out. Despawned when the timer finishes.
+#[derive(Component)]
+pub struct Fading(Timer);
+
+/// How long a step of the fight takes with the player's settings
+pub fn step_secs(base: f32, settings: &Settings) -> f32 {
+    if settings.skip_combat_animations {
+        0.0
+    } else {
+        base / settings.animation_speed.max(0.01)
+    }
+}
+
+fn timer(base: f32, settings: &Settings) -> Timer {
+    Timer::from_seconds(step_secs(base, settings), TimerMode::Once)
+}
+
+fn fraction(timer: &Timer) -> f32 {
+    if timer.duration().is_zero() { 1.0 } else { timer.fraction() }
+}
+
+pub fn animation_plugin(app: &mut App) {
+    app
+        .add_systems(Update, (
+            play_lunges,
+            play_flashes,
+            float_damage_text,
+            fade_out_units,
+        ).run_if(in_state(GameState::InBattle)));
+}
+
+/// Plays one strike and returns how long the fight should wait before the next one
+pub fn play_strike(
+    commands: &mut Commands,
+    settings: &Settings,
+    (actor, actor_pos): (Entity, Vec3),
+    (target, target_pos): (Entity, Vec3),
+    damage: &Damage,
+) -> Timer {
+    let (text, color, base) = match damage {
+        Damage::Hit(x) => (x.to_string(), HIT_COLOR, ATTACK_SECS),
+        Damage::Crit(x) => (format!("CRIT {}", x), CRIT_COLOR, CRIT_SECS),
+        Damage::Miss => ("MISS".to_string(), MISS_COLOR, ATTACK_SECS),
+    };
+    if settings.skip_combat_animations {
+        return timer(base, settings);
+    }
+
+    let offset = (target_pos - actor_pos).truncate().normalize_or_zero() * LUNGE_DIST;
+    commands.entity(actor).insert(Lunge {
+        origin: actor_pos,
+        offset: offset.extend(0.),
+        timer: timer(ATTACK_SECS / 2.0, settings),
+    });
+
+    if !matches!(damage, Damage::Miss) {
+        commands.entity(target).insert(Flash { color, timer: timer(FLASH_SECS, settings) });
+    }
+
+    let origin = target_pos + Vec3::new(0., FLOAT_START, 20.);
+    commands.spawn((
+        Text2d::new(text),
+        TextFont { font_size: 8.0, ..default() },
+        TextColor(color),
+        Transform::from_translation(origin),
+        FloatingText { origin, timer: timer(FLOAT_SECS, settings) },
+        OnLevelScreen,
+    ));
+
+    timer(base, settings)
+}
+
+/// Starts fading a defeated unit and returns how long it takes
+pub fn fade_out(commands: &mut Commands, settings: &Settings, unit: Entity) -> Timer {
+    commands.entity(unit).insert(Fading(timer(FADE_SECS, settings)));
+    timer(FADE_SECS, settings)
+}
+
+fn play_lunges(
+    mut commands: Commands,
+    mut lunge_q: Query<(Entity, &mut Transform, &mut Lunge)>,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut lunge) in lunge_q.iter_mut() {
+        lunge.timer.tick(time.delta());
+        if lunge.timer.finished() {
+            transform.translation = lunge.origin;
+            commands.entity(entity).remove::<Lunge>();
+        } else {
+            // Out and back in one arc
+            let t = (fraction(&lunge.timer) * std::f32::consts::PI).sin();
+            transform.translation = lunge.origin + lunge.offset * t;
+        }
+    }
+}
+
+fn play_flashes(
+    mut commands: Commands,
+    mut flash_q: Query<(Entity, &mut Sprite, &mut Flash)>,
+    time: Res<Time>,
+) {
+    for (entity, mut sprite, mut flash) in flash_q.iter_mut() {
+        flash.timer.tick(time.delta());
+        if flash.timer.finished() {
+            sprite.color = Color::WHITE;
+            commands.entity(entity).remove::<Flash>();
+        } else {
+            sprite.color = flash.color;
+        }
+    }
+}
+
+fn float_damage_text(
+    mut commands: Commands,
+    mut text_q: Query<(Entity, &mut Transform, &mut TextColor, &mut FloatingText)>,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut color, mut text) in text_q.iter_mut() {
+        text.timer.tick(time.delta());
+        if text.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let t = fraction(&text.timer);
+        transform.translation = text.origin + Vec3::Y * FLOAT_HEIGHT * t;
+        color.0.set_alpha(1.0 - t);
+    }
+}
+
+fn fade_out_units(
+    mut commands: Commands,
+    mut fading_q: Query<(Entity, &mut Sprite, &mut Fading)>,
+    time: Res<Time>,
+) {
+    for (entity, mut sprite, mut fading) in fading_q.iter_mut() {
+        fading.0.tick(time.delta());
+        if fading.0.finished() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            sprite.color.set_alpha(1.0 - fraction(&fading.0));
+        }
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use crate::game::Settings;
+    #[allow(unused_imports)]
+    use super::step_secs;
+
+    #[test]
+    fn test_step_secs_follows_settings() {
+        let mut settings = Settings { animation_speed: 2.0, ..Default::default() };
+        assert_eq!(step_secs(0.5, &settings), 0.25);
+        settings.skip_combat_animations = true;
+        assert_eq!(step_secs(0.5, &settings), 0.0);
+    }
+}
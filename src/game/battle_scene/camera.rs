@@ -1,61 +1,201 @@
-use bevy::{input::mouse::MouseWheel, prelude::*};
+use bevy::{input::mouse::{AccumulatedMouseMotion, MouseWheel}, prelude::*};
 use bevy::window::PrimaryWindow;
 
-const ZOOM_SPEED: f32 = 0.02; 
+use super::fight::Attacker;
+use super::{LevelWalls, Selected};
+use crate::game::{Action, ActionState, Enemy, Settings, GRID_SIZE};
+
+// The zoom each battle starts at. Pan speeds are tuned for it.
+const DEFAULT_ZOOM: f32 = 0.5;
+const MIN_ZOOM: f32 = 0.2;
+const MAX_ZOOM: f32 = 1.5;
+// How quickly the camera closes on its focus, per second
+const FOCUS_RATE: f32 = 6.0;
+// Close enough to stop following, in pixels
+const FOCUS_DIST: f32 = 0.5;
+
+/// Where the camera is easing towards. Any manual pan lets go of it.
+#[derive(Default, Resource, Debug)]
+pub struct CameraFocus(pub Option<Vec2>);
+
+pub fn clamp_zoom(scale: f32) -> f32 {
+    scale.clamp(MIN_ZOOM, MAX_ZOOM)
+}
+
+/// How far a pan moves at this zoom, so the map moves the same on screen zoomed in or out
+pub fn pan_step(speed: f32, scale: f32) -> f32 {
+    speed * scale / DEFAULT_ZOOM
+}
+
+/// Keeps the center of the camera over the level. Levels start at the origin.
+pub fn clamp_to_level(position: Vec2, walls: &LevelWalls) -> Vec2 {
+    let size = Vec2::new(walls.level_width as f32, walls.level_height as f32) * GRID_SIZE as f32;
+    position.clamp(Vec2::ZERO, size.max(Vec2::ZERO))
+}
 
 pub fn zoom_in_scroll_wheel(
     // TODO: Does this even need to be mut?
     mut scroll_evr: EventReader<MouseWheel>,
-    mut q_proj: Query<&mut OrthographicProjection, With<Camera>>
+    mut q_proj: Query<&mut OrthographicProjection, With<Camera>>,
+    actions: Res<ActionState>,
+    settings: Res<Settings>,
 ) {
     use bevy::input::mouse::MouseScrollUnit;
     let mut proj = q_proj.single_mut();
 
+    // Held zoom actions zoom every frame, the wheel zooms per notch
+    if actions.pressed(Action::ZoomIn) {
+        proj.scale -= settings.zoom_speed;
+    }
+    if actions.pressed(Action::ZoomOut) {
+        proj.scale += settings.zoom_speed;
+    }
+
     for ev in scroll_evr.read() {
         match ev.unit {
             MouseScrollUnit::Line => {
                 if ev.y > 0. {
-                    proj.scale -= ZOOM_SPEED;
+                    proj.scale -= settings.zoom_speed;
                 } else if ev.y < 0. {
-                    proj.scale += ZOOM_SPEED;
+                    proj.scale += settings.zoom_speed;
                 }
             },
             MouseScrollUnit::Pixel => {
                 // TODO: Figure out how to test this since
                 // my mouse is line
                 if ev.y < 0. {
-                    proj.scale -= ZOOM_SPEED;
+                    proj.scale -= settings.zoom_speed;
                 } else if ev.y > 0. {
-                    proj.scale += ZOOM_SPEED;
+                    proj.scale += settings.zoom_speed;
                 }
             }
         }
     }
+
+    proj.scale = clamp_zoom(proj.scale);
+}
+
+/// Holding PanCamera drags the map with the mouse
+pub fn drag_camera(
+    actions: Res<ActionState>,
+    motion: Res<AccumulatedMouseMotion>,
+    mut q_cam: Query<(&mut Transform, &OrthographicProjection), With<Camera>>,
+    mut focus: ResMut<CameraFocus>,
+) {
+    if !actions.pressed(Action::PanCamera) || motion.delta == Vec2::ZERO { return }
+
+    let (mut transform, proj) = q_cam.single_mut();
+    // Screen y points down, world y points up
+    transform.translation.x -= motion.delta.x * proj.scale;
+    transform.translation.y += motion.delta.y * proj.scale;
+    focus.0 = None;
+}
+
+/// The pan actions move the camera like edge scrolling does
+pub fn pan_camera_keys(
+    actions: Res<ActionState>,
+    mut q_cam: Query<(&mut Transform, &OrthographicProjection), With<Camera>>,
+    mut focus: ResMut<CameraFocus>,
+    settings: Res<Settings>,
+) {
+    let mut dir = Vec2::ZERO;
+    if actions.pressed(Action::PanUp) { dir.y += 1. }
+    if actions.pressed(Action::PanDown) { dir.y -= 1. }
+    if actions.pressed(Action::PanRight) { dir.x += 1. }
+    if actions.pressed(Action::PanLeft) { dir.x -= 1. }
+    if dir == Vec2::ZERO { return }
+
+    let (mut transform, proj) = q_cam.single_mut();
+    let step = dir.normalize() * pan_step(settings.edge_scroll_speed, proj.scale);
+    transform.translation += step.extend(0.);
+    focus.0 = None;
 }
 
 // TODO: scroll harder the closer you are to the edge
 const EDGE_SCROLL_DIST: f32 = 40.;
-// TODO: Scale speed based on zoom in
-const EDGE_SCROLL_SPEED: f32 = 5.;
 
 pub fn move_screen_rts(
     q_window: Query<&Window, With<PrimaryWindow>>,
-    mut q_cam_transform: Query<&mut Transform, With<Camera>>
+    mut q_cam: Query<(&mut Transform, &OrthographicProjection), With<Camera>>,
+    mut focus: ResMut<CameraFocus>,
+    settings: Res<Settings>,
 ) {
     let window = q_window.single();
+    // The cursor sits wherever it left the window, which shouldn't keep scrolling
+    if !window.focused { return }
+
     let width: f32 = window.width();
     let height: f32 = window.height();
-    let mut transform = q_cam_transform.single_mut();
+    let (mut transform, proj) = q_cam.single_mut();
 
     if let Some(position) = window.cursor_position() {
         if let Some(dir) = get_scroll_direction(height, width, position) {
-            // TODO: Check if there's a better way to move the screen
-            transform.translation.x += dir.x * EDGE_SCROLL_SPEED;
-            transform.translation.y += dir.y * EDGE_SCROLL_SPEED;
+            let step = pan_step(settings.edge_scroll_speed, proj.scale);
+            transform.translation.x += dir.x * step;
+            transform.translation.y += dir.y * step;
+            focus.0 = None;
         }
     }
 }
 
+/// Eases towards a newly selected unit, or an enemy starting an attack
+pub fn focus_on_acting_unit(
+    mut focus: ResMut<CameraFocus>,
+    selected_q: Query<&Transform, Added<Selected>>,
+    attacker_q: Query<&Transform, (Added<Attacker>, With<Enemy>)>,
+) {
+    if let Some(transform) = selected_q.iter().chain(attacker_q.iter()).last() {
+        focus.0 = Some(transform.translation.xy());
+    }
+}
+
+pub fn follow_camera_focus(
+    mut focus: ResMut<CameraFocus>,
+    mut q_cam: Query<&mut Transform, With<Camera>>,
+    walls: Res<LevelWalls>,
+    time: Res<Time>,
+) {
+    let Some(target) = focus.0 else { return };
+    let mut transform = q_cam.single_mut();
+    // The camera can't go past the level, so aim for where it will end up
+    let target = clamp_to_level(target, &walls);
+
+    let position = transform.translation.xy();
+    let t = (FOCUS_RATE * time.delta_secs()).min(1.0);
+    let next = position.lerp(target, t);
+    if next.distance(target) < FOCUS_DIST {
+        transform.translation = target.extend(transform.translation.z);
+        focus.0 = None;
+    } else {
+        transform.translation = next.extend(transform.translation.z);
+    }
+}
+
+/// Runs after every other camera system so nothing can leave the level
+pub fn clamp_camera(
+    mut q_cam: Query<&mut Transform, With<Camera>>,
+    walls: Res<LevelWalls>,
+) {
+    let mut transform = q_cam.single_mut();
+    let clamped = clamp_to_level(transform.translation.xy(), &walls);
+    if clamped != transform.translation.xy() {
+        transform.translation = clamped.extend(transform.translation.z);
+    }
+}
+
+/// Starts each battle zoomed in over the middle of the level
+pub fn center_camera(
+    mut q: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+    mut focus: ResMut<CameraFocus>,
+    walls: Res<LevelWalls>,
+) {
+    let (mut transform, mut proj) = q.single_mut();
+    let center = Vec2::new(walls.level_width as f32, walls.level_height as f32) * GRID_SIZE as f32 / 2.0;
+    transform.translation = center.extend(transform.translation.z);
+    proj.scale = DEFAULT_ZOOM;
+    focus.0 = None;
+}
+
 // Returns a normalized vector
 // return an Option to indicate it's good?
 pub fn get_scroll_direction(h: f32, w: f32, mouse_pos: Vec2) -> Option<Vec2> {
@@ -83,3 +223,28 @@ pub fn get_scroll_direction(h: f32, w: f32, mouse_pos: Vec2) -> Option<Vec2> {
     }
 }
 
+mod test {
+    #[allow(unused_imports)]
+    use bevy::prelude::*;
+    #[allow(unused_imports)]
+    use crate::game::battle_scene::LevelWalls;
+    #[allow(unused_imports)]
+    use super::{clamp_to_level, clamp_zoom, pan_step, DEFAULT_ZOOM, MAX_ZOOM, MIN_ZOOM};
+
+    #[test]
+    fn test_clamp_to_level() {
+        // 10 wide, 5 tall in tiles
+        let walls = LevelWalls::new(5, 10, None);
+        assert_eq!(clamp_to_level(Vec2::new(-20., 30.), &walls), Vec2::new(0., 30.));
+        assert_eq!(clamp_to_level(Vec2::new(500., 500.), &walls), Vec2::new(160., 80.));
+        assert_eq!(clamp_to_level(Vec2::new(5., 5.), &LevelWalls::default()), Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_zoom_and_pan_speed() {
+        assert_eq!(clamp_zoom(-1.0), MIN_ZOOM);
+        assert_eq!(clamp_zoom(10.0), MAX_ZOOM);
+        assert_eq!(pan_step(5.0, DEFAULT_ZOOM), 5.0);
+        assert_eq!(pan_step(5.0, DEFAULT_ZOOM * 2.0), 10.0);
+    }
+}
@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{prelude::*, utils::grid_coords_to_translation, utils::translation_to_grid_coords};
+
+use super::map::UnitsOnMap;
+use super::movement::end_unit_move;
+use crate::util::manhattan_dist;
+use super::{BattleState, LevelWalls, MouseGridCoords, Selected, UnitType};
+use crate::game::units::{Carried, Carrying, Teams};
+use crate::game::{Action, ActionState, Player, GRID_SIZE_VEC};
+
+/// Rescue on an adjacent ally picks them up. Rescue on an adjacent free tile while
+/// carrying drops them there. Both end the unit's turn.
+#[allow(clippy::too_many_arguments)]
+pub fn rescue_or_drop(
+    mut commands: Commands,
+    mut state: ResMut<NextState<BattleState>>,
+    mut units_on_map: ResMut<UnitsOnMap>,
+    mut player_team_q: Query<&mut Teams>,
+    walls: Res<LevelWalls>,
+    actions: Res<ActionState>,
+    mouse_coords: Res<MouseGridCoords>,
+    selected: Single<(Entity, &Transform, &mut GridCoords, Option<&Carrying>), With<Selected>>,
+    mut allies: Query<
+        (&mut Transform, &mut GridCoords, &mut Visibility, Has<Carrying>),
+        (With<Player>, Without<Selected>)
+    >,
+) {
+    if !actions.just_pressed(Action::Rescue) { return }
+
+    let (entity, transform, mut coords, carrying) = selected.into_inner();
+    let dest_coords = translation_to_grid_coords(transform.translation.xy(), GRID_SIZE_VEC);
+    let target = mouse_coords.0;
+    if manhattan_dist(dest_coords, target) != 1 { return }
+
+    let mut team = player_team_q.single_mut();
+    match carrying {
+        None => {
+            let Some(ally) = units_on_map.get_player(&target) else { return };
+            // NOTE: Fails for the selected unit's own starting tile, which is what we want
+            let Ok((_, _, mut visibility, ally_is_carrying)) = allies.get_mut(ally) else { return };
+            if ally_is_carrying { return }
+
+            info!("Rescuing ally at {:?}", target);
+            *visibility = Visibility::Hidden;
+            units_on_map.remove(&target);
+            commands.entity(ally).insert(Carried);
+            commands.entity(entity).insert(Carrying(ally));
+        },
+        Some(Carrying(carried)) => {
+            if units_on_map.contains(&target) || walls.in_wall(&target) { return }
+            let Ok((mut ally_transform, mut ally_coords, mut visibility, _)) = allies.get_mut(*carried) else {
+                return
+            };
+
+            info!("Dropping ally at {:?}", target);
+            place_dropped_unit(&mut ally_transform, &mut ally_coords, &mut visibility, target);
+            commands.entity(*carried).remove::<Carried>();
+            commands.entity(entity).remove::<Carrying>();
+            units_on_map.add(&target, *carried, UnitType::Player);
+            // A dropped unit has already spent its turn being carried
+            team.add(*carried);
+        },
+    }
+
+    end_unit_move(&mut commands, entity, dest_coords, &mut coords, &mut units_on_map, &mut team);
+    state.set(BattleState::Select);
+}
+
+pub fn place_dropped_unit(
+    transform: &mut Transform,
+    coords: &mut GridCoords,
+    visibility: &mut Visibility,
+    target: GridCoords,
+) {
+    *coords = target;
+    transform.translation = grid_coords_to_translation(target, GRID_SIZE_VEC).extend(transform.translation.z);
+    *visibility = Visibility::Inherited;
+}
@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use bevy::window::CursorMoved;
+use bevy_ecs_ldtk::prelude::*;
+
+use super::{LevelWalls, MouseGridCoords};
+use crate::game::units::{Carried, Teams};
+use crate::game::{Action, ActionState, Player};
+
+// Seconds a direction is held before the cursor starts repeating, then between repeats
+const CURSOR_REPEAT_DELAY: f32 = 0.3;
+const CURSOR_REPEAT_RATE: f32 = 0.08;
+
+/// Confirm and cancel for this frame, from whichever device was used
+#[derive(Default, Resource, Debug)]
+pub struct CursorInput {
+    pub confirm: bool,
+    pub cancel: bool,
+    /// True while the grid cursor is moved with keys or a gamepad. Moving or
+    /// clicking the mouse hands it back to the mouse.
+    pub keyboard_cursor: bool,
+}
+
+#[derive(Default)]
+pub struct CursorRepeat {
+    dir: IVec2,
+    timer: f32,
+}
+
+pub fn read_cursor_input(
+    mut input: ResMut<CursorInput>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    actions: Res<ActionState>,
+) {
+    if cursor_moved.read().count() > 0 || mouse.get_just_pressed().next().is_some() {
+        input.keyboard_cursor = false;
+    }
+
+    input.confirm = actions.just_pressed(Action::Confirm);
+    input.cancel = actions.just_pressed(Action::Cancel);
+}
+
+fn held_direction(actions: &ActionState) -> IVec2 {
+    let mut dir = IVec2::ZERO;
+    if actions.pressed(Action::CursorUp) { dir.y += 1 }
+    if actions.pressed(Action::CursorDown) { dir.y -= 1 }
+    if actions.pressed(Action::CursorRight) { dir.x += 1 }
+    if actions.pressed(Action::CursorLeft) { dir.x -= 1 }
+    dir
+}
+
+/// Moves the cursor one tile, keeping it on the level
+pub fn step_cursor(coords: GridCoords, dir: IVec2, walls: &LevelWalls) -> GridCoords {
+    GridCoords::new(
+        (coords.x + dir.x).clamp(0, (walls.level_width - 1).max(0)),
+        (coords.y + dir.y).clamp(0, (walls.level_height - 1).max(0)),
+    )
+}
+
+/// The cursor actions move the grid cursor. Holding a direction repeats.
+pub fn move_grid_cursor(
+    mut mouse_coords: ResMut<MouseGridCoords>,
+    mut input: ResMut<CursorInput>,
+    mut repeat: Local<CursorRepeat>,
+    actions: Res<ActionState>,
+    walls: Res<LevelWalls>,
+    time: Res<Time>,
+) {
+    let dir = held_direction(&actions);
+    if dir == IVec2::ZERO {
+        repeat.dir = dir;
+        return;
+    }
+
+    if dir != repeat.dir {
+        repeat.dir = dir;
+        repeat.timer = CURSOR_REPEAT_DELAY;
+    } else {
+        repeat.timer -= time.delta_secs();
+        if repeat.timer > 0.0 { return }
+        repeat.timer = CURSOR_REPEAT_RATE;
+    }
+
+    let coords = step_cursor(mouse_coords.0, dir, &walls);
+    if coords != mouse_coords.0 {
+        mouse_coords.0 = coords;
+    }
+    input.keyboard_cursor = true;
+}
+
+/// The next unit after `current`, reading the map top to bottom then left to right
+pub fn next_unit(units: &[GridCoords], current: GridCoords) -> Option<GridCoords> {
+    let mut units = units.to_vec();
+    units.sort_by_key(|c| (-c.y, c.x));
+    let key = (-current.y, current.x);
+    units.iter().find(|c| (-c.y, c.x) > key).or(units.first()).copied()
+}
+
+/// NextUnit jumps the cursor to the next player unit that hasn't acted this turn
+pub fn cycle_unready_units(
+    mut mouse_coords: ResMut<MouseGridCoords>,
+    mut input: ResMut<CursorInput>,
+    actions: Res<ActionState>,
+    team: Single<&Teams>,
+    units_q: Query<(Entity, &GridCoords), (With<Player>, Without<Carried>)>,
+) {
+    if !actions.just_pressed(Action::NextUnit) { return }
+
+    let unready: Vec<GridCoords> = units_q.iter()
+        .filter(|(entity, _)| !team.contains(entity))
+        .map(|(_, coords)| *coords)
+        .collect();
+    if let Some(coords) = next_unit(&unready, mouse_coords.0) {
+        mouse_coords.0 = coords;
+        input.keyboard_cursor = true;
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use bevy::prelude::*;
+    #[allow(unused_imports)]
+    use bevy_ecs_ldtk::GridCoords;
+    #[allow(unused_imports)]
+    use crate::game::battle_scene::LevelWalls;
+    #[allow(unused_imports)]
+    use super::{next_unit, step_cursor};
+
+    #[test]
+    fn test_step_cursor_stays_on_level() {
+        let walls = LevelWalls::new(4, 6, None);
+        assert_eq!(step_cursor(GridCoords::new(0, 0), IVec2::new(-1, -1), &walls), GridCoords::new(0, 0));
+        assert_eq!(step_cursor(GridCoords::new(5, 3), IVec2::new(1, 1), &walls), GridCoords::new(5, 3));
+        assert_eq!(step_cursor(GridCoords::new(2, 2), IVec2::new(1, -1), &walls), GridCoords::new(3, 1));
+    }
+
+    #[test]
+    fn test_next_unit_wraps() {
+        let units = [GridCoords::new(1, 1), GridCoords::new(4, 5), GridCoords::new(0, 5)];
+        assert_eq!(next_unit(&units, GridCoords::new(0, 5)), Some(GridCoords::new(4, 5)));
+        assert_eq!(next_unit(&units, GridCoords::new(4, 5)), Some(GridCoords::new(1, 1)));
+        assert_eq!(next_unit(&units, GridCoords::new(1, 1)), Some(GridCoords::new(0, 5)));
+        assert_eq!(next_unit(&[], GridCoords::new(1, 1)), None);
+    }
+}
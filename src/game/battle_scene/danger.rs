@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use std::collections::HashSet;
+use bevy_ecs_ldtk::{prelude::*, utils::grid_coords_to_translation};
+
+use super::map::UnitsOnMap;
+use super::{InteractionTextures, LevelWalls, UnitType};
+use crate::game::units::{Carried, UnitStats, WeaponPack};
+use crate::game::{Action, ActionState, Enemy, GRID_SIZE_VEC};
+use crate::util::{ToGrid, ToSim};
+use sim::{attack_range, movement_range};
+
+const DANGER_COLOR: Color = Color::srgba(1.0, 0.4, 0.4, 0.6);
+
+/// Whether the danger zone is showing. Kept between battles.
+#[derive(Default, Resource)]
+pub struct ShowDanger(pub bool);
+
+#[derive(Component)]
+pub struct DangerTile;
+
+pub fn toggle_danger_zone(
+    actions: Res<ActionState>,
+    mut show: ResMut<ShowDanger>,
+) {
+    if actions.just_pressed(Action::ToggleDanger) {
+        show.0 = !show.0;
+    }
+}
+
+/// Every tile an enemy could move onto or attack next turn
+pub fn danger_zone(
+    enemies: &[(GridCoords, &UnitStats, &WeaponPack)],
+    units_on_map: &UnitsOnMap,
+    walls: &LevelWalls,
+) -> HashSet<GridCoords> {
+    let mut zone = HashSet::new();
+    for (coords, stats, pack) in enemies.iter() {
+        let range = movement_range(coords.to_sim(), stats.mov, UnitType::Enemy, units_on_map, walls);
+        zone.extend(attack_range(pack.get_equipped().range, &range));
+        zone.extend(range);
+    }
+    zone.iter().map(ToGrid::to_grid).collect()
+}
+
+/// Redraws the danger zone, since enemies have moved or the toggle changed
+#[allow(clippy::too_many_arguments)]
+pub fn refresh_danger_zone(
+    mut commands: Commands,
+    show: Res<ShowDanger>,
+    tiles_q: Query<Entity, With<DangerTile>>,
+    enemies_q: Query<(&GridCoords, &UnitStats, &WeaponPack), (With<Enemy>, Without<Carried>)>,
+    textures: Res<InteractionTextures>,
+    units_on_map: Res<UnitsOnMap>,
+    walls: Res<LevelWalls>,
+    layers: Query<(&Name, Entity), With<LayerMetadata>>,
+) {
+    for entity in tiles_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !show.0 { return }
+
+    let Some((_, layer)) = layers.iter().find(|p| p.0.as_str() == "StartingLocations") else { return };
+    let enemies: Vec<_> = enemies_q.iter().map(|(coords, stats, pack)| (*coords, stats, pack)).collect();
+    let zone = danger_zone(&enemies, &units_on_map, &walls);
+
+    commands.entity(layer).with_children(|parent| {
+        for coord in zone {
+            parent.spawn((
+                DangerTile,
+                coord,
+                // Under the unit's own range highlight
+                Transform::from_translation(grid_coords_to_translation(coord, GRID_SIZE_VEC).extend(4.0)),
+                Sprite {
+                    image: textures.attack_highlight.clone(),
+                    color: DANGER_COLOR,
+                    ..default()
+                },
+            ));
+        }
+    });
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use bevy_ecs_ldtk::GridCoords;
+    #[allow(unused_imports)]
+    use bevy::prelude::*;
+    #[allow(unused_imports)]
+    use crate::game::battle_scene::{map::UnitsOnMap, LevelWalls, UnitType};
+    #[allow(unused_imports)]
+    use crate::game::units::{UnitStats, WeaponPack};
+    #[allow(unused_imports)]
+    use sim::Weapon;
+    #[allow(unused_imports)]
+    use super::danger_zone;
+
+    #[test]
+    fn test_player_units_block_enemy_movement() {
+        let walls = LevelWalls::new(9, 9, None);
+        let mut units_on_map = UnitsOnMap::new();
+        let enemy = Entity::from_raw(1);
+        units_on_map.add(&GridCoords::new(4, 4), enemy, UnitType::Enemy);
+        units_on_map.add(&GridCoords::new(5, 4), Entity::from_raw(2), UnitType::Player);
+        units_on_map.add(&GridCoords::new(3, 4), Entity::from_raw(3), UnitType::Enemy);
+
+        let stats = UnitStats(sim::UnitStats { mov: 2, ..Default::default() });
+        // Default weapon is melee 1
+        let mut pack = WeaponPack::default();
+        pack.weapons.push(Weapon::default());
+        let zone = danger_zone(&[(GridCoords::new(4, 4), &stats, &pack)], &units_on_map, &walls);
+
+        // The player can be attacked but not walked through, allies can be walked through
+        assert!(zone.contains(&GridCoords::new(5, 4)));
+        assert!(!zone.contains(&GridCoords::new(7, 4)));
+        assert!(zone.contains(&GridCoords::new(1, 4)));
+    }
+}
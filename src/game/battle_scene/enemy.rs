@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+use bevy::{prelude::*, time::Stopwatch};
+use bevy_ecs_ldtk::prelude::*;
+
+use super::fight::{Attacker, Defender};
+use super::movement::QueuedMovementTarget;
+use super::{BattleState, LevelWalls, UnitType, UnitsOnMap};
+use crate::game::units::{Carried, Carrying, UnitStats, WeaponPack};
+use crate::game::{Enemy, Player};
+use crate::game::rules::CombatRules;
+use crate::util::{ToGrid, ToSim};
+use sim::{effective_stats, plan_path, plan_turn, SimUnit};
+
+/// Enemies still to act this enemy phase, in the order they go
+#[derive(Default, Resource)]
+pub struct EnemyQueue {
+    pending: VecDeque<Entity>,
+    started: bool,
+}
+
+/// An enemy walking up to a fight, which starts once it arrives
+#[derive(Component)]
+pub struct PlannedAttack(Entity);
+
+type MapUnits<'w, 's> = Query<
+    'w, 's,
+    (Entity, &'static mut GridCoords, &'static UnitStats, &'static WeaponPack, Has<Player>, Has<Carrying>),
+    Without<Carried>,
+>;
+
+/// Plays the enemies one at a time with the sim AI, waiting out each walk and fight
+#[allow(clippy::too_many_arguments)]
+pub fn enemy_turn(
+    mut commands: Commands,
+    mut queue: ResMut<EnemyQueue>,
+    mut state: ResMut<NextState<BattleState>>,
+    mut units_on_map: ResMut<UnitsOnMap>,
+    walls: Res<LevelWalls>,
+    rules: Res<CombatRules>,
+    mut units_q: MapUnits,
+    walking_q: Query<(), With<QueuedMovementTarget>>,
+    planned_q: Query<(Entity, &PlannedAttack)>,
+    enemy_q: Query<Entity, (With<Enemy>, Without<Carried>)>,
+) {
+    if !walking_q.is_empty() { return }
+
+    if let Some((entity, planned)) = planned_q.iter().next() {
+        commands.entity(entity).remove::<PlannedAttack>();
+        if units_q.contains(planned.0) {
+            info!("Enemy attacking");
+            commands.entity(entity).insert(Attacker);
+            commands.entity(planned.0).insert(Defender);
+            state.set(BattleState::Attack);
+        }
+        return;
+    }
+
+    if !queue.started {
+        let mut enemies: Vec<Entity> = enemy_q.iter().collect();
+        enemies.sort_by_key(|e| units_q.get(*e).map(|unit| (unit.1.x, unit.1.y)).ok());
+        queue.pending = enemies.into();
+        queue.started = true;
+    }
+
+    // Enemies that fell to a counter are skipped
+    let next = std::iter::from_fn(|| queue.pending.pop_front())
+        .find(|e| units_q.get(*e).is_ok_and(|unit| unit.2.hp > 0));
+    let Some(entity) = next else {
+        info!("Enemy phase over");
+        queue.started = false;
+        state.set(BattleState::ToPlayerTurn);
+        return;
+    };
+
+    let mut entities = Vec::new();
+    let mut sims = Vec::new();
+    for (e, coords, stats, pack, is_player, carrying) in units_q.iter() {
+        entities.push(e);
+        sims.push(SimUnit::new(
+            effective_stats(stats, carrying),
+            pack.get_equipped().clone(),
+            coords.to_sim(),
+            if is_player { UnitType::Player } else { UnitType::Enemy },
+        ));
+    }
+    let actor = entities.iter().position(|e| *e == entity).unwrap();
+    let plan = plan_turn(&rules, actor, &sims, &*walls);
+    let destination = plan.destination.to_grid();
+    let mut path: VecDeque<GridCoords> = plan_path(&sims[actor], plan.destination, &sims[..], &*walls)
+        .iter()
+        .map(ToGrid::to_grid)
+        .collect();
+
+    if let Ok((_, mut coords, ..)) = units_q.get_mut(entity) {
+        if *coords != destination {
+            units_on_map.remove(&coords);
+            units_on_map.add(&destination, entity, UnitType::Enemy);
+            *coords = destination;
+
+            path.pop_front();
+            // Walk straight over if no path was found, rather than never arriving
+            if path.is_empty() {
+                path.push_back(destination);
+            }
+            commands.entity(entity).insert(QueuedMovementTarget {
+                targets: path,
+                speed: 125.0,
+                time: Stopwatch::new(),
+            });
+        }
+    }
+
+    if let Some(target) = plan.target {
+        commands.entity(entity).insert(PlannedAttack(entities[target]));
+    }
+}
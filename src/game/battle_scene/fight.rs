@@ -1,26 +1,64 @@
 use std::collections::VecDeque;
-use std::fmt;
-use rand::Rng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
 
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy_ecs_ldtk::GridCoords;
 use bevy_ecs_ldtk::utils::translation_to_grid_coords;
 
-use crate::game::units::{Teams, UnitStats, WeaponPack};
-use crate::game::weapon::{Weapon, WeaponEffectiveness};
-use crate::game::GRID_SIZE_VEC;
+use crate::game::units::{Carried, Carrying, UnitStats, WeaponPack};
+use crate::game::roster::{RosterIndex, RunRoster};
+use crate::game::run_stats::RunStats;
+use crate::game::support::{SupportRankUp, Supports};
+use crate::game::rules::CombatRules;
+use sim::{effective_stats, percent, resolve_fight, Actor, BattleSummary, Damage, Strike};
+use crate::game::settings::{ForecastDetail, Settings};
+use crate::game::{Player, GRID_SIZE_VEC};
 use crate::util::manhattan_dist;
 
 use super::map::UnitsOnMap;
+use super::carry::place_dropped_unit;
 use super::mouse::hover_unit;
-use super::ui::{BattleSummaryText, BattleSummaryView}; use super::{BattleState, Hovered, Selected};
+use super::ui::{BattleSummaryText, BattleSummaryView}; use super::{BattleState, Hovered, Selected, UnitType};
 use super::movement::AttackHighlightBag;
+use super::animation::{fade_out, play_strike};
 
-const WEAPON_ACCURACY_BONUS: u32 = 15;
-const WEAPON_DAMAGE_BONUS: u32 = 1;
-const WEAPON_CRIT_BONUS: u32 = 0;
-const DOUBLE_ATTACK_SPEED: u32 = 4;
+// Experience for surviving a fight, and the extra for landing the killing blow
+const FIGHT_XP: u32 = 10;
+const KILL_XP: u32 = 20;
+
+/// Rolls hits and crits. The whole state is one u64 (SplitMix64), so a suspended
+/// battle picks up with the same rolls it would have had.
+#[derive(Default, Resource, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BattleRng(u64);
+
+impl BattleRng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+}
+
+impl RngCore for BattleRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
 
 #[derive(Component)]
 pub struct Attacker;
@@ -36,6 +74,7 @@ struct UnitDeathEvent {
 
 pub fn fight_plugin(app: &mut App) {
     app
+        .init_resource::<BattleRng>()
         .add_event::<UnitDeathEvent>()
         .add_systems(Update, (
                 hover_unit, 
@@ -49,229 +88,82 @@ pub fn fight_plugin(app: &mut App) {
         .add_systems(Update, ((animate_attack, delete_units).chain()).run_if(in_state(BattleState::Attack)));
 }
 
-struct ActorSummary {
-    hp: u32,
-    dmg: u32,
-    hit: u32,
-    crit: u32,
-    is_double: bool,
-}
-
-struct BattleSummary {
-    attacker: ActorSummary,
-    defender: ActorSummary
-}
-
-impl fmt::Display for BattleSummary {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "{} HP {}\n", self.attacker.hp, self.defender.hp)?;
-        write!(f, "{}", self.attacker.dmg)?;
-        if self.attacker.is_double {
-            write!(f, "x2")?;
-        }
-        write!(f, " DMG ")?;
-        write!(f, "{}", self.defender.hp)?;
-        if self.defender.is_double {
-            write!(f, "x2")?;
-        }
-        writeln!(f)?;
-        writeln!(f, "{} HIT {}", self.attacker.hit, self.defender.hit)?;
-        writeln!(f, "{} CRIT {}", self.attacker.crit, self.defender.crit)
-    }
-}
-
-impl BattleSummary {
-    fn new(
-        attacker: &UnitStats,
-        attacker_weapon: &Weapon,
-        defender: &UnitStats,
-        defender_weapon: &Weapon,
-        dist: u32,
-    ) -> Self {
-        let (atk_hit, def_hit) = calculate_accuracy_for_both(attacker, attacker_weapon, defender, defender_weapon);
-        let (atk_crit, def_crit) = calculate_crit_for_both(attacker, attacker_weapon, defender, defender_weapon);
-        let (atk_dmg, def_dmg) = calculate_damage_for_both(attacker, attacker_weapon, defender, defender_weapon);
-        let (atk_is_double, def_is_double) = is_double_attack_for_both(
-            attacker,
-            attacker_weapon,
-            defender,
-            defender_weapon
-        );
-
-        let attacker_summary = ActorSummary {
-            hp: attacker.hp,
-            dmg: atk_dmg,
-            hit: atk_hit,
-            crit: atk_crit,
-            is_double: atk_is_double
-        };
-
-        // NOTE: This dist is from the units original position, not it's new position where the
-        // dist should be calcualted from
-        let defender_summary = if defender_weapon.within_range(dist) {
-            ActorSummary {
-                hp: defender.hp,
-                dmg: def_dmg,
-                hit: def_hit,
-                crit: def_crit,
-                is_double: def_is_double
-            }
-        } else {
-            ActorSummary {
-                hp: 0,
-                dmg: 0,
-                hit: 0,
-                crit: 0,
-                is_double: false
-            }
-        };
-
-        BattleSummary {
-            attacker: attacker_summary,
-            defender: defender_summary
-        }
+fn forecast(summary: &BattleSummary, detail: ForecastDetail) -> String {
+    match detail {
+        ForecastDetail::Full => summary.to_string(),
+        ForecastDetail::Compact => {
+            let odds = summary.odds();
+            format!(
+                "{} HP {}\n{} DMG {}\n{} HIT {}\n{} KILL {}",
+                summary.attacker.hp, summary.defender.hp,
+                summary.attacker.dmg, summary.defender.dmg,
+                summary.attacker.hit, summary.defender.hit,
+                percent(odds.kill), percent(odds.death),
+            )
+        },
     }
 }
 
-#[derive(Debug)]
-enum Actor {
-    Attacker,
-    Defender
-}
-
-#[derive(Debug)]
-enum Damage {
-    Hit(u32),
-    Crit(u32),
-    Miss
-}
-
-// TODO: Refactor to enum. Should be Attack(Actor, Damge), Death(Actor)
+/// The resolved fight, played back one step at a time
 enum BattleAction {
-    Attack {actor: Actor, damage: Damage},
+    Strike(Strike),
     Death(Actor)
 }
 
 #[derive(Component)]
 struct BattleQueue {
-    queue: VecDeque<BattleAction>
+    queue: VecDeque<BattleAction>,
+    /// The animation of the last action. The next one waits for it.
+    timer: Timer,
+    /// Set once a unit has died, the fight ends when its fade out does
+    done: bool,
+    /// Where the battle goes once the fight is over, back to whichever side attacked
+    return_to: BattleState,
 }
 
 impl BattleQueue {
-    fn new() -> Self {
-        BattleQueue { queue: VecDeque::new() }
-    }
-}
-
-fn calculate_damage_for_both(
-    attacker: &UnitStats,
-    attacker_weapon: &Weapon,
-    defender: &UnitStats,
-    defender_weapon: &Weapon
-) ->(u32, u32) {
-    let atk_damange = calculate_damage(attacker, attacker_weapon, defender, defender_weapon);
-    let def_damange = calculate_damage(defender, defender_weapon, attacker, attacker_weapon);
-    (atk_damange, def_damange)
-}
-
-fn calculate_damage(
-    attacker: &UnitStats,
-    attacker_weapon: &Weapon,
-    defender: &UnitStats,
-    defender_weapon: &Weapon
-) -> u32 {
-    let damage = (attacker.atk + attacker_weapon.attack).saturating_sub(defender.def);
-
-    match attacker_weapon.get_effectivness(defender_weapon) {
-        WeaponEffectiveness::Strong => damage + WEAPON_DAMAGE_BONUS,
-        WeaponEffectiveness::Weak => damage.saturating_sub(WEAPON_DAMAGE_BONUS),
-        WeaponEffectiveness::Neutral => damage,
+    fn new(return_to: BattleState) -> Self {
+        BattleQueue {
+            queue: VecDeque::new(),
+            timer: Timer::default(),
+            done: false,
+            return_to,
+        }
     }
 }
 
-fn calculate_accuracy_for_both(
-    attacker: &UnitStats,
-    attacker_weapon: &Weapon,
-    defender: &UnitStats,
-    defender_weapon: &Weapon
-) -> (u32, u32) {
-    let atk_acc = calculate_accuracy(attacker, attacker_weapon, defender, defender_weapon);
-    let def_acc = calculate_accuracy(defender, defender_weapon, attacker, attacker_weapon);
-    (atk_acc, def_acc)
-}
-
-fn calculate_accuracy(
-    attacker: &UnitStats,
-    attacker_weapon: &Weapon,
-    defender: &UnitStats,
-    defender_weapon: &Weapon
-) -> u32 {
-    let mut atk_accuracy = attacker.accuracy() + attacker_weapon.hit;
-    atk_accuracy = atk_accuracy.saturating_sub(defender.dodge());
-
-    let atk_accuracy = match attacker_weapon.get_effectivness(defender_weapon) {
-        WeaponEffectiveness::Strong => atk_accuracy + WEAPON_ACCURACY_BONUS,
-        WeaponEffectiveness::Weak => atk_accuracy.saturating_sub(WEAPON_ACCURACY_BONUS),
-        WeaponEffectiveness::Neutral => atk_accuracy,
-    };
-
-    if atk_accuracy > 100 { 100 } else { atk_accuracy }
-}
-
-fn calculate_crit_for_both(
-    attacker: &UnitStats,
-    attacker_weapon: &Weapon,
-    defender: &UnitStats,
-    defender_weapon: &Weapon
-) -> (u32, u32) {
-    (
-        calculate_crit(attacker, attacker_weapon),
-        calculate_crit(defender, defender_weapon)
-    )
-}
-
-fn calculate_crit(
-    attacker: &UnitStats,
-    attacker_weapon: &Weapon,
-) -> u32 {
-    attacker.crit() + attacker_weapon.crit
-}
-
-fn is_double_attack_for_both(
-    attacker: &UnitStats,
-    attacker_weapon: &Weapon,
-    defender: &UnitStats,
-    defender_weapon: &Weapon
-) -> (bool, bool) {
-    (
-        is_double_attack(attacker, attacker_weapon, defender, defender_weapon),
-        is_double_attack(defender, defender_weapon, attacker, attacker_weapon)
-    )
-}
-
-fn is_double_attack(
-    attacker: &UnitStats,
-    attacker_weapon: &Weapon,
-    defender: &UnitStats,
-    defender_weapon: &Weapon
-) -> bool {
-    let atk_speed = attacker.attack_speed().saturating_sub(attacker_weapon.weight);
-    let def_speed = defender.spd.saturating_sub(defender_weapon.weight);
-    atk_speed.saturating_sub(def_speed) < DOUBLE_ATTACK_SPEED
+type UnitPositions<'w, 's> = Query<
+    'w, 's,
+    (Entity, &'static UnitStats, &'static GridCoords, Has<Player>),
+    Without<Carried>
+>;
+
+/// Allies of the given unit and where they stand
+fn get_allies<'a>(units_q: &'a UnitPositions, entity: Entity) -> Vec<(&'a str, GridCoords)> {
+    let is_player = units_q.get(entity).is_ok_and(|(_, _, _, p)| p);
+    units_q.iter()
+        .filter(|(e, _, _, p)| *e != entity && *p == is_player)
+        .map(|(_, stats, coords, _)| (stats.name.as_str(), *coords))
+        .collect()
 }
 
+#[allow(clippy::too_many_arguments)]
 fn show_battle_summary(
     battle_summary_view: Single<(&mut Visibility, &mut Node), With<BattleSummaryView>>,
     battle_summary_text: Single<&mut TextSpan, With<BattleSummaryText>>,
-    attacker: Single<(&UnitStats, &WeaponPack, &Transform), With<Selected>>,
-    defender_q: Query<(&UnitStats, &WeaponPack, &GridCoords), Added<Hovered>>,
+    attacker: Single<(Entity, &UnitStats, &WeaponPack, &Transform, Has<Carrying>), With<Selected>>,
+    defender_q: Query<(Entity, &UnitStats, &WeaponPack, &GridCoords, Has<Carrying>), Added<Hovered>>,
     attack_range_q: Single<&AttackHighlightBag>,
     window: Single<&Window, With<PrimaryWindow>>,
+    units_q: UnitPositions,
+    supports: Res<Supports>,
+    settings: Res<Settings>,
+    rules: Res<CombatRules>,
 ) {
     if defender_q.is_empty() { return }
 
-    let (def_stats, def_weapon_pack, def_coord) = defender_q.iter().next().unwrap();
-    let (atk_stats, atk_weapon_pack, atk_transform) = attacker.into_inner();
+    let (def_entity, def_stats, def_weapon_pack, def_coord, def_carrying) = defender_q.iter().next().unwrap();
+    let (atk_entity, atk_stats, atk_weapon_pack, atk_transform, atk_carrying) = attacker.into_inner();
     let attack_range = attack_range_q.into_inner();
     if !attack_range.0.contains(def_coord) { return }
 
@@ -284,18 +176,23 @@ fn show_battle_summary(
 
     let atk_coord = translation_to_grid_coords(atk_transform.translation.xy(), GRID_SIZE_VEC);
     let dist = manhattan_dist(atk_coord, *def_coord);
+    let atk_support = supports.bonus(&atk_stats.name, atk_coord, get_allies(&units_q, atk_entity).into_iter());
+    let def_support = supports.bonus(&def_stats.name, *def_coord, get_allies(&units_q, def_entity).into_iter());
 
     let summary = BattleSummary::new(
-        atk_stats,
+        &rules,
+        &effective_stats(atk_stats, atk_carrying),
         atk_weapon_pack.get_equipped(),
-        def_stats,
+        &effective_stats(def_stats, def_carrying),
         def_weapon_pack.get_equipped(),
-        dist
+        dist,
+        atk_support,
+        def_support,
     );
 
     let mut text = battle_summary_text.into_inner();
     // TODO: Rudimentry battle summary here:
-    **text = format!("{}", summary);
+    **text = forecast(&summary, settings.forecast);
 }
 
 fn remove_battle_summary(
@@ -321,146 +218,149 @@ fn cleanup_battle_summary_and_hover(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn calculate_battle_queue(
     mut commands: Commands,
-    attacker: Single<(&UnitStats, &WeaponPack, &GridCoords), With<Attacker>>,
-    defender: Single<(&UnitStats, &WeaponPack, &GridCoords), With<Defender>>,
+    attacker: Single<(Entity, &UnitStats, &WeaponPack, &GridCoords, Has<Carrying>, Has<Player>), With<Attacker>>,
+    defender: Single<(Entity, &UnitStats, &WeaponPack, &GridCoords, Has<Carrying>), With<Defender>>,
+    units_q: UnitPositions,
+    mut supports: ResMut<Supports>,
+    mut rng: ResMut<BattleRng>,
+    rules: Res<CombatRules>,
+    mut rank_up_events: EventWriter<SupportRankUp>,
 ) {
-    let (atk_stats, atk_pack, atk_coords) = attacker.into_inner();
-    let (def_stats, def_pack, def_coords) = defender.into_inner();
+    let (atk_entity, atk_stats, atk_pack, atk_coords, atk_carrying, atk_is_player) = attacker.into_inner();
+    let (def_entity, def_stats, def_pack, def_coords, def_carrying) = defender.into_inner();
+    let atk_allies = get_allies(&units_q, atk_entity);
+    let def_allies = get_allies(&units_q, def_entity);
+
+    // Bonuses use the ranks from before this fight
+    let atk_support = supports.bonus(&atk_stats.name, *atk_coords, atk_allies.iter().copied());
+    let def_support = supports.bonus(&def_stats.name, *def_coords, def_allies.iter().copied());
+    rank_up_events.send_batch(supports.gain(&atk_stats.name, *atk_coords, atk_allies.iter().copied()));
+    rank_up_events.send_batch(supports.gain(&def_stats.name, *def_coords, def_allies.iter().copied()));
+
+    let atk_stats = &effective_stats(atk_stats, atk_carrying);
+    let def_stats = &effective_stats(def_stats, def_carrying);
     let atk_weapon = atk_pack.get_equipped();
     let def_weapon = def_pack.get_equipped();
 
     let dist = manhattan_dist(*atk_coords, *def_coords);
-    let battle_summary = BattleSummary::new(atk_stats, atk_weapon, def_stats, def_weapon, dist);
-    let mut battle_queue = BattleQueue::new();
-
-    {
-        let atk_action = BattleAction::Attack {
-            actor: Actor::Attacker,
-            damage: simulate_attack(&battle_summary.attacker)
-        };
-        battle_queue.queue.push_back(atk_action);
-    }
-
-    if def_weapon.within_range(dist) {
-        let def_action = BattleAction::Attack {
-            actor: Actor::Defender,
-            damage: simulate_attack(&battle_summary.defender)
-        };
-        battle_queue.queue.push_back(def_action);
-    }
-
-    if battle_summary.attacker.is_double {
-        let atk_action = BattleAction::Attack {
-            actor: Actor::Attacker,
-            damage: simulate_attack(&battle_summary.attacker)
-        };
-        battle_queue.queue.push_back(atk_action);
-    }
+    let battle_summary = BattleSummary::new(
+        &rules,
+        atk_stats,
+        atk_weapon,
+        def_stats,
+        def_weapon,
+        dist,
+        atk_support,
+        def_support,
+    );
+    let return_to = if atk_is_player { BattleState::Select } else { BattleState::EnemyTurn };
+    let mut battle_queue = BattleQueue::new(return_to);
 
-    if def_weapon.within_range(dist) && battle_summary.defender.is_double {
-        let def_action = BattleAction::Attack {
-            actor: Actor::Defender,
-            damage: simulate_attack(&battle_summary.defender)
-        };
-        battle_queue.queue.push_back(def_action);
+    let result = resolve_fight(&battle_summary.roll_attacks(&mut *rng), atk_stats.hp, def_stats.hp);
+    battle_queue.queue.extend(result.strikes.into_iter().map(BattleAction::Strike));
+    if let Some(actor) = result.death {
+        battle_queue.queue.push_back(BattleAction::Death(actor));
     }
 
     commands.spawn(battle_queue);
 }
 
-fn simulate_attack(attacker: &ActorSummary) -> Damage {
-    // NOTE: 0 (inclusive) to 100 (exclusive)
-    // a hit is if hit is greater than rand_number
-    let atk_hit = rand::rng().random_range(0..100);
-    if attacker.hit > atk_hit {
-        let atk_crit = rand::rng().random_range(0..100);
-        if attacker.crit > atk_crit {
-            Damage::Crit(attacker.dmg.saturating_mul(3))
-        } else {
-            Damage::Hit(attacker.dmg)
-        }
-    } else {
-        Damage::Miss
-    }
-}
-
+/// Plays the next action in the battle queue once the last one has finished animating
+#[allow(clippy::too_many_arguments)]
 fn animate_attack(
     mut commands: Commands,
     mut state: ResMut<NextState<BattleState>>,
     mut battle_queue: Query<(Entity, &mut BattleQueue)>,
     mut death_event: EventWriter<UnitDeathEvent>,
-    attacker: Single<(Entity, &GridCoords, &mut UnitStats), With<Attacker>>,
+    mut run_stats: ResMut<RunStats>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+    attacker: Single<(Entity, &GridCoords, &mut UnitStats, &Transform, Has<Player>), With<Attacker>>,
     // NOTE: I can't grab 2 mutable references to the same struct, so need
     // to make sure it's impossible, i.e. defender CANNOT have attacker
     defender: Single<
-        (Entity, &GridCoords, &mut UnitStats),
+        (Entity, &GridCoords, &mut UnitStats, &Transform, Has<Player>),
         (With<Defender>, Without<Attacker>)
     >
 ) {
     if battle_queue.is_empty() { return }
     let (e, mut bq) = battle_queue.iter_mut().next().unwrap();
 
-    let (atk_entity, atk_coords, mut atk_stats) = attacker.into_inner();
-    let (def_enitty, def_coords, mut def_stats) = defender.into_inner();
+    bq.timer.tick(time.delta());
+    if !bq.timer.finished() { return }
+    if bq.done {
+        commands.entity(e).remove::<BattleQueue>();
+        state.set(bq.return_to);
+        return
+    }
+
+    let (atk_entity, atk_coords, mut atk_stats, atk_transform, atk_is_player) = attacker.into_inner();
+    let (def_enitty, def_coords, mut def_stats, def_transform, def_is_player) = defender.into_inner();
+    let atk = (atk_entity, atk_transform.translation);
+    let def = (def_enitty, def_transform.translation);
 
     match bq.queue.pop_front() {
-        Some(BattleAction::Attack { actor, damage }) => {
-            let d = match damage {
-                Damage::Miss => {
-                    info!("{:?} Missed!", actor);
-                    0
-                },
-                Damage::Crit(x) => {
-                    info!("{:?} CRIT for {}", actor, x);
-                    x
-                },
-                Damage::Hit(x) => {
-                    info!("{:?} hit for {}", actor, x);
-                    x
-                }
+        Some(BattleAction::Strike(strike)) => {
+            bq.timer = match strike.actor {
+                Actor::Attacker => play_strike(&mut commands, &settings, atk, def, &strike.damage),
+                Actor::Defender => play_strike(&mut commands, &settings, def, atk, &strike.damage),
             };
 
-            match actor {
-                Actor::Attacker => {
-                    def_stats.hp = def_stats.hp.saturating_sub(d);
-                    if def_stats.hp == 0 {
-                        bq.queue.push_front(BattleAction::Death(Actor::Defender));
-                    }
+            match strike.damage {
+                Damage::Miss => info!("{:?} Missed!", strike.actor),
+                Damage::Crit(x) => info!("{:?} CRIT for {}", strike.actor, x),
+                Damage::Hit(x) => info!("{:?} hit for {}", strike.actor, x),
+            }
+
+            match strike.actor {
+                Actor::Attacker if atk_is_player => {
+                    run_stats.record_damage(&atk_stats.name, def_stats.hp - strike.defender_hp);
                 },
-                Actor::Defender => {
-                    atk_stats.hp = atk_stats.hp.saturating_sub(d);
-                    if atk_stats.hp == 0 {
-                        bq.queue.push_front(BattleAction::Death(Actor::Attacker));
-                    }
-                }
+                Actor::Defender if def_is_player => {
+                    run_stats.record_damage(&def_stats.name, atk_stats.hp - strike.attacker_hp);
+                },
+                _ => (),
             }
+            atk_stats.hp = strike.attacker_hp;
+            def_stats.hp = strike.defender_hp;
         },
         Some(BattleAction::Death(actor)) => {
             bq.queue.clear();
+            bq.done = true;
             match actor {
                 Actor::Attacker => {
+                    def_stats.xp += FIGHT_XP + KILL_XP;
+                    if def_is_player {
+                        run_stats.record_kill(&def_stats.name);
+                    }
                     death_event.send(UnitDeathEvent {
                         coords: *atk_coords,
                         entity: atk_entity
                     });
+                    bq.timer = fade_out(&mut commands, &settings, atk_entity);
                 },
                 Actor::Defender => {
+                    atk_stats.xp += FIGHT_XP + KILL_XP;
+                    if atk_is_player {
+                        run_stats.record_kill(&atk_stats.name);
+                    }
                     death_event.send(UnitDeathEvent {
                         coords: *def_coords,
                         entity: def_enitty
                     });
+                    bq.timer = fade_out(&mut commands, &settings, def_enitty);
                 },
             }
-            // So that we don't have to continue calling animate attack
-            commands.entity(e).remove::<BattleQueue>();
-            state.set(BattleState::Select)
         },
         None => {
+            atk_stats.xp += FIGHT_XP;
+            def_stats.xp += FIGHT_XP;
             bq.queue.clear();
             commands.entity(e).remove::<BattleQueue>();
-            state.set(BattleState::Select)
+            state.set(bq.return_to)
         },
     };
 }
@@ -469,12 +369,29 @@ fn delete_units(
     mut commands: Commands,
     mut death_event: EventReader<UnitDeathEvent>,
     mut map: ResMut<UnitsOnMap>,
+    mut roster: ResMut<RunRoster>,
+    roster_index_q: Query<&RosterIndex>,
+    carrying_q: Query<&Carrying>,
+    mut carried_q: Query<(&mut Transform, &mut GridCoords, &mut Visibility), With<Carried>>,
 ) {
     for ev in death_event.read() {
         info!("{:?} Died!", ev.coords);
         map.remove(&ev.coords);
-        // TODO: Move the sprite away or maybe just remove UnitStats and Sprite?
-        commands.entity(ev.entity).despawn();
+        if let Ok(index) = roster_index_q.get(ev.entity) {
+            roster.fall(index.0);
+        }
+
+        // The carried unit is dropped where the carrier fell
+        if let Ok(Carrying(carried)) = carrying_q.get(ev.entity) {
+            if let Ok((mut transform, mut coords, mut visibility)) = carried_q.get_mut(*carried) {
+                info!("Dropping carried unit at {:?}", ev.coords);
+                place_dropped_unit(&mut transform, &mut coords, &mut visibility, ev.coords);
+                commands.entity(*carried).remove::<Carried>();
+                map.add(&ev.coords, *carried, UnitType::Player);
+            }
+        }
+
+        // The unit is despawned once it has faded out
     }
 }
 
@@ -0,0 +1,410 @@
+use std::collections::{HashSet, VecDeque};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_ldtk::utils::grid_coords_to_translation;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+
+use super::{BattleComponentsLoaded, LevelWalls};
+use crate::game::unit_selection::MAX_NUMBER_OF_UNITS;
+use crate::game::{OnLevelScreen, GRID_SIZE_VEC};
+
+const MAX_ATTEMPTS: u64 = 10;
+const MAX_ROOMS: usize = 6;
+const MAX_ROOM_ATTEMPTS: usize = 40;
+const CAVE_FILL_CHANCE: f64 = 0.45;
+const CAVE_SMOOTHING_STEPS: usize = 4;
+
+// Tiles in tilesets/Dungeon_Tileset.png, a 10x10 grid of 16px tiles
+const DUNGEON_COLUMNS: u32 = 10;
+const DUNGEON_ROWS: u32 = 10;
+const FLOOR_TILES: [usize; 8] = [6, 7, 8, 9, 16, 17, 18, 19];
+const WALL_TILE: usize = 1;
+const SOLID_TILE: usize = 78;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelStyle {
+    Rooms,
+    Cave,
+}
+
+#[derive(Clone, Debug)]
+pub struct GeneratedLevel {
+    pub width: i32,
+    pub height: i32,
+    pub walls: HashSet<GridCoords>,
+    pub player_starts: Vec<GridCoords>,
+    pub enemy_starts: Vec<GridCoords>,
+}
+
+impl GeneratedLevel {
+    /// Generates a level, retrying with new seeds until it passes `is_valid`.
+    /// Falls back to an open arena, which is always valid.
+    pub fn generate(seed: u64, width: i32, height: i32, enemies: usize) -> Self {
+        for attempt in 0..MAX_ATTEMPTS {
+            let mut rng = StdRng::seed_from_u64(seed.wrapping_add(attempt));
+            let style = if rng.random_bool(0.5) { LevelStyle::Rooms } else { LevelStyle::Cave };
+            let level = Self::generate_style(&mut rng, style, width, height, enemies);
+            if level.is_valid() {
+                return level;
+            }
+            debug!("Generated level {} failed validation, retrying", attempt);
+        }
+
+        warn!("Could not generate a valid level, using an open arena");
+        let mut rng = StdRng::seed_from_u64(seed);
+        let floor = open_arena(width, height);
+        Self::place_units(&mut rng, floor, width, height, enemies)
+    }
+
+    pub fn generate_style<R: Rng>(rng: &mut R, style: LevelStyle, width: i32, height: i32, enemies: usize) -> Self {
+        let floor = match style {
+            LevelStyle::Rooms => carve_rooms(rng, width, height),
+            LevelStyle::Cave => carve_cave(rng, width, height),
+        };
+        Self::place_units(rng, floor, width, height, enemies)
+    }
+
+    // Players start together at the left most floor tile, enemies are spread
+    // across the far half of the level
+    fn place_units<R: Rng>(rng: &mut R, floor: HashSet<GridCoords>, width: i32, height: i32, enemies: usize) -> Self {
+        let walls: HashSet<GridCoords> = (0..width)
+            .flat_map(|x| (0..height).map(move |y| GridCoords::new(x, y)))
+            .filter(|c| !floor.contains(c))
+            .collect();
+
+        let mut player_starts = Vec::new();
+        let mut enemy_starts = Vec::new();
+
+        if let Some(anchor) = floor.iter().min_by_key(|c| (c.x, c.y)).copied() {
+            let order = bfs_order(anchor, &floor);
+            player_starts = order.iter().take(MAX_NUMBER_OF_UNITS).map(|(c, _)| *c).collect();
+
+            let max_dist = order.last().map(|(_, d)| *d).unwrap_or(0);
+            let far: Vec<GridCoords> = order.iter()
+                .filter(|(c, d)| *d * 2 >= max_dist && !player_starts.contains(c))
+                .map(|(c, _)| *c)
+                .collect();
+            enemy_starts = far.choose_multiple(rng, enemies).copied().collect();
+        }
+
+        Self {
+            width,
+            height,
+            walls,
+            player_starts,
+            enemy_starts,
+        }
+    }
+
+    /// Enough player starts for a full party, and every start can reach an enemy
+    pub fn is_valid(&self) -> bool {
+        if self.player_starts.len() < MAX_NUMBER_OF_UNITS || self.enemy_starts.is_empty() {
+            return false;
+        }
+
+        let floor: HashSet<GridCoords> = (0..self.width)
+            .flat_map(|x| (0..self.height).map(move |y| GridCoords::new(x, y)))
+            .filter(|c| !self.walls.contains(c))
+            .collect();
+
+        self.player_starts.iter().all(|start| {
+            floor.contains(start) && bfs_order(*start, &floor)
+                .iter()
+                .any(|(c, _)| self.enemy_starts.contains(c))
+        })
+    }
+
+    pub fn level_walls(&self) -> LevelWalls {
+        LevelWalls::new(self.height, self.width, Some(self.walls.clone()))
+    }
+}
+
+/// Every floor tile reachable from start, with its distance, closest first
+fn bfs_order(start: GridCoords, floor: &HashSet<GridCoords>) -> Vec<(GridCoords, u32)> {
+    let mut visited = HashSet::from([start]);
+    let mut queue = VecDeque::from([(start, 0)]);
+    let mut order = Vec::new();
+
+    while let Some((coords, dist)) = queue.pop_front() {
+        order.push((coords, dist));
+        for neighbor in neighbors(coords) {
+            if floor.contains(&neighbor) && visited.insert(neighbor) {
+                queue.push_back((neighbor, dist + 1));
+            }
+        }
+    }
+
+    order
+}
+
+fn neighbors(coords: GridCoords) -> [GridCoords; 4] {
+    [
+        coords + GridCoords::new(1, 0),
+        coords + GridCoords::new(-1, 0),
+        coords + GridCoords::new(0, 1),
+        coords + GridCoords::new(0, -1),
+    ]
+}
+
+fn open_arena(width: i32, height: i32) -> HashSet<GridCoords> {
+    (1..width - 1)
+        .flat_map(|x| (1..height - 1).map(move |y| GridCoords::new(x, y)))
+        .collect()
+}
+
+struct Room {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl Room {
+    fn center(&self) -> GridCoords {
+        GridCoords::new(self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    fn overlaps(&self, other: &Room) -> bool {
+        // Keep a tile of wall between rooms
+        self.x <= other.x + other.width
+            && other.x <= self.x + self.width
+            && self.y <= other.y + other.height
+            && other.y <= self.y + self.height
+    }
+}
+
+/// Rectangular rooms joined in order by L shaped corridors
+fn carve_rooms<R: Rng>(rng: &mut R, width: i32, height: i32) -> HashSet<GridCoords> {
+    let mut rooms: Vec<Room> = Vec::new();
+
+    for _ in 0..MAX_ROOM_ATTEMPTS {
+        let room_width = rng.random_range(3..=7).min(width - 2);
+        let room_height = rng.random_range(3..=6).min(height - 2);
+        if room_width < 1 || room_height < 1 { break }
+
+        let room = Room {
+            x: rng.random_range(1..=width - room_width - 1),
+            y: rng.random_range(1..=height - room_height - 1),
+            width: room_width,
+            height: room_height,
+        };
+        if rooms.iter().all(|r| !r.overlaps(&room)) {
+            rooms.push(room);
+        }
+        if rooms.len() >= MAX_ROOMS { break }
+    }
+
+    // Corridors read better going left to right
+    rooms.sort_by_key(|r| r.x);
+
+    let mut floor = HashSet::new();
+    for room in rooms.iter() {
+        for x in room.x..room.x + room.width {
+            for y in room.y..room.y + room.height {
+                floor.insert(GridCoords::new(x, y));
+            }
+        }
+    }
+
+    for pair in rooms.windows(2) {
+        let (start, end) = (pair[0].center(), pair[1].center());
+        let corner = if rng.random_bool(0.5) {
+            GridCoords::new(end.x, start.y)
+        } else {
+            GridCoords::new(start.x, end.y)
+        };
+        carve_line(&mut floor, start, corner);
+        carve_line(&mut floor, corner, end);
+    }
+
+    floor
+}
+
+fn carve_line(floor: &mut HashSet<GridCoords>, start: GridCoords, end: GridCoords) {
+    for x in start.x.min(end.x)..=start.x.max(end.x) {
+        for y in start.y.min(end.y)..=start.y.max(end.y) {
+            floor.insert(GridCoords::new(x, y));
+        }
+    }
+}
+
+/// Random noise smoothed with cellular automata, keeping only the largest open area
+fn carve_cave<R: Rng>(rng: &mut R, width: i32, height: i32) -> HashSet<GridCoords> {
+    let is_border = |x: i32, y: i32| x == 0 || y == 0 || x == width - 1 || y == height - 1;
+
+    let mut walls: HashSet<GridCoords> = HashSet::new();
+    for x in 0..width {
+        for y in 0..height {
+            if is_border(x, y) || rng.random_bool(CAVE_FILL_CHANCE) {
+                walls.insert(GridCoords::new(x, y));
+            }
+        }
+    }
+
+    for _ in 0..CAVE_SMOOTHING_STEPS {
+        let mut next = HashSet::new();
+        for x in 0..width {
+            for y in 0..height {
+                let mut count = 0;
+                for dx in -1..=1 {
+                    for dy in -1..=1 {
+                        let (nx, ny) = (x + dx, y + dy);
+                        let outside = nx < 0 || ny < 0 || nx >= width || ny >= height;
+                        if outside || walls.contains(&GridCoords::new(nx, ny)) {
+                            count += 1;
+                        }
+                    }
+                }
+                if is_border(x, y) || count >= 5 {
+                    next.insert(GridCoords::new(x, y));
+                }
+            }
+        }
+        walls = next;
+    }
+
+    // Kept in order so ties between equally sized areas are seeded too
+    let floor_tiles: Vec<GridCoords> = (0..width)
+        .flat_map(|x| (0..height).map(move |y| GridCoords::new(x, y)))
+        .filter(|c| !walls.contains(c))
+        .collect();
+    let floor: HashSet<GridCoords> = floor_tiles.iter().copied().collect();
+
+    let mut seen: HashSet<GridCoords> = HashSet::new();
+    let mut largest: HashSet<GridCoords> = HashSet::new();
+    for start in floor_tiles.iter() {
+        if seen.contains(start) { continue }
+
+        let region: HashSet<GridCoords> = bfs_order(*start, &floor).into_iter().map(|(c, _)| c).collect();
+        seen.extend(region.iter().copied());
+        if region.len() > largest.len() {
+            largest = region;
+        }
+    }
+
+    largest
+}
+
+/// Spawns the tiles and starting locations for a generated level. Units get picked up
+/// by init_units_on_map the same way they are for LDtk levels.
+pub fn spawn_generated_level(
+    commands: &mut Commands,
+    level: &GeneratedLevel,
+    level_walls: &mut LevelWalls,
+    components_loaded: &mut BattleComponentsLoaded,
+    assert_server: &AssetServer,
+    texture_atlases: &mut Assets<TextureAtlasLayout>,
+) {
+    let texture = assert_server.load("tilesets/Dungeon_Tileset.png");
+    let layout = texture_atlases.add(TextureAtlasLayout::from_grid(
+        UVec2::new(16, 16),
+        DUNGEON_COLUMNS,
+        DUNGEON_ROWS,
+        None,
+        None,
+    ));
+
+    commands.spawn((
+        Transform::default(),
+        Visibility::default(),
+        OnLevelScreen,
+    )).with_children(|parent| {
+        for x in 0..level.width {
+            for y in 0..level.height {
+                let coords = GridCoords::new(x, y);
+                let index = if !level.walls.contains(&coords) {
+                    FLOOR_TILES[(x * 7 + y * 13) as usize % FLOOR_TILES.len()]
+                } else if !level.walls.contains(&(coords + GridCoords::new(0, -1))) && y > 0 {
+                    // Wall with floor beneath it, so the brick face shows
+                    WALL_TILE
+                } else {
+                    SOLID_TILE
+                };
+
+                parent.spawn((
+                    Sprite {
+                        image: texture.clone(),
+                        texture_atlas: Some(TextureAtlas { layout: layout.clone(), index }),
+                        ..default()
+                    },
+                    Transform::from_translation(grid_coords_to_translation(coords, GRID_SIZE_VEC).extend(0.0)),
+                ));
+            }
+        }
+    });
+
+    // Matches the entity layer of the LDtk levels so highlights have somewhere to go
+    commands.spawn((
+        Name::new("StartingLocations"),
+        LayerMetadata::default(),
+        Transform::from_xyz(0.0, 0.0, 2.0),
+        Visibility::default(),
+        OnLevelScreen,
+    )).with_children(|parent| {
+        let starts = level.player_starts.iter().map(|c| (c, "Player_Start"))
+            .chain(level.enemy_starts.iter().map(|c| (c, "Enemy_Start")));
+        for (coords, identifier) in starts {
+            parent.spawn((
+                EntityInstance {
+                    identifier: identifier.to_string(),
+                    ..default()
+                },
+                Transform::from_translation(grid_coords_to_translation(*coords, GRID_SIZE_VEC).extend(0.0)),
+                Visibility::default(),
+            ));
+        }
+    });
+
+    *level_walls = level.level_walls();
+    components_loaded.0 += 1;
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use rand::{rngs::StdRng, SeedableRng};
+    #[allow(unused_imports)]
+    use crate::game::unit_selection::MAX_NUMBER_OF_UNITS;
+    #[allow(unused_imports)]
+    use super::{GeneratedLevel, LevelStyle};
+
+    #[test]
+    fn test_generated_levels_are_valid() {
+        for seed in 0..30 {
+            let level = GeneratedLevel::generate(seed, 20, 16, 4);
+            assert!(level.is_valid(), "seed {} generated an invalid level", seed);
+            assert!(level.player_starts.len() >= MAX_NUMBER_OF_UNITS);
+            assert_eq!(level.enemy_starts.len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_starts_are_on_floor() {
+        for style in [LevelStyle::Rooms, LevelStyle::Cave] {
+            let mut rng = StdRng::seed_from_u64(5);
+            let level = GeneratedLevel::generate_style(&mut rng, style, 24, 18, 3);
+            for start in level.player_starts.iter().chain(level.enemy_starts.iter()) {
+                assert!(!level.walls.contains(start));
+                assert!(!level.level_walls().in_wall(start));
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_is_seeded() {
+        let lhs = GeneratedLevel::generate(9, 20, 16, 4);
+        let rhs = GeneratedLevel::generate(9, 20, 16, 4);
+
+        assert_eq!(lhs.walls, rhs.walls);
+        assert_eq!(lhs.player_starts, rhs.player_starts);
+        assert_eq!(lhs.enemy_starts, rhs.enemy_starts);
+    }
+
+    #[test]
+    fn test_invalid_without_enemies() {
+        let mut level = GeneratedLevel::generate(1, 20, 16, 4);
+        level.enemy_starts.clear();
+        assert!(!level.is_valid());
+    }
+}
@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use super::animation::{Fading, Flash};
+use crate::game::units::{MaxHp, Teams, UnitStats};
+use crate::game::{GameState, Player, Settings};
+
+// In pixels. The bar sits along the bottom of the unit's tile.
+const HP_BAR_WIDTH: f32 = 14.0;
+const HP_BAR_HEIGHT: f32 = 2.0;
+const HP_BAR_Y: f32 = -7.0;
+// Fraction of the gap to the real HP an HP bar closes per second
+const HP_DRAIN_RATE: f32 = 8.0;
+
+const HP_BAR_BACK_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+const PLAYER_HP_COLOR: Color = Color::srgb(0.3, 0.6, 1.0);
+const ENEMY_HP_COLOR: Color = Color::srgb(0.95, 0.3, 0.3);
+/// Units that have already acted this phase are drawn greyed out
+const MOVED_TINT: Color = Color::srgb(0.45, 0.45, 0.45);
+
+/// Background of an HP bar, a child of the unit
+#[derive(Component)]
+pub struct HpBarFrame;
+
+/// The filled part of an HP bar. Shown HP catches up with the unit's real HP over time.
+#[derive(Component)]
+pub struct HpBar {
+    unit: Entity,
+    shown: f32,
+}
+
+pub fn hp_bar_plugin(app: &mut App) {
+    app.add_systems(Update, (
+        add_hp_bars,
+        drain_hp_bars,
+        grey_out_moved_units,
+    ).run_if(in_state(GameState::InBattle)));
+}
+
+/// Width of an HP bar's fill for the shown HP
+pub fn hp_bar_width(shown: f32, max_hp: u32) -> f32 {
+    if max_hp == 0 { return 0.0 }
+    HP_BAR_WIDTH * (shown / max_hp as f32).clamp(0.0, 1.0)
+}
+
+fn hp_bar_color(is_player: bool) -> Color {
+    if is_player { PLAYER_HP_COLOR } else { ENEMY_HP_COLOR }
+}
+
+/// Every unit gets a bar once it's placed on the map
+fn add_hp_bars(
+    mut commands: Commands,
+    units_q: Query<(Entity, &UnitStats, &MaxHp, Has<Player>), Added<MaxHp>>,
+) {
+    for (unit, stats, max_hp, is_player) in units_q.iter() {
+        commands.entity(unit).with_children(|parent| {
+            parent
+                .spawn((
+                    Sprite::from_color(HP_BAR_BACK_COLOR, Vec2::new(HP_BAR_WIDTH, HP_BAR_HEIGHT)),
+                    Transform::from_xyz(0., HP_BAR_Y, 1.),
+                    HpBarFrame,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Sprite {
+                            anchor: Anchor::CenterLeft,
+                            ..Sprite::from_color(hp_bar_color(is_player), Vec2::new(hp_bar_width(stats.hp as f32, max_hp.0), HP_BAR_HEIGHT))
+                        },
+                        Transform::from_xyz(-HP_BAR_WIDTH / 2.0, 0., 1.),
+                        HpBar { unit, shown: stats.hp as f32 },
+                    ));
+                });
+        });
+    }
+}
+
+fn drain_hp_bars(
+    mut bar_q: Query<(&mut HpBar, &mut Sprite)>,
+    units_q: Query<(&UnitStats, &MaxHp)>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    for (mut bar, mut sprite) in bar_q.iter_mut() {
+        let Ok((stats, max_hp)) = units_q.get(bar.unit) else { continue };
+        let hp = stats.hp as f32;
+        if bar.shown == hp { continue }
+
+        if settings.skip_combat_animations {
+            bar.shown = hp;
+        } else {
+            let t = (HP_DRAIN_RATE * settings.animation_speed * time.delta_secs()).min(1.0);
+            bar.shown += (hp - bar.shown) * t;
+            if (bar.shown - hp).abs() < 0.05 {
+                bar.shown = hp;
+            }
+        }
+        sprite.custom_size = Some(Vec2::new(hp_bar_width(bar.shown, max_hp.0), HP_BAR_HEIGHT));
+    }
+}
+
+/// Units in the Teams moved-set are greyed out until the phase ends. Flashing and fading
+/// units are left to their animation.
+fn grey_out_moved_units(
+    team: Option<Single<&Teams>>,
+    mut units_q: Query<(Entity, &mut Sprite), (With<Player>, With<UnitStats>, Without<Flash>, Without<Fading>)>,
+) {
+    let Some(team) = team else { return };
+    for (entity, mut sprite) in units_q.iter_mut() {
+        let color = if team.contains(&entity) { MOVED_TINT } else { Color::WHITE };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::{hp_bar_width, HP_BAR_WIDTH};
+
+    #[test]
+    fn test_hp_bar_width() {
+        assert_eq!(hp_bar_width(5.0, 10), HP_BAR_WIDTH / 2.0);
+        assert_eq!(hp_bar_width(12.0, 10), HP_BAR_WIDTH);
+        assert_eq!(hp_bar_width(3.0, 0), 0.0);
+    }
+}
@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+use bevy_asset_loader::asset_collection::AssetCollection;
+use bevy_ecs_ldtk::ldtk::{ldtk_fields::LdtkFieldsError, LdtkJson, Level};
+use bevy_ecs_ldtk::prelude::*;
+use rand::seq::IndexedRandom;
+use rand::Rng;
+use thiserror::Error;
+
+const MAX_TIER: u32 = 3;
+// Run map layers per difficulty tier
+const LAYERS_PER_TIER: usize = 2;
+
+#[derive(Resource, AssetCollection)]
+pub struct BattleLevels {
+    #[asset(path="test_level.ldtk")]
+    pub project: Handle<LdtkProject>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Objective {
+    /// Defeat every enemy
+    Rout,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum LevelInfoError {
+    #[error("Missing level field: {0}")]
+    Field(#[from] LdtkFieldsError),
+    #[error("Unknown objective: {0}")]
+    UnknownObjective(String),
+}
+
+/// Metadata read from the level fields of a hand-made LDtk level
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelInfo {
+    pub iid: String,
+    pub identifier: String,
+    pub difficulty: u32,
+    pub biome: String,
+    pub objective: Objective,
+    pub party_size: usize,
+}
+
+impl LevelInfo {
+    pub fn from_level(level: &Level) -> Result<Self, LevelInfoError> {
+        let objective = match level.get_string_field("Objective")?.as_str() {
+            "Rout" => Objective::Rout,
+            other => return Err(LevelInfoError::UnknownObjective(other.to_string())),
+        };
+
+        Ok(Self {
+            iid: level.iid.clone(),
+            identifier: level.identifier.clone(),
+            difficulty: (*level.get_int_field("Difficulty")?).max(1) as u32,
+            biome: level.get_string_field("Biome")?.clone(),
+            objective,
+            party_size: (*level.get_int_field("Party_size")?).max(1) as usize,
+        })
+    }
+}
+
+/// Where the next battle's level comes from
+#[derive(Clone, Debug, PartialEq)]
+pub enum LevelChoice {
+    Ldtk(LevelInfo),
+    Generated,
+}
+
+/// Every hand-made level that can show up in a run
+#[derive(Default, Resource, Debug)]
+pub struct LevelPool {
+    pub levels: Vec<LevelInfo>,
+    last: Option<String>,
+}
+
+impl LevelPool {
+    pub fn from_project(project: &LdtkJson) -> Self {
+        let levels = project.levels.iter()
+            .filter_map(|level| match LevelInfo::from_level(level) {
+                Ok(info) => Some(info),
+                Err(e) => {
+                    error!("Skipping level {} in the level pool. {}", level.identifier, e);
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            levels,
+            last: None,
+        }
+    }
+
+    pub fn tier_for_depth(depth: usize) -> u32 {
+        (1 + (depth / LAYERS_PER_TIER) as u32).min(MAX_TIER)
+    }
+
+    /// Hand-made levels at the depth's tier or the one below it that fit the party,
+    /// skipping the last level played
+    pub fn candidates(&self, depth: usize, party_size: usize) -> Vec<&LevelInfo> {
+        let tier = Self::tier_for_depth(depth);
+        self.levels.iter()
+            .filter(|l| l.difficulty <= tier && l.difficulty + 1 >= tier)
+            .filter(|l| l.party_size >= party_size)
+            .filter(|l| self.last.as_ref() != Some(&l.iid))
+            .collect()
+    }
+
+    /// Picks from the fitting levels, with a generated level as one more option
+    pub fn pick<R: Rng + ?Sized>(&mut self, depth: usize, party_size: usize, rng: &mut R) -> LevelChoice {
+        let mut choices: Vec<LevelChoice> = self.candidates(depth, party_size)
+            .into_iter()
+            .map(|l| LevelChoice::Ldtk(l.clone()))
+            .collect();
+        choices.push(LevelChoice::Generated);
+
+        let choice = choices.choose(rng).cloned().unwrap_or(LevelChoice::Generated);
+        self.last = match &choice {
+            LevelChoice::Ldtk(info) => Some(info.iid.clone()),
+            LevelChoice::Generated => None,
+        };
+        choice
+    }
+}
+
+pub fn init_level_pool(
+    mut commands: Commands,
+    battle_levels: Res<BattleLevels>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+) {
+    let Some(project) = ldtk_project_assets.get(&battle_levels.project) else {
+        error!("Unable to create the level pool. Asset not properly loaded.");
+        return;
+    };
+
+    let pool = LevelPool::from_project(project.json_data());
+    info!("Loaded {} levels into the level pool", pool.levels.len());
+    commands.insert_resource(pool);
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use bevy_ecs_ldtk::ldtk::LdtkJson;
+    #[allow(unused_imports)]
+    use rand::{rngs::StdRng, SeedableRng};
+    #[allow(unused_imports)]
+    use super::{LevelChoice, LevelPool, Objective};
+
+    #[allow(dead_code)]
+    fn get_pool() -> LevelPool {
+        let file = include_bytes!("../../../assets/test_level.ldtk");
+        let project: LdtkJson = serde_json::from_slice(file).unwrap();
+        LevelPool::from_project(&project)
+    }
+
+    #[test]
+    fn test_levels_have_fields() {
+        let pool = get_pool();
+
+        assert_eq!(pool.levels.len(), 4);
+        assert!(pool.levels.iter().all(|l| l.objective == Objective::Rout));
+        for tier in 1..=3 {
+            assert!(pool.levels.iter().any(|l| l.difficulty == tier), "no levels for tier {}", tier);
+        }
+    }
+
+    #[test]
+    fn test_candidates_fit_depth() {
+        let pool = get_pool();
+
+        assert!(pool.candidates(0, 2).iter().all(|l| l.difficulty == 1));
+        assert!(pool.candidates(6, 3).iter().all(|l| l.difficulty >= 2));
+        // Level_0 only has room for two units
+        assert!(pool.candidates(0, 3).iter().all(|l| l.identifier != "Level_0"));
+    }
+
+    #[test]
+    fn test_no_back_to_back_repeats() {
+        let mut pool = get_pool();
+        let mut rng = StdRng::seed_from_u64(4);
+
+        let mut last = None;
+        for _ in 0..50 {
+            let choice = pool.pick(0, 2, &mut rng);
+            if let LevelChoice::Ldtk(info) = &choice {
+                assert_ne!(last.as_ref(), Some(&info.iid));
+                last = Some(info.iid.clone());
+            } else {
+                last = None;
+            }
+        }
+    }
+}
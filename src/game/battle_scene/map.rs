@@ -4,8 +4,17 @@ use bevy_ecs_ldtk::prelude::*;
 use bevy::prelude::*;
 
 use super::{BattleState, BattleComponentsLoaded, PlayerTurnLabel, UnitType};
-use crate::game::units::{UnitStats, UnitBundle, WeaponPack};
-use crate::game::{Player, Enemy, GRID_SIZE, SelectedUnits};
+use crate::game::units::{Inventory, MaxHp, UnitStats, UnitBundle, WeaponPack};
+use crate::game::roster::{RosterIndex, RunRoster};
+use crate::game::map_selection::{NodeType, RunMap};
+use crate::game::catalogue::WeaponCatalogue;
+use crate::game::{Player, Enemy, GRID_SIZE};
+use sim::{Coords, Occupancy};
+use crate::util::ToGrid;
+
+// Indices into Dungeon_Character_2.png
+pub const PLAYER_SPRITE: usize = 2;
+pub const ENEMY_SPRITE: usize = 8;
 
 // Maybe use an Enum in a new struct to show Enemy/Player
 #[derive(Default, Resource, Debug)]
@@ -15,6 +24,7 @@ pub struct UnitsOnMap {
 }
 
 impl UnitsOnMap {
+    #[allow(dead_code)]
     pub fn new() -> Self {
         Self {
             player_units: HashMap::new(),
@@ -36,6 +46,10 @@ impl UnitsOnMap {
         self.enemy_units.get(coords).copied()
     }
 
+    pub fn get_player(&self, coords: &GridCoords) -> Option<Entity> {
+        self.player_units.get(coords).copied()
+    }
+
     pub fn remove(&mut self, coords: &GridCoords) {
         if self.player_units.contains_key(coords) {
             self.player_units.remove(coords);
@@ -71,10 +85,29 @@ impl UnitsOnMap {
     pub fn is_enemy(&self, coords: &GridCoords) -> bool {
         self.enemy_units.contains_key(coords)
     }
+
+    /// A unit on the other side from unit_type is standing on coords
+    pub fn is_hostile(&self, coords: &GridCoords, unit_type: &UnitType) -> bool {
+        match unit_type {
+            UnitType::Player => self.is_enemy(coords),
+            UnitType::Enemy => self.is_player(coords),
+        }
+    }
+}
+
+impl Occupancy for UnitsOnMap {
+    fn contains(&self, coords: &Coords) -> bool {
+        UnitsOnMap::contains(self, &coords.to_grid())
+    }
+
+    fn is_hostile(&self, coords: &Coords, unit_type: &UnitType) -> bool {
+        UnitsOnMap::is_hostile(self, &coords.to_grid(), unit_type)
+    }
 }
 
 // NOTE: This function will create Units, and add them to
 //  an existing entity
+#[allow(clippy::too_many_arguments)]
 pub fn init_units_on_map(
     mut commands: Commands,
     mut components_loaded: ResMut<BattleComponentsLoaded>,
@@ -82,62 +115,58 @@ pub fn init_units_on_map(
     assert_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
     mut units_on_map: ResMut<UnitsOnMap>,
-    mut selected_units: ResMut<SelectedUnits>,
-
+    roster: Res<RunRoster>,
+    run_map: Res<RunMap>,
+    catalogue: Res<WeaponCatalogue>,
 ) {
     let mut units_loaded = false;
     let mut tracker: usize = 0;
 
     for (entity, transform, entity_instance) in entity_query.iter() {
         units_loaded = true;
-        let texture = assert_server.load("tilesets/Dungeon_Character_2.png");
         let grid_coords = translation_to_grid_coords(transform.translation.xy(), IVec2::splat(GRID_SIZE));
-        let layout = texture_atlases.add(TextureAtlasLayout::from_grid(
-            UVec2::new(16, 16),
-            7,
-            2,
-            None,
-            None,
-        ));
 
-        let (atlas, stats) = match entity_instance.identifier.as_str() {
+        let (sprite_index, stats, max_hp, pack, inventory) = match entity_instance.identifier.as_str() {
             "Enemy_Start" => {
                 info!("Creating enemy unit on map");
                 commands.entity(entity).insert(Enemy);
-                let stats = UnitStats::enemy();
+                let stats = match run_map.current_node_type() {
+                    Some(NodeType::EliteBattle) => UnitStats(sim::UnitStats::elite()),
+                    _ => UnitStats(sim::UnitStats::enemy()),
+                };
                 units_on_map.enemy_units.insert(grid_coords, entity);
                 units_on_map.add(&grid_coords, entity, UnitType::Enemy);
                 (
-                    TextureAtlas {
-                        index: 8,
-                        layout
-                    },
-                    stats
+                    ENEMY_SPRITE,
+                    stats.clone(),
+                    stats.hp,
+                    WeaponPack::new(&catalogue),
+                    Inventory::default()
                 )
             },
             "Player_Start" => {
-                if selected_units.0.is_empty() {
+                if roster.units.is_empty() {
                     error!("No selected units. Skipping placement for starting location.");
                     // We continue instead of break so Enemies can still be placed
                     continue;
                 }
 
-                if tracker >= selected_units.0.len() {
+                if tracker >= roster.units.len() {
                     debug!("There are more starting locations than units selected.");
                     continue;
                 }
 
                 info!("Creating player unit on map");
-                commands.entity(entity).insert(Player);
-                let stats = selected_units.0[tracker].clone();
+                commands.entity(entity).insert((Player, RosterIndex(tracker)));
+                let unit = roster.units[tracker].clone();
                 tracker += 1;
                 units_on_map.add(&grid_coords, entity, UnitType::Player);
                 (
-                    TextureAtlas {
-                        index: 2,
-                        layout
-                    },
-                    stats
+                    PLAYER_SPRITE,
+                    unit.stats,
+                    unit.max_hp,
+                    unit.pack,
+                    unit.inventory
                 )
             }
             _ => {
@@ -148,27 +177,45 @@ pub fn init_units_on_map(
 
         commands.entity(entity).insert ((
             UnitBundle {
-                pack: WeaponPack::new(),
+                pack,
+                inventory,
                 stats,
+                max_hp: MaxHp(max_hp),
                 grid_coords
             },
-            Sprite {
-                image: texture,
-                texture_atlas: Some(atlas),
-                ..Default::default()
-            },
+            unit_sprite(&assert_server, &mut texture_atlases, sprite_index),
             *transform,
         ));
     }
 
     if units_loaded {
         components_loaded.0 += 1;
-        if tracker == 0 && !selected_units.0.is_empty() {
+        if tracker == 0 && !roster.units.is_empty() {
             error!("Could not place any selected units. Map has no starting locations for Player");
         }
     }
 }
 
+pub fn unit_sprite(
+    asset_server: &AssetServer,
+    texture_atlases: &mut Assets<TextureAtlasLayout>,
+    index: usize,
+) -> Sprite {
+    let layout = texture_atlases.add(TextureAtlasLayout::from_grid(
+        UVec2::new(16, 16),
+        7,
+        2,
+        None,
+        None,
+    ));
+
+    Sprite {
+        image: asset_server.load("tilesets/Dungeon_Character_2.png"),
+        texture_atlas: Some(TextureAtlas { index, layout }),
+        ..Default::default()
+    }
+}
+
 // TOOD: Move this over to game/ui.rs
 pub fn setup_transition_animation(
     mut _commands: Commands,
@@ -178,8 +225,8 @@ pub fn setup_transition_animation(
     info!("Setting up transition animation");
     for (_entity, _node, mut text) in entities.iter_mut() {
         match active_game_state.get() {
-            BattleState::ToEnemyTurn => **text = format!("ENEMY TURN"),
-            BattleState::ToPlayerTurn => **text = format!("PLAYER TURN"),
+            BattleState::ToEnemyTurn => **text = "ENEMY TURN".to_string(),
+            BattleState::ToPlayerTurn => **text = "PLAYER TURN".to_string(),
             _ => (),
         }
     }
@@ -1,6 +1,8 @@
 use std::collections::HashSet;
 use bevy::prelude::*;
 use bevy_asset_loader::asset_collection::AssetCollection;
+use bevy_asset_loader::loading_state::config::{ConfigureLoadingState, LoadingStateConfig};
+use bevy_asset_loader::loading_state::LoadingStateAppExt;
 use bevy_ecs_ldtk::prelude::*;
 use bevy_ecs_ldtk::LdtkProjectHandle;
 
@@ -10,11 +12,26 @@ mod map;
 mod mouse;
 mod ui;
 mod fight;
-
-use crate::{despawn_screen, AppState};
+mod trade;
+mod carry;
+mod generator;
+mod level_pool;
+mod suspend;
+mod cursor;
+mod danger;
+mod animation;
+mod hp_bar;
+mod enemy;
+
+use crate::despawn_screen;
 use crate::game::GRID_SIZE;
+use sim::{Coords, UnitType, Walls};
+use crate::util::ToGrid;
 use map::{UnitsOnMap, init_units_on_map, setup_transition_animation, transition_animation};
-use super::{OnLevelScreen, GameState};
+use super::{ActionSystem, OnLevelScreen, GameState, Player, Enemy};
+use super::roster::{write_back_roster, RunRoster};
+use super::run_stats::RunStats;
+use super::map_selection::{NodeType, RunMap};
 use super::units::{Teams, check_for_team_refresh};
 use movement::{
     add_queued_movement_target_to_entity,
@@ -24,13 +41,30 @@ use movement::{
     confirm_movement_or_attack,
     show_attack_highlight
 };
-use mouse::{update_hovered_unit, select_unit, removed_hovered_unit, update_cursor_sprite,
+use mouse::{update_hovered_unit, place_detail_view, select_unit, removed_hovered_unit, update_cursor_sprite,
             hover_unit, track_mouse_coords, spawn_cursor_sprite, cursor_sprite_not_yet_spawned};
-use camera::{move_screen_rts, zoom_in_scroll_wheel};
+use camera::{CameraFocus, center_camera, clamp_camera, drag_camera, focus_on_acting_unit, follow_camera_focus,
+             move_screen_rts, pan_camera_keys, zoom_in_scroll_wheel};
+use cursor::{CursorInput, cycle_unready_units, move_grid_cursor, read_cursor_input};
+use danger::{ShowDanger, refresh_danger_zone, toggle_danger_zone};
+use enemy::{EnemyQueue, enemy_turn};
 use ui::init_ui;
-use fight::fight_plugin;
+use fight::{fight_plugin, BattleRng};
+use animation::animation_plugin;
+use hp_bar::hp_bar_plugin;
+use trade::trade_plugin;
+use carry::rescue_or_drop;
+use generator::{GeneratedLevel, spawn_generated_level};
+use level_pool::{BattleLevels, LevelChoice, LevelPool, init_level_pool};
+use suspend::{BattleLevel, CurrentLevel, clear_pending_battle, restore_units_on_map, suspend_battle};
+pub use suspend::{BattleSave, PendingBattle};
 
 const REQUIRED_BATTLE_COMPONENTS: u32 = 2;
+const GENERATED_LEVEL_WIDTH: i32 = 20;
+const GENERATED_LEVEL_HEIGHT: i32 = 16;
+const BASE_ENEMY_COUNT: usize = 3;
+const BATTLE_GOLD: u32 = 100;
+const ELITE_BATTLE_GOLD: u32 = 250;
 
 #[derive(Component)]
 struct EndBattleEarly;
@@ -41,6 +75,10 @@ pub struct PlayerTurnLabel;
 #[derive(Default, Resource, Debug)]
 pub struct BattleComponentsLoaded(pub u32);
 
+/// Player turns since the battle started, starting at 1
+#[derive(Default, Resource, Debug, Clone, Copy)]
+pub struct BattleTurn(pub u32);
+
 #[derive(Default, Resource, Debug)]
 pub struct MouseGridCoords(GridCoords);
 
@@ -64,11 +102,6 @@ struct InteractionTextures {
     cursor: Handle<Image>,
 }
 
-enum UnitType {
-    Player,
-    Enemy
-}
-
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, SubStates)]
 #[source(GameState = GameState::InBattle)]
 pub enum BattleState {
@@ -82,6 +115,8 @@ pub enum BattleState {
     ConfirmMovement,
     // Attack (goes back to Select)
     Attack,
+    // Trading with an adjacent ally (goes back to ConfirmMovement)
+    Trade,
     _InGameMenu,
     // Transitions
     ToEnemyTurn,
@@ -107,6 +142,7 @@ impl LevelWalls {
         }
     }
 
+    #[allow(dead_code)]
     pub fn insert(&mut self, grid_coords: GridCoords) {
         self.wall_locations.insert(grid_coords);
     }
@@ -121,6 +157,12 @@ impl LevelWalls {
     }
 }
 
+impl Walls for LevelWalls {
+    fn in_wall(&self, coords: &Coords) -> bool {
+        LevelWalls::in_wall(self, &coords.to_grid())
+    }
+}
+
 #[derive(Default, Component, Debug)]
 struct Wall;
 
@@ -133,23 +175,36 @@ pub fn battle_scene_plugin(app: &mut App) {
     app
         .init_resource::<LevelWalls>()
         .init_resource::<BattleComponentsLoaded>()
+        .init_resource::<BattleTurn>()
         .init_resource::<UnitsOnMap>()
         .init_resource::<MouseGridCoords>()
+        .init_resource::<CursorInput>()
+        .init_resource::<ShowDanger>()
+        .init_resource::<EnemyQueue>()
+        .init_resource::<CameraFocus>()
         .init_resource::<InteractionTextures>()
+        .init_resource::<LevelPool>()
+        .configure_loading_state(LoadingStateConfig::new(GameState::Loading)
+            .load_collection::<BattleLevels>()
+        )
+        .add_systems(OnExit(GameState::Loading), init_level_pool)
         .register_ldtk_int_cell::<WallBundle>(1)
         .add_plugins(fight_plugin)
+        .add_plugins(trade_plugin)
+        .add_plugins(animation_plugin)
+        .add_plugins(hp_bar_plugin)
         .add_systems(OnEnter(BattleState::Loading), (init_battle, init_ui))
         // TODO: Should we force this to run when the level loads
         // and not run any other update code until it's done?
         .add_systems(Update, (
             init_level_walls,
-            init_units_on_map,
+            init_units_on_map.run_if(not(resource_exists::<PendingBattle>)),
+            restore_units_on_map.run_if(resource_exists::<PendingBattle>),
             transition_to_game
         ).run_if(in_state(BattleState::Loading)))
         .add_systems(Update, (
-            exit_to_menu,
-            move_screen_rts,
-            zoom_in_scroll_wheel,
+            suspend_battle,
+            cycle_unready_units,
             add_queued_movement_target_to_entity,
             lerp_queued_movement,
             highlight_range,
@@ -157,38 +212,79 @@ pub fn battle_scene_plugin(app: &mut App) {
             hover_unit,
             removed_hovered_unit,
             check_for_team_refresh,
-            update_hovered_unit,
+            // Moving straight from one unit to another hides the view, then fills it again
+            (update_hovered_unit, place_detail_view).chain().after(removed_hovered_unit),
+            check_battle_outcome,
         ).run_if(in_state(BattleState::Select)))
         .add_systems(Update, (
-            confirm_movement_or_attack
+            toggle_danger_zone,
+            refresh_danger_zone.run_if(resource_changed::<ShowDanger>),
+        ).chain().run_if(in_state(BattleState::Select)))
+        // Enemies have moved by the time the player gets control back
+        .add_systems(OnEnter(BattleState::Select), refresh_danger_zone)
+        .add_systems(Update, (
+            confirm_movement_or_attack,
+            rescue_or_drop,
         ).run_if(in_state(BattleState::ConfirmMovement)))
+        // Only once the battle is out of Loading, so init_units_on_map can't pick up the restored units
+        .add_systems(OnExit(BattleState::Loading), (clear_pending_battle, center_camera))
+        .add_systems(Update, (
+            zoom_in_scroll_wheel,
+            drag_camera,
+            pan_camera_keys,
+            move_screen_rts,
+            focus_on_acting_unit,
+            follow_camera_focus,
+            clamp_camera,
+        ).chain().run_if(in_state(GameState::InBattle).and(not(in_state(BattleState::Loading)))))
         .add_systems(OnExit(BattleState::EnemyTurn), refresh_units)
         .add_systems(OnExit(BattleState::Select), dehilight_range)
         .add_systems(OnExit(BattleState::ConfirmMovement), dehilight_range)
-        .add_systems(OnEnter(BattleState::ToEnemyTurn), setup_transition_animation)
-        .add_systems(OnEnter(BattleState::ToPlayerTurn), setup_transition_animation)
+        .add_systems(OnEnter(BattleState::ToEnemyTurn), (setup_transition_animation, count_turn))
+        .add_systems(OnEnter(BattleState::ToPlayerTurn), (setup_transition_animation, next_battle_turn))
         .add_systems(OnEnter(BattleState::ConfirmMovement), show_attack_highlight)
         .add_systems(Update, (
-            enemy_turn
+            enemy_turn,
+            lerp_queued_movement,
+            check_battle_outcome,
         ).run_if(in_state(BattleState::EnemyTurn)))
         .add_sub_state::<BattleState>()
-        .add_systems(OnExit(GameState::InBattle), (despawn_screen::<OnLevelScreen>, reset_game))
+        .add_systems(OnExit(GameState::InBattle), (
+            write_back_roster,
+            despawn_screen::<OnLevelScreen>,
+            reset_game
+        ).chain())
         .add_systems(Update, (
             transition_animation,
             menu_action,
             // dehilight_range,
         ).run_if(in_state(GameState::InBattle)))
+        .add_systems(Update, move_grid_cursor.run_if(
+            in_state(BattleState::Select).or(in_state(BattleState::ConfirmMovement))
+        ))
         .add_systems(Update, spawn_cursor_sprite.run_if(cursor_sprite_not_yet_spawned))
         .add_systems(Update, update_cursor_sprite.run_if(resource_exists_and_changed::<MouseGridCoords>))
-        .add_systems(Update, track_mouse_coords);
+        // Before Update so every battle system sees the same confirm and cancel
+        .add_systems(PreUpdate, (read_cursor_input, track_mouse_coords).chain().after(ActionSystem));
 }
 
-// Loads the given ldtk file
+// Loads a level from the level pool, or generates one. A suspended battle
+// reloads the level it was on instead.
 // Must run before init_level_walls and init_units_on_map
+#[allow(clippy::too_many_arguments)]
 fn init_battle(
     mut commands: Commands, 
-    mut q: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
     mut map_interactions: ResMut<InteractionTextures>,
+    mut level_walls: ResMut<LevelWalls>,
+    mut components_loaded: ResMut<BattleComponentsLoaded>,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    mut level_pool: ResMut<LevelPool>,
+    mut rng: ResMut<BattleRng>,
+    mut turn: ResMut<BattleTurn>,
+    pending: Option<Res<PendingBattle>>,
+    run_map: Res<RunMap>,
+    roster: Res<RunRoster>,
+    battle_levels: Res<BattleLevels>,
     assert_server: Res<AssetServer>, 
 ) {
     map_interactions.attack_highlight = assert_server.load("tilesets/attack_highlight.png");
@@ -196,25 +292,75 @@ fn init_battle(
     map_interactions.cursor = assert_server.load("cursor.png");
 
     info!("Initialzing the battle");
-    commands.spawn((
-        LdtkWorldBundle {
-            ldtk_handle: LdtkProjectHandle { handle: assert_server.load("test_level.ldtk")},
-            ..Default::default()
+    let level = match pending {
+        Some(pending) => {
+            info!("Resuming a suspended battle");
+            *rng = pending.0.rng;
+            turn.0 = pending.0.turn;
+            pending.0.level.clone()
         },
-        OnLevelScreen
-    ));
+        None => {
+            *rng = BattleRng::new(rand::random());
+            turn.0 = 1;
+            let (choice, depth) = match run_map.current {
+                Some(current) => {
+                    let depth = run_map.nodes[current].layer;
+                    (level_pool.pick(depth, roster.units.len(), &mut rand::rng()), depth)
+                },
+                None => {
+                    let choice = level_pool.levels.first().cloned().map_or(LevelChoice::Generated, LevelChoice::Ldtk);
+                    (choice, 0)
+                },
+            };
+
+            match choice {
+                LevelChoice::Ldtk(info) => {
+                    info!("Loading {} ({}, tier {})", info.identifier, info.biome, info.difficulty);
+                    BattleLevel::Ldtk(info.iid)
+                },
+                // Deeper nodes bring more enemies
+                LevelChoice::Generated => BattleLevel::Generated {
+                    seed: run_map.seed ^ (run_map.current.unwrap_or(0) as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+                    enemies: BASE_ENEMY_COUNT + depth / 2,
+                },
+            }
+        },
+    };
+
+    match &level {
+        BattleLevel::Ldtk(iid) => {
+            commands.insert_resource(LevelSelection::iid(iid.clone()));
+            commands.spawn((
+                LdtkWorldBundle {
+                    ldtk_handle: LdtkProjectHandle { handle: battle_levels.project.clone() },
+                    ..Default::default()
+                },
+                OnLevelScreen
+            ));
+        },
+        BattleLevel::Generated { seed, enemies } => {
+            let level = GeneratedLevel::generate(
+                *seed,
+                GENERATED_LEVEL_WIDTH,
+                GENERATED_LEVEL_HEIGHT,
+                *enemies,
+            );
+            spawn_generated_level(
+                &mut commands,
+                &level,
+                &mut level_walls,
+                &mut components_loaded,
+                &assert_server,
+                &mut texture_atlases,
+            );
+        },
+    }
+    commands.insert_resource(CurrentLevel(level));
 
     commands.spawn((
         Teams::new(),
         OnLevelScreen
     ));
-
-    // TODO: Remove the transform stuff here since it's
-    // not needed anymore
-    let (mut transform, mut proj) = q.single_mut();
-    transform.translation.x += 100.0 / 4.0;
-    transform.translation.x += 50.0 / 4.0;
-    proj.scale = 0.5;
 }
 
 fn refresh_units(
@@ -223,17 +369,28 @@ fn refresh_units(
     team_q.single_mut().clear();
 }
 
-fn reset_game(mut components_loaded: ResMut<BattleComponentsLoaded>) {
+fn reset_game(
+    mut components_loaded: ResMut<BattleComponentsLoaded>,
+    mut units_on_map: ResMut<UnitsOnMap>,
+    mut enemy_queue: ResMut<EnemyQueue>,
+) {
     components_loaded.0 = 0;
+    units_on_map.clear();
+    // A battle can end partway through the enemy phase
+    *enemy_queue = EnemyQueue::default();
 }
 
 fn transition_to_game(
     mut state: ResMut<NextState<BattleState>>,
-    components_loaded: Res<BattleComponentsLoaded>
+    components_loaded: Res<BattleComponentsLoaded>,
+    pending: Option<Res<PendingBattle>>,
 ) {
     info!("{} >= {}", components_loaded.0, REQUIRED_BATTLE_COMPONENTS);
     if components_loaded.0 >= REQUIRED_BATTLE_COMPONENTS {
-        info!("Starting game and transition over to select state");
+        match pending {
+            Some(pending) => info!("Resuming the battle on turn {}", pending.0.turn),
+            None => info!("Starting game and transition over to select state"),
+        }
         state.set(BattleState::Select);
     }
 }
@@ -262,13 +419,11 @@ fn init_level_walls(
 
             let wall_locations: HashSet<GridCoords> = walls.iter().copied().collect();
 
-            let new_level_walls = LevelWalls {
-                wall_locations,
-                level_width: level.px_wid / GRID_SIZE,
-                level_height: level.px_hei / GRID_SIZE,
-            };
-
-            *level_walls = new_level_walls;
+            *level_walls = LevelWalls::new(
+                level.px_hei / GRID_SIZE,
+                level.px_wid / GRID_SIZE,
+                Some(wall_locations)
+            );
         }
     }
     if loaded_walls {
@@ -276,22 +431,35 @@ fn init_level_walls(
     }
 }
 
-fn enemy_turn(
-    mut game: ResMut<NextState<BattleState>>
+/// Ends the battle once either side has no units left
+fn check_battle_outcome(
+    mut game_state: ResMut<NextState<GameState>>,
+    mut run_stats: ResMut<RunStats>,
+    mut roster: ResMut<RunRoster>,
+    run_map: Res<RunMap>,
+    player_q: Query<(), With<Player>>,
+    enemy_q: Query<(), With<Enemy>>,
 ) {
-    info!("Calculating the enemies turn");
-    game.set(BattleState::ToPlayerTurn);
+    if player_q.is_empty() {
+        info!("All player units have fallen");
+        game_state.set(GameState::Rewards);
+    } else if enemy_q.is_empty() {
+        info!("All enemies defeated");
+        run_stats.battles_won += 1;
+        roster.gold += match run_map.current_node_type() {
+            Some(NodeType::EliteBattle) => ELITE_BATTLE_GOLD,
+            _ => BATTLE_GOLD,
+        };
+        game_state.set(GameState::ChestSelection);
+    }
 }
 
-fn exit_to_menu(
-    mut game_state: ResMut<NextState<AppState>>,
-    keys: Res<ButtonInput<KeyCode>>,
-    mut map: ResMut<UnitsOnMap>
-) {
-    if keys.pressed(KeyCode::Escape) {
-        map.clear();
-        game_state.set(AppState::Menu);
-    }
+fn count_turn(mut run_stats: ResMut<RunStats>) {
+    run_stats.turns += 1;
+}
+
+fn next_battle_turn(mut turn: ResMut<BattleTurn>) {
+    turn.0 += 1;
 }
 
 fn menu_action(
@@ -3,11 +3,14 @@ use bevy::window::PrimaryWindow;
 use bevy_ecs_ldtk::{prelude::*, utils::grid_coords_to_translation, utils::translation_to_grid_coords};
 
 use super::{MouseGridCoords, Selected, Hovered};
+use super::cursor::CursorInput;
 use super::map::UnitsOnMap;
-use super::ui::{DetailView, Stats};
+use super::ui::{spawn_detail_rows, unit_detail_rows, DetailView};
+use crate::game::ui::tooltip_position;
 use crate::game::{GRID_SIZE, GRID_SIZE_VEC};
-use crate::game::units::{UnitStats, WeaponPack};
-use crate::game::Teams;
+use crate::game::units::{Carrying, MaxHp, UnitStats, WeaponPack};
+use crate::game::{Player, Teams};
+use crate::game::rules::CombatRules;
 
 #[derive(Component)]
 pub struct MouseCursor;
@@ -56,9 +59,13 @@ pub fn update_cursor_sprite(
 
 pub fn track_mouse_coords(
     mut mouse_coords: ResMut<MouseGridCoords>,
+    input: Res<CursorInput>,
     q_window: Query<&Window, With<PrimaryWindow>>,
     q_camera: Query<(&Camera, &GlobalTransform), With<Camera>>
 ) {
+    // The keys or gamepad are moving the cursor
+    if input.keyboard_cursor { return }
+
     let (cam, cam_transform) = q_camera.single();
     let window = q_window.single();
 
@@ -73,33 +80,53 @@ pub fn track_mouse_coords(
     }
 }
 
-// TODO: Move this to UI?
-// TODO: Add actual stats in the box
+/// Fills the detail view with the newly hovered unit
 pub fn update_hovered_unit(
-    mut detail_view: Query<(&mut Visibility, &mut Node), With<DetailView>>,
-    mut stats_q: Query<&mut TextSpan, With<Stats>>,
-    unit_q: Query<(&UnitStats, &WeaponPack), Added<Hovered>>,
+    mut commands: Commands,
+    mut detail_view: Query<(Entity, &mut Visibility), With<DetailView>>,
+    unit_q: Query<(Entity, &UnitStats, &MaxHp, &WeaponPack, Has<Player>, Option<&Carrying>), Added<Hovered>>,
+    names_q: Query<&UnitStats>,
+    teams: Option<Single<&Teams>>,
+    rules: Res<CombatRules>,
+) {
+    let Some((unit, stats, max_hp, pack, is_player, carrying)) = unit_q.iter().next() else { return };
+    let (view, mut vis) = detail_view.single_mut();
+
+    let mut statuses = Vec::new();
+    if teams.is_some_and(|teams| teams.contains(&unit)) {
+        statuses.push("Acted this turn".to_string());
+    }
+    if let Some(ally) = carrying.and_then(|carrying| names_q.get(carrying.0).ok()) {
+        statuses.push(format!("Carrying {}: SPD/SKL halved", ally.name));
+    }
+
+    let rows = unit_detail_rows(stats, &rules, max_hp.0, pack, is_player, &statuses);
+    commands.entity(view)
+        .despawn_descendants()
+        .with_children(|parent| spawn_detail_rows(parent, rows));
+    *vis = Visibility::Visible;
+}
+
+/// Keeps the detail view next to the cursor tile and inside the window
+pub fn place_detail_view(
+    mut detail_view: Query<(&mut Node, &ComputedNode, &Visibility), With<DetailView>>,
+    mouse_coords: Res<MouseGridCoords>,
     window_q: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<Camera>>,
 ) {
-    if !unit_q.is_empty() {
-        let (mut vis, mut node) = detail_view.single_mut();
-        let window_pos = window_q.single().cursor_position().unwrap_or(Vec2::new(0.0, 0.0));
-        let (stats, pack) = unit_q.single();
-        let mut stats_view = stats_q.single_mut();
-
-        *vis = Visibility::Visible;
-        node.left = Val::Px(window_pos.x);
-        node.top = Val::Px(window_pos.y);
-        let stats_detailed = format!(
-            "HP: {}\nATK: {}\nDEF: {}\nSPD: {}\nMOV: {}", stats.hp, stats.atk, stats.def, stats.spd, stats.mov
-        );
-
-        let mut weapon_details = String::new();
-        for w in pack.weapons.iter() {
-            weapon_details = format!("{}\n{:?}", weapon_details, w);
-        }
+    let (mut node, computed, vis) = detail_view.single_mut();
+    if *vis == Visibility::Hidden { return }
 
-        **stats_view = format!("{}\n{}", stats_detailed, weapon_details);
+    let window = window_q.single();
+    let (cam, cam_transform) = q_camera.single();
+    let tile = grid_coords_to_translation(mouse_coords.0, IVec2::splat(GRID_SIZE));
+    let Ok(anchor) = cam.world_to_viewport(cam_transform, tile.extend(0.)) else { return };
+
+    let size = computed.size() * computed.inverse_scale_factor();
+    let position = tooltip_position(anchor, size, Vec2::new(window.width(), window.height()));
+    if node.left != Val::Px(position.x) || node.top != Val::Px(position.y) {
+        node.left = Val::Px(position.x);
+        node.top = Val::Px(position.y);
     }
 }
 
@@ -118,14 +145,14 @@ pub fn select_unit(
     mut commands: Commands,
     units_on_map: Res<UnitsOnMap>,
     mouse_coords: Res<MouseGridCoords>,
-    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    input: Res<CursorInput>,
     selected_q: Query<Entity, With<Selected>>,
     teams_q: Query<&Teams>
 ) {
     let teams = teams_q.single();
 
     // TODO: This looks horrendous
-    if mouse_buttons.just_pressed(MouseButton::Left) {
+    if input.confirm {
         debug!("Pressed tile: {:?}", mouse_coords);
         if let Some(entity) = units_on_map.get(&mouse_coords.0) {
             if !teams.contains(&entity) && units_on_map.is_player(&mouse_coords.0) {
@@ -137,7 +164,7 @@ pub fn select_unit(
                 }
             }
         }
-    } else if mouse_buttons.just_pressed(MouseButton::Right) && !selected_q.is_empty() {
+    } else if input.cancel && !selected_q.is_empty() {
         for entity in selected_q.iter() {
             commands.entity(entity).remove::<Selected>();
         }
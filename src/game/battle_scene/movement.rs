@@ -1,15 +1,17 @@
-use bevy::utils::HashSet;
-use bevy::{prelude::*, time::Stopwatch, utils::HashMap};
+use bevy::{prelude::*, time::Stopwatch};
 use bevy_ecs_ldtk::{prelude::*, utils::grid_coords_to_translation, utils::translation_to_grid_coords};
-use std::{cmp::Ordering, collections::VecDeque};
+use std::collections::{HashSet, VecDeque};
 use std::ops::Sub;
-use std::collections::BinaryHeap;
 
 use super::{BattleState, InteractionTextures, LevelWalls, MouseGridCoords, Selected, Teams, UnitType, UnitsOnMap};
 use crate::game::battle_scene::fight::{Attacker, Defender};
+use crate::game::battle_scene::cursor::CursorInput;
+use crate::game::battle_scene::trade::TradePartner;
 use crate::game::units::WeaponPack;
-use crate::game::weapon::WeaponRange;
-use crate::game::{GRID_SIZE, units::UnitStats, GRID_SIZE_VEC};
+use sim::WeaponRange;
+use sim::{attack_range, attack_range_from_coord, movement_path, movement_range};
+use crate::util::{manhattan_dist, ToGrid, ToSim};
+use crate::game::{GRID_SIZE, units::UnitStats, GRID_SIZE_VEC, Player, Settings};
 
 #[derive(Component)]
 pub struct QueuedMovementTarget {
@@ -34,12 +36,12 @@ pub fn add_queued_movement_target_to_entity(
     mut commands: Commands,
     // NOTE: Could this be an Option<Single<>>?
     highlight_bag_q: Query<&HighlightBag>,
-    buttons: Res<ButtonInput<MouseButton>>,
+    input: Res<CursorInput>,
     mouse_coords: Res<MouseGridCoords>,
     walls: Res<LevelWalls>,
     entities: Query<(Entity, &GridCoords, &UnitStats), With<Selected>>,
 ) {
-    if buttons.just_pressed(MouseButton::Left) {
+    if input.confirm {
         let bag = highlight_bag_q.iter().next();
         if bag.is_none() {
             return
@@ -51,10 +53,8 @@ pub fn add_queued_movement_target_to_entity(
                 return;
             }
 
-            let unit_move = unit_stats.mov.try_into().unwrap();
-
-            if let Some(targets) = get_movement_path(mouse_coords.0, *current_coords, &walls, unit_move) {
-                let mut queue = VecDeque::from(targets);
+            if let Some(targets) = movement_path(mouse_coords.0.to_sim(), current_coords.to_sim(), &*walls, unit_stats.mov) {
+                let mut queue: VecDeque<GridCoords> = targets.iter().map(ToGrid::to_grid).collect();
                 queue.pop_front();
                 commands.entity(entity).insert(QueuedMovementTarget {
                     targets: queue,
@@ -68,134 +68,23 @@ pub fn add_queued_movement_target_to_entity(
     }
 }
 
-fn manhattan_dist(start: GridCoords, end: GridCoords) -> i32 {
-    (end.x - start.x).abs() + (end.y - start.y).abs()
-}
-
-#[derive(Copy, Clone, Eq, PartialEq)]
-struct PathState {
-    // TODO: Rename this to cost
-    cost: i32,
-    coords: GridCoords
-}
-
-// TODO: Maybe i gotta flip this for a min heap?
-impl Ord for PathState {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.cost.cmp(&self.cost)
-    }
-}
-
-impl PartialOrd for PathState {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-
-fn get_movement_path(
-    target_coords: GridCoords,
-    start_coords: GridCoords,
-    walls: &Res<LevelWalls>,
-    max_dist: i32
-) -> Option<Vec<GridCoords>> {
-    let mut f_scores: HashMap<GridCoords, i32> = HashMap::new();
-    let mut g_scores: HashMap<GridCoords, i32> = HashMap::new();
-    let mut came_from: HashMap<GridCoords, GridCoords> = HashMap::new();
-    let mut queue: BinaryHeap<PathState> = BinaryHeap::new();
-
-    f_scores.insert(start_coords, manhattan_dist(start_coords, target_coords));
-    g_scores.insert(start_coords, 0);
-    queue.push(PathState { cost: 0, coords: start_coords });
-
-    while queue.len() > 0 {
-        let curr = match queue.pop() {
-            Some(c) => c.coords,
-            None => continue,
-        };
-
-        if curr == target_coords {
-            let res = resolve_path(came_from, curr);
-            return Some(res);
-        }
-
-        for next_coord in get_neighbors(curr) {
-            if walls.in_wall(&next_coord) {
-                continue;
-            }
-
-            if let Some(curr_g_score) = g_scores.get(&curr) {
-                if *curr_g_score >= max_dist {
-                    break;
-                }
-
-                // TODO: 1 is the weight of the edge. In our case this should change depending on
-                // the cost of the tile to move into
-                let next_g_score = curr_g_score + 1;
-                let neighbor_g_score = g_scores.entry(next_coord).or_insert(i32::MAX);
-                let neighbor_f_score = f_scores.entry(next_coord).or_insert(i32::MAX);
-
-                if next_g_score < *neighbor_g_score {
-                    *came_from.entry(next_coord).or_insert(curr) = curr;
-                    *neighbor_g_score = next_g_score;
-                    *neighbor_f_score = next_g_score + manhattan_dist(next_coord, target_coords);
-
-                    queue.push(PathState {
-                        cost: next_g_score + manhattan_dist(next_coord, target_coords),
-                        coords: next_coord
-                    });
-                }
-            }
-        }
-    }
-    None
-}
-
-
-// TODO: Make this look nicer
-fn resolve_path(came_from: HashMap<GridCoords, GridCoords>, target: GridCoords) -> Vec<GridCoords> {
-    let mut path: Vec<GridCoords> = Vec::new();
-    let mut curr = target;
-    path.push(curr);
-
-    while came_from.contains_key(&curr) {
-        curr = match came_from.get(&curr) {
-            Some(c) => *c,
-            None => break,
-        };
-
-        path.push(curr);
-    }
-
-    path.reverse();
-    path
-}
-
-fn get_neighbors(center: GridCoords) -> Vec<GridCoords> {
-    vec![
-        center + GridCoords::new(0, -1),
-        center + GridCoords::new(0, 1),
-        center + GridCoords::new(-1, 0),
-        center + GridCoords::new(1, 0),
-    ]
-}
-
 pub fn lerp_queued_movement(
     mut commands: Commands,
     // NOTE: Transform will keep the current location of the Unit, but GridCoords will keep it's
     // original position
-    mut query: Query<(Entity, &mut Transform, &mut QueuedMovementTarget)>,
+    mut query: Query<(Entity, &mut Transform, &mut QueuedMovementTarget, Has<Player>)>,
     time: Res<Time>,
     mut state: ResMut<NextState<BattleState>>,
+    settings: Res<Settings>,
 ) {
-    for (entity, mut transform, mut target) in query.iter_mut() {
+    for (entity, mut transform, mut target, is_player) in query.iter_mut() {
         if let Some(dest_target) = target.targets.front() {
             let time_delta = time.delta_secs();
             let target_in_world = grid_coords_to_translation(
                 *dest_target,
                 IVec2::splat(GRID_SIZE)).extend(transform.translation.z
             );
-            let translation = target_in_world.sub(transform.translation).normalize() * (time_delta * target.speed);
+            let translation = target_in_world.sub(transform.translation).normalize() * (time_delta * target.speed * settings.animation_speed);
             transform.translation += translation;
 
             // NOTE: What about moving down or left?
@@ -213,7 +102,10 @@ pub fn lerp_queued_movement(
                 if target.targets.len() == 1 {
                     // Stopping the movement
                     commands.entity(entity).remove::<QueuedMovementTarget>();
-                    state.set(BattleState::ConfirmMovement);
+                    // Enemies have already committed to their move
+                    if is_player {
+                        state.set(BattleState::ConfirmMovement);
+                    }
                 }
                 target.targets.pop_front();
             }
@@ -228,96 +120,10 @@ fn calculate_range(
     units_on_map: &UnitsOnMap,
     walls: &LevelWalls,
 ) -> HashSet<GridCoords> {
-    let max_dist = stats.mov;
-    let mut curr_dist = 0;
-    let mut queue: VecDeque<GridCoords> = VecDeque::new();
-    // using aHash
-    let mut range_of_movement: HashSet<GridCoords> = HashSet::new();
-
-    queue.push_back(*origin);
-    while curr_dist <= max_dist {
-        let mut next_queue: VecDeque<GridCoords> = VecDeque::new();
-
-        while let Some(center) = queue.pop_back() {
-            let neighbors = get_neighbors(center);
-
-            if !units_on_map.contains(&center) || center == *origin {
-                range_of_movement.insert(center);
-            }
-
-            // TODO: Add 'is_hostile' to units on map that can check if a unit exists in the space
-            // that is hostile to the given faction
-            // Why didn't i just make this a for loop?
-            if !(units_on_map.is_enemy(&neighbors[0]) || walls.in_wall(&neighbors[0])) {
-                next_queue.push_back(neighbors[0]);
-            }
-            if !(units_on_map.is_enemy(&neighbors[1]) || walls.in_wall(&neighbors[1])) {
-                next_queue.push_back(neighbors[1]);
-            }
-            if !(units_on_map.is_enemy(&neighbors[2]) || walls.in_wall(&neighbors[2])) {
-                next_queue.push_back(neighbors[2]);
-            }
-            if !(units_on_map.is_enemy(&neighbors[3]) || walls.in_wall(&neighbors[3])) {
-                next_queue.push_back(neighbors[3]);
-            }
-        }
-
-        queue = next_queue;
-        curr_dist += 1;
-    }
-
-    range_of_movement
-}
-
-// This is gonna be a very dumb implementation. It's gonna check every box lol
-fn calculate_attack_range(weapon_range: WeaponRange, movement_range: &HashSet<GridCoords>) -> HashSet<GridCoords>{
-    let (min_dist, max_dist) = match weapon_range {
-        WeaponRange::Melee(d) => (0,d),
-        WeaponRange::Ranged{ min, max } => (min, max),
-    };
-
-
-    // using aHash
-    let mut range_of_attack: HashSet<GridCoords> = HashSet::new();
-
-    for coord in movement_range.iter() {
-        let filtered_coords = calculate_attack_range_from_coord(*coord, min_dist, max_dist)
-            .into_iter().filter(|item| 
-            !movement_range.contains(item)
-        );
-
-        for coord in filtered_coords {
-            range_of_attack.insert(coord);
-        }
-    }
-
-    range_of_attack
-}
-
-pub fn calculate_attack_range_from_coord(origin: GridCoords, min_dist: u32, max_dist: u32) -> HashSet<GridCoords> {
-    let mut curr_dist = 1;
-    let mut queue: VecDeque<GridCoords> = VecDeque::new();   
-    let mut attack_range: HashSet<GridCoords> = HashSet::new();
-
-    queue.push_back(origin);
-    while curr_dist <= max_dist {
-        let mut next_queue: VecDeque<GridCoords> = VecDeque::new();
-        while let Some(center) = queue.pop_back() {
-            for neighbor in get_neighbors(center) {
-                if !attack_range.contains(&neighbor) && neighbor != origin {
-                    next_queue.push_back(neighbor);
-                    if curr_dist >= min_dist {
-                        attack_range.insert(neighbor);
-                    }
-                }
-            }
-        }
-
-        queue = next_queue;
-        curr_dist += 1;
-    }
-
-    attack_range
+    movement_range(origin.to_sim(), stats.mov, UnitType::Player, units_on_map, walls)
+        .iter()
+        .map(ToGrid::to_grid)
+        .collect()
 }
 
 pub fn highlight_range(
@@ -340,7 +146,10 @@ pub fn highlight_range(
             }
 
             let range: HashSet<GridCoords> = calculate_range(grid_coords, unit, map, walls);
-            let attack_range: HashSet<GridCoords> = calculate_attack_range(weapons.get_equipped().range, &range);
+            let attack_range: HashSet<GridCoords> = attack_range(weapons.get_equipped().range, &range.iter().map(ToSim::to_sim).collect())
+                .iter()
+                .map(ToGrid::to_grid)
+                .collect();
             // NOTE: Can't I just pass ownership to the struct, why am I cloning thing?
             layer_entity.with_child(HighlightBag(range.clone()));
             layer_entity.with_child(AttackHighlightBag(attack_range.clone()));
@@ -407,22 +216,25 @@ pub fn confirm_movement_or_attack(
     mut player_team_q: Query<&mut Teams>,
     mouse_coords: Res<MouseGridCoords>,
     single: Single<(Entity, &mut Transform, &mut GridCoords), With<Selected>>,
-    buttons: Res<ButtonInput<MouseButton>>,
+    input: Res<CursorInput>,
 ) {
     let (entity, mut transform, mut coords) = single.into_inner();
 
-    if buttons.just_pressed(MouseButton::Left) {
-        debug!("Left button clicked for confirm!");
-        commands.entity(entity).remove::<Selected>();
-
+    if input.confirm {
+        debug!("Confirming the move");
         let dest_coords = translation_to_grid_coords(transform.translation.xy(), GRID_SIZE_VEC);
-        units_on_map.remove(&coords);
-        units_on_map.add(&dest_coords, entity, UnitType::Player);
-        // When team is full then we end the turn
-        let mut team = player_team_q.single_mut();
-        team.add(entity);
 
-        *coords = dest_coords;
+        if let Some(ally) = units_on_map.get_player(&mouse_coords.0) {
+            if ally != entity && manhattan_dist(dest_coords, mouse_coords.0) == 1 {
+                info!("Trading with ally");
+                commands.entity(ally).insert(TradePartner);
+                state.set(BattleState::Trade);
+                return;
+            }
+        }
+
+        let mut team = player_team_q.single_mut();
+        end_unit_move(&mut commands, entity, dest_coords, &mut coords, &mut units_on_map, &mut team);
 
         if let Some(enemy) = units_on_map.get_enemy(&mouse_coords.0) {
             info!("Attacking unit!");
@@ -433,8 +245,8 @@ pub fn confirm_movement_or_attack(
             info!("Confirming movement");
             state.set(BattleState::Select);
         }
-    } else if buttons.just_pressed(MouseButton::Right) {
-        info!("Right button clicked for cancel!");
+    } else if input.cancel {
+        info!("Cancelling the move");
         commands.entity(entity).remove::<Selected>();
 
         let original_position = grid_coords_to_translation(
@@ -447,6 +259,24 @@ pub fn confirm_movement_or_attack(
     }
 }
 
+/// Moves the Selected unit to dest_coords on the map and marks it as having acted
+pub fn end_unit_move(
+    commands: &mut Commands,
+    entity: Entity,
+    dest_coords: GridCoords,
+    coords: &mut GridCoords,
+    units_on_map: &mut UnitsOnMap,
+    team: &mut Teams,
+) {
+    commands.entity(entity).remove::<Selected>();
+    units_on_map.remove(coords);
+    units_on_map.add(&dest_coords, entity, UnitType::Player);
+    // When team is full then we end the turn
+    team.add(entity);
+
+    *coords = dest_coords;
+}
+
 pub fn show_attack_highlight(
     mut commands: Commands,
     single: Single<(&Transform, &WeaponPack), With<Selected>>,
@@ -467,7 +297,10 @@ pub fn show_attack_highlight(
     };
     let origin = translation_to_grid_coords(transform.translation.xy(), GRID_SIZE_VEC);
 
-    let attack_range: HashSet<GridCoords> = calculate_attack_range_from_coord(origin, min_dist, max_dist);
+    let attack_range: HashSet<GridCoords> = attack_range_from_coord(origin.to_sim(), min_dist, max_dist)
+        .iter()
+        .map(ToGrid::to_grid)
+        .collect();
     for coord in attack_range.iter() {
         layer_entity.with_children(|p| {
             create_highlight_tile(p, *coord, highlight_texture_handles.attack_highlight.clone());
@@ -481,9 +314,7 @@ mod test {
     #[allow(unused_imports)]
     use bevy_ecs_ldtk::GridCoords;
     #[allow(unused_imports)]
-    use bevy::utils::HashSet;
-    #[allow(unused_imports)]
-    use crate::game::battle_scene::movement::calculate_attack_range_from_coord;
+    use std::collections::HashSet;
     #[allow(unused_imports)]
     use crate::game::{battle_scene::{map::UnitsOnMap, LevelWalls}, units::UnitStats};
     #[allow(unused_imports)]
@@ -493,7 +324,7 @@ mod test {
     fn test_caculate_range_one() {
         let walls = LevelWalls::new(7, 7, None);
         let units_on_map = UnitsOnMap::new();
-        let unit_stats = UnitStats { mov: 1, ..Default::default()};
+        let unit_stats = UnitStats(sim::UnitStats { mov: 1, ..Default::default()});
 
         let range = calculate_range(
             &GridCoords::new(4, 4),
@@ -516,7 +347,7 @@ mod test {
     fn test_caculate_range_two() {
         let walls = LevelWalls::new(7, 7, None);
         let units_on_map = UnitsOnMap::new();
-        let unit_stats = UnitStats { mov: 2, ..Default::default()};
+        let unit_stats = UnitStats(sim::UnitStats { mov: 2, ..Default::default()});
 
         let range = calculate_range(
             &GridCoords::new(4, 4),
@@ -548,7 +379,7 @@ mod test {
     fn test_caculate_range_one_with_walls() {
         let mut walls = LevelWalls::new(7, 7, None);
         let units_on_map = UnitsOnMap::new();
-        let unit_stats = UnitStats { mov: 1, ..Default::default()};
+        let unit_stats = UnitStats(sim::UnitStats { mov: 1, ..Default::default()});
         walls.insert(GridCoords { x: 3, y: 4 });
         walls.insert(GridCoords { x: 4, y: 5 });
 
@@ -566,88 +397,4 @@ mod test {
         ]);
         assert_eq!(range, test);
     }
-
-    #[test]
-    fn test_caculate_attack_range_one() {
-        let range = calculate_attack_range_from_coord(
-            GridCoords { x: 2, y: 2 },
-            0,
-            1
-        );
-
-        let test: HashSet<GridCoords> = HashSet::from_iter(vec![
-            GridCoords::new(1, 2),
-            GridCoords::new(2, 1),
-            GridCoords::new(3, 2),
-            GridCoords::new(2, 3),
-        ]);
-        assert_eq!(range, test);
-    }
-
-    #[test]
-    fn test_caculate_attack_range_two_min_one() {
-        let range = calculate_attack_range_from_coord(
-            GridCoords::new(2, 2),
-            2,
-            2
-        );
-
-        let test: HashSet<GridCoords> = HashSet::from_iter(vec![
-            GridCoords::new(0, 2),
-            GridCoords::new(2, 0),
-            GridCoords::new(4, 2),
-            GridCoords::new(2, 4),
-            GridCoords::new(3, 3),
-            GridCoords::new(1, 1),
-            GridCoords::new(1, 3),
-            GridCoords::new(3, 1),
-        ]);
-        assert_eq!(range, test);
-    }
-
-    #[test]
-    fn test_caculate_attack_range_two() {
-        let range = calculate_attack_range_from_coord(
-            GridCoords::new(2, 2),
-            0,
-            2
-        );
-
-        let test: HashSet<GridCoords> = HashSet::from_iter(vec![
-            GridCoords::new(1, 2),
-            GridCoords::new(2, 1),
-            GridCoords::new(3, 2),
-            GridCoords::new(2, 3),
-            GridCoords::new(0, 2),
-            GridCoords::new(2, 0),
-            GridCoords::new(4, 2),
-            GridCoords::new(2, 4),
-            GridCoords::new(3, 3),
-            GridCoords::new(1, 1),
-            GridCoords::new(1, 3),
-            GridCoords::new(3, 1),
-        ]);
-        assert_eq!(range, test);
-    }
-
-    #[test]
-    fn test_caculate_attack_range_two_min_two() {
-        let range = calculate_attack_range_from_coord(
-            GridCoords::new(2, 2),
-            2,
-            2
-        );
-
-        let test: HashSet<GridCoords> = HashSet::from_iter(vec![
-            GridCoords::new(0, 2),
-            GridCoords::new(2, 0),
-            GridCoords::new(4, 2),
-            GridCoords::new(2, 4),
-            GridCoords::new(3, 3),
-            GridCoords::new(1, 1),
-            GridCoords::new(1, 3),
-            GridCoords::new(3, 1),
-        ]);
-        assert_eq!(range, test);
-    }
 }
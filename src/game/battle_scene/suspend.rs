@@ -0,0 +1,236 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_ldtk::utils::grid_coords_to_translation;
+use serde::{Deserialize, Serialize};
+
+use super::fight::BattleRng;
+use super::map::{unit_sprite, UnitsOnMap, ENEMY_SPRITE, PLAYER_SPRITE};
+use super::{BattleComponentsLoaded, BattleTurn, UnitType};
+use crate::game::map_selection::RunMap;
+use crate::game::roster::{RosterIndex, RunRoster};
+use crate::game::run_stats::RunStats;
+use crate::game::save::RunSave;
+use crate::game::support::Supports;
+use crate::game::units::{Carried, Carrying, Inventory, MaxHp, Teams, UnitBundle, UnitStats, WeaponPack};
+use crate::game::{Action, ActionState, Enemy, GameState, Player, GRID_SIZE_VEC};
+use crate::AppState;
+
+/// Where the current battle's level came from, so the same one can be rebuilt
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BattleLevel {
+    /// Level iid in the LDtk project
+    Ldtk(String),
+    Generated { seed: u64, enemies: usize },
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct CurrentLevel(pub BattleLevel);
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Faction {
+    Player,
+    Enemy,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SavedUnit {
+    pub stats: UnitStats,
+    /// Saves from before max HP was tracked on the map load as 0, which means stats.hp
+    #[serde(default)]
+    pub max_hp: u32,
+    pub coords: (i32, i32),
+    pub pack: WeaponPack,
+    pub inventory: Inventory,
+    pub faction: Faction,
+    pub roster_index: Option<usize>,
+    /// In the Teams moved-set
+    pub moved: bool,
+    /// Index in BattleSave::units of the unit being carried
+    pub carrying: Option<usize>,
+}
+
+/// A battle in progress, saved alongside the run when the player suspends. Suspending
+/// is only possible in Select, between actions, so a resumed battle always picks up there.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BattleSave {
+    pub level: BattleLevel,
+    pub units: Vec<SavedUnit>,
+    pub turn: u32,
+    pub rng: BattleRng,
+}
+
+/// Set when resuming a suspended battle. The next battle loads from it instead of
+/// picking a new level and placing units at their starting locations.
+#[derive(Resource)]
+pub struct PendingBattle(pub BattleSave);
+
+/// The Menu action saves the battle into the run save and goes back to the main menu.
+/// Only scheduled in Select, so no move, fight or enemy phase is ever half done.
+#[allow(clippy::too_many_arguments)]
+pub fn suspend_battle(
+    mut app_state: ResMut<NextState<AppState>>,
+    actions: Res<ActionState>,
+    mut map: ResMut<UnitsOnMap>,
+    level: Res<CurrentLevel>,
+    turn: Res<BattleTurn>,
+    rng: Res<BattleRng>,
+    team: Single<&Teams>,
+    units_q: Query<(
+        Entity, &UnitStats, &MaxHp, &GridCoords, &WeaponPack, &Inventory,
+        Has<Player>, Option<&RosterIndex>, Option<&Carrying>
+    )>,
+    roster: Res<RunRoster>,
+    run_map: Res<RunMap>,
+    stats: Res<RunStats>,
+    supports: Res<Supports>,
+) {
+    if !actions.pressed(Action::Menu) { return }
+
+    let entities: Vec<Entity> = units_q.iter().map(|(entity, ..)| entity).collect();
+    let units = units_q.iter()
+        .map(|(entity, stats, max_hp, coords, pack, inventory, is_player, roster_index, carrying)| SavedUnit {
+            stats: stats.clone(),
+            max_hp: max_hp.0,
+            coords: (coords.x, coords.y),
+            pack: pack.clone(),
+            inventory: inventory.clone(),
+            faction: if is_player { Faction::Player } else { Faction::Enemy },
+            roster_index: roster_index.map(|i| i.0),
+            moved: team.contains(&entity),
+            carrying: carrying.and_then(|c| entities.iter().position(|e| *e == c.0)),
+        })
+        .collect();
+
+    let mut save = RunSave::new(GameState::InBattle, &roster, &run_map, &stats, &supports);
+    save.battle = Some(BattleSave {
+        level: level.0.clone(),
+        units,
+        turn: turn.0,
+        rng: *rng,
+    });
+    match save.save() {
+        Ok(()) => info!("Suspended the battle on turn {}", turn.0),
+        Err(e) => error!("Unable to suspend the battle. {}", e),
+    }
+
+    map.clear();
+    app_state.set(AppState::Menu);
+}
+
+/// Puts the saved units on the reloaded level. The level's starting locations are reused
+/// for the units so they sit in the same layer, and any left over are removed. Units
+/// beyond the starting locations get new entities in that layer.
+#[allow(clippy::too_many_arguments)]
+pub fn restore_units_on_map(
+    mut commands: Commands,
+    mut components_loaded: ResMut<BattleComponentsLoaded>,
+    entity_query: Query<(Entity, &Transform, &Parent), Added<EntityInstance>>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    mut units_on_map: ResMut<UnitsOnMap>,
+    mut team_q: Query<&mut Teams>,
+    pending: Res<PendingBattle>,
+) {
+    if entity_query.is_empty() { return }
+
+    let spots: Vec<(Entity, Transform, Entity)> = entity_query.iter()
+        .map(|(entity, transform, parent)| (entity, *transform, parent.get()))
+        .collect();
+    let (_, template, layer) = spots[0];
+
+    let mut entities = Vec::new();
+    for (i, unit) in pending.0.units.iter().enumerate() {
+        let (entity, mut transform) = match spots.get(i) {
+            Some((entity, transform, _)) => (*entity, *transform),
+            None => (commands.spawn_empty().set_parent(layer).id(), template),
+        };
+
+        let coords = GridCoords::new(unit.coords.0, unit.coords.1);
+        transform.translation = grid_coords_to_translation(coords, GRID_SIZE_VEC).extend(transform.translation.z);
+
+        let sprite_index = match unit.faction {
+            Faction::Player => {
+                commands.entity(entity).insert(Player);
+                if let Some(index) = unit.roster_index {
+                    commands.entity(entity).insert(RosterIndex(index));
+                }
+                PLAYER_SPRITE
+            },
+            Faction::Enemy => {
+                commands.entity(entity).insert(Enemy);
+                ENEMY_SPRITE
+            },
+        };
+
+        commands.entity(entity).insert((
+            UnitBundle {
+                pack: unit.pack.clone(),
+                inventory: unit.inventory.clone(),
+                stats: unit.stats.clone(),
+                max_hp: MaxHp(unit.max_hp.max(unit.stats.hp)),
+                grid_coords: coords,
+            },
+            unit_sprite(&asset_server, &mut texture_atlases, sprite_index),
+            transform,
+        ));
+        entities.push(entity);
+    }
+
+    for (entity, ..) in spots.iter().skip(entities.len()) {
+        commands.entity(*entity).despawn_recursive();
+    }
+
+    let carried: Vec<usize> = pending.0.units.iter().filter_map(|u| u.carrying).collect();
+    let mut team = team_q.get_single_mut().ok();
+    for (i, unit) in pending.0.units.iter().enumerate() {
+        if unit.moved {
+            if let Some(team) = team.as_mut() {
+                team.add(entities[i]);
+            }
+        }
+
+        if let Some(carrying) = unit.carrying.and_then(|c| entities.get(c)) {
+            commands.entity(entities[i]).insert(Carrying(*carrying));
+            commands.entity(*carrying).insert((Carried, Visibility::Hidden));
+        }
+
+        // Carried units aren't on the map until they're dropped
+        if !carried.contains(&i) {
+            let unit_type = match unit.faction {
+                Faction::Player => UnitType::Player,
+                Faction::Enemy => UnitType::Enemy,
+            };
+            units_on_map.add(&GridCoords::new(unit.coords.0, unit.coords.1), entities[i], unit_type);
+        }
+    }
+
+    info!("Restored {} units on turn {}", entities.len(), pending.0.turn);
+    components_loaded.0 += 1;
+}
+
+pub fn clear_pending_battle(mut commands: Commands) {
+    commands.remove_resource::<PendingBattle>();
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use rand::Rng;
+    #[allow(unused_imports)]
+    use crate::game::battle_scene::fight::BattleRng;
+    #[allow(unused_imports)]
+    use crate::game::storage::{from_ron, to_ron};
+
+    #[test]
+    fn test_rng_resumes_with_same_rolls() {
+        let mut rng = BattleRng::new(77);
+        for _ in 0..10 {
+            rng.random_range(0..100);
+        }
+
+        let mut resumed: BattleRng = from_ron(&to_ron(&rng).unwrap()).unwrap();
+        let expected: Vec<u32> = (0..20).map(|_| rng.random_range(0..100)).collect();
+        let rolls: Vec<u32> = (0..20).map(|_| resumed.random_range(0..100)).collect();
+        assert_eq!(rolls, expected);
+        assert!(rolls.iter().any(|r| *r != rolls[0]));
+    }
+}
@@ -0,0 +1,229 @@
+use bevy::prelude::*;
+
+use crate::despawn_screen;
+use crate::game::units::{Inventory, UnitStats, WeaponPack};
+
+use super::{BattleState, Selected};
+use super::cursor::CursorInput;
+
+/// Tags the adjacent ally the Selected unit is trading with
+#[derive(Component)]
+pub struct TradePartner;
+
+#[derive(Component)]
+struct TradeWindow;
+
+#[derive(Clone, Copy)]
+enum TradeSide {
+    Selected,
+    Partner,
+}
+
+#[derive(Component)]
+enum TradeButton {
+    Weapon(TradeSide, usize),
+    Item(TradeSide, usize),
+    Done,
+}
+
+type TradeUnit<'a> = (&'a UnitStats, &'a WeaponPack, &'a Inventory);
+
+pub fn trade_plugin(app: &mut App) {
+    app
+        .add_systems(OnEnter(BattleState::Trade), init_trade_window)
+        .add_systems(Update, trade_action.run_if(in_state(BattleState::Trade)))
+        .add_systems(OnExit(BattleState::Trade), (despawn_screen::<TradeWindow>, clean_trade));
+}
+
+fn init_trade_window(
+    mut commands: Commands,
+    selected: Single<(&UnitStats, &WeaponPack, &Inventory), With<Selected>>,
+    partner: Single<(&UnitStats, &WeaponPack, &Inventory), (With<TradePartner>, Without<Selected>)>,
+) {
+    spawn_trade_window(&mut commands, selected.into_inner(), partner.into_inner());
+}
+
+fn spawn_trade_window(commands: &mut Commands, selected: TradeUnit, partner: TradeUnit) {
+    commands.spawn((
+        TradeWindow,
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+    )).with_children(|parent| {
+        parent.spawn((
+            Node {
+                width: Val::Percent(50.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::WHITE),
+        )).with_children(|parent| {
+            parent.spawn(Node {
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::SpaceBetween,
+                ..default()
+            }).with_children(|parent| {
+                create_trade_column(parent, TradeSide::Selected, selected);
+                create_trade_column(parent, TradeSide::Partner, partner);
+            });
+
+            create_trade_button(parent, "Done".to_string(), TradeButton::Done);
+        });
+    });
+}
+
+fn create_trade_column(parent: &mut ChildBuilder, side: TradeSide, unit: TradeUnit) {
+    let (stats, pack, inventory) = unit;
+
+    parent.spawn(Node {
+        width: Val::Percent(50.0),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn((
+            Text::new(stats.name.clone()),
+            TextColor(Color::BLACK),
+        ));
+
+        for (i, weapon) in pack.weapons.iter().enumerate() {
+            let label = if i == pack.equipped_index() {
+                format!("{} (E)", weapon.get_name())
+            } else {
+                weapon.get_name()
+            };
+            create_trade_button(parent, label, TradeButton::Weapon(side, i));
+        }
+
+        for (i, item) in inventory.items.iter().enumerate() {
+            create_trade_button(parent, item.get_name(), TradeButton::Item(side, i));
+        }
+    });
+}
+
+fn create_trade_button(parent: &mut ChildBuilder, label: String, action: TradeButton) {
+    parent.spawn((
+        Button,
+        Node {
+            width: Val::Px(200.0),
+            height: Val::Px(30.0),
+            margin: UiRect::all(Val::Px(5.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(Color::BLACK),
+        action,
+    )).with_children(|parent| {
+        parent.spawn((
+            Text::new(label),
+            TextFont {
+                font_size: 13.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        ));
+    });
+}
+
+// NOTE: Trading does not add the unit to Teams, so the unit can still act afterwards
+fn trade_action(
+    mut commands: Commands,
+    mut state: ResMut<NextState<BattleState>>,
+    interaction_query: Query<(&Interaction, &TradeButton), (Changed<Interaction>, With<Button>)>,
+    window_q: Query<Entity, With<TradeWindow>>,
+    input: Res<CursorInput>,
+    selected: Single<(&UnitStats, &mut WeaponPack, &mut Inventory), With<Selected>>,
+    partner: Single<
+        (&UnitStats, &mut WeaponPack, &mut Inventory),
+        (With<TradePartner>, Without<Selected>)
+    >,
+) {
+    if input.cancel {
+        state.set(BattleState::ConfirmMovement);
+        return;
+    }
+
+    let (sel_stats, mut sel_pack, mut sel_inventory) = selected.into_inner();
+    let (par_stats, mut par_pack, mut par_inventory) = partner.into_inner();
+    let mut traded = false;
+
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            TradeButton::Done => state.set(BattleState::ConfirmMovement),
+            TradeButton::Weapon(side, i) => {
+                let (from, to) = match side {
+                    TradeSide::Selected => (&mut *sel_pack, &mut *par_pack),
+                    TradeSide::Partner => (&mut *par_pack, &mut *sel_pack),
+                };
+                traded |= move_weapon(from, to, *i);
+            },
+            TradeButton::Item(side, i) => {
+                let (from, to) = match side {
+                    TradeSide::Selected => (&mut *sel_inventory, &mut *par_inventory),
+                    TradeSide::Partner => (&mut *par_inventory, &mut *sel_inventory),
+                };
+                traded |= move_item(from, to, *i);
+            },
+        }
+    }
+
+    if traded {
+        for entity in window_q.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+
+        spawn_trade_window(
+            &mut commands,
+            (sel_stats, &sel_pack, &sel_inventory),
+            (par_stats, &par_pack, &par_inventory),
+        );
+    }
+}
+
+fn move_weapon(from: &mut WeaponPack, to: &mut WeaponPack, index: usize) -> bool {
+    if to.is_full() {
+        return false;
+    }
+
+    match from.take(index) {
+        Some(weapon) => {
+            to.add(weapon);
+            true
+        },
+        None => false,
+    }
+}
+
+fn move_item(from: &mut Inventory, to: &mut Inventory, index: usize) -> bool {
+    if to.is_full() {
+        return false;
+    }
+
+    match from.take(index) {
+        Some(item) => {
+            to.items.push(item);
+            true
+        },
+        None => false,
+    }
+}
+
+fn clean_trade(
+    mut commands: Commands,
+    partner_q: Query<Entity, With<TradePartner>>,
+) {
+    for entity in partner_q.iter() {
+        commands.entity(entity).remove::<TradePartner>();
+    }
+}
@@ -1,12 +1,34 @@
 use bevy::ui::prelude::*;
 use bevy::prelude::*;
 use super::{EndBattleEarly, OnLevelScreen, PlayerTurnLabel};
+use crate::game::chest_selection::rarity_color;
+use crate::game::units::{UnitStats, WeaponPack};
+use sim::Ruleset;
 
+// In pixels
+const DETAIL_WIDTH: f32 = 200.0;
+
+/// Panel with everything about the hovered unit. Its rows are rebuilt for each unit.
 #[derive(Debug, Component)]
 pub struct DetailView;
 
-#[derive(Debug, Component)]
-pub struct Stats;
+/// One line of the detail view
+#[derive(Debug, PartialEq)]
+pub struct DetailRow {
+    pub text: String,
+    pub color: Color,
+    pub heading: bool,
+}
+
+impl DetailRow {
+    fn heading(text: &str) -> Self {
+        Self { text: text.to_string(), color: Color::BLACK, heading: true }
+    }
+
+    fn line(text: String) -> Self {
+        Self { text, color: Color::BLACK, heading: false }
+    }
+}
 
 #[derive(Debug, Component)]
 pub struct BattleSummaryView;
@@ -38,24 +60,15 @@ pub fn init_ui(
         OnLevelScreen,
         DetailView,
         Node {
-            width: Val::Percent(15.0),
-            height: Val::Percent(25.0),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            width: Val::Px(DETAIL_WIDTH),
+            padding: UiRect::all(Val::Px(6.0)),
             ..Default::default()
         },
         Visibility::Hidden,
         BackgroundColor(Color::WHITE),
-        Text::new(""),
-    )).with_children(|parent| {
-        parent.spawn((
-            Stats,
-            TextSpan::default(),
-            TextColor(Color::BLACK),
-            TextFont {
-                font_size: 13.0,
-                ..Default::default()
-            },
-        ));
-    });
+    ));
 
     commands.spawn((
         OnLevelScreen,
@@ -110,3 +123,106 @@ pub fn init_ui(
         });
     });
 }
+
+/// Everything the detail view shows about a unit, top to bottom
+pub fn unit_detail_rows(
+    stats: &UnitStats,
+    rules: &Ruleset,
+    max_hp: u32,
+    pack: &WeaponPack,
+    is_player: bool,
+    statuses: &[String],
+) -> Vec<DetailRow> {
+    let faction = if is_player { "Ally" } else { "Enemy" };
+    let mut rows = vec![
+        DetailRow::heading(&stats.name),
+        DetailRow::line(format!("Faction {}", faction)),
+        DetailRow::line(format!("LV {}  XP {}", stats.level(), stats.xp)),
+        DetailRow::line(format!("HP {}/{}", stats.hp, max_hp)),
+        DetailRow::line(format!("ATK {}  DEF {}  MOV {}", stats.atk, stats.def, stats.mov)),
+        DetailRow::line(format!("SPD {}  SKL {}", stats.spd, stats.skill)),
+        DetailRow::line(format!("Hit {}  Crit {}", stats.accuracy(rules), stats.crit(rules))),
+        DetailRow::line(format!("AS {}  Dodge {}", stats.attack_speed(rules), stats.dodge(rules))),
+        DetailRow::heading("Weapons"),
+    ];
+
+    for (i, weapon) in pack.weapons.iter().enumerate() {
+        let marker = if i == pack.equipped_index() { "E" } else { "-" };
+        rows.push(DetailRow {
+            text: format!("{} {}  Rng {}", marker, weapon.get_name(), weapon.range.get_name()),
+            color: rarity_color(&weapon.rarity),
+            heading: false,
+        });
+    }
+
+    rows.push(DetailRow::heading("Status"));
+    if statuses.is_empty() {
+        rows.push(DetailRow::line("None".to_string()));
+    }
+    rows.extend(statuses.iter().map(|status| DetailRow::line(status.clone())));
+
+    // Weapon effects are the only abilities units have
+    rows.push(DetailRow::heading("Skills"));
+    let skills: Vec<String> = pack.weapons.iter()
+        .filter_map(|weapon| weapon.weapon_effect.as_ref().map(|effect| {
+            format!("{} ({})", weapon.get_name(), effect.get_name())
+        }))
+        .collect();
+    if skills.is_empty() {
+        rows.push(DetailRow::line("None".to_string()));
+    }
+    rows.extend(skills.into_iter().map(DetailRow::line));
+
+    rows
+}
+
+pub fn spawn_detail_rows(parent: &mut ChildBuilder, rows: Vec<DetailRow>) {
+    for row in rows {
+        parent.spawn((
+            Text::new(row.text),
+            TextColor(row.color),
+            TextFont {
+                font_size: if row.heading { 15.0 } else { 13.0 },
+                ..Default::default()
+            },
+            Node {
+                margin: UiRect::top(Val::Px(if row.heading { 4.0 } else { 0.0 })),
+                ..Default::default()
+            },
+        ));
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use bevy::prelude::*;
+    #[allow(unused_imports)]
+    use crate::game::chest_selection::rarity_color;
+    #[allow(unused_imports)]
+    use crate::game::units::{UnitStats, WeaponPack};
+    #[allow(unused_imports)]
+    use sim::{Rarity, Weapon};
+    #[allow(unused_imports)]
+    use sim::Ruleset;
+    #[allow(unused_imports)]
+    use super::unit_detail_rows;
+
+    #[test]
+    fn test_unit_detail_rows() {
+        let mut pack = WeaponPack::default();
+        pack.add(Weapon::default());
+        pack.add(Weapon { rarity: Rarity::Rare, ..Default::default() });
+        let stats = UnitStats(sim::UnitStats { name: "Scooby".to_string(), hp: 7, skill: 4, ..Default::default() });
+
+        let rows = unit_detail_rows(&stats, &Ruleset::default(), 10, &pack, true, &[]);
+        let texts: Vec<&str> = rows.iter().map(|row| row.text.as_str()).collect();
+        assert_eq!(texts[0], "Scooby");
+        assert!(texts.contains(&"Faction Ally"));
+        assert!(texts.contains(&"HP 7/10"));
+        assert!(texts.contains(&"E common-sword  Rng 1"));
+        let rare = rows.iter().find(|row| row.text == "- rare-sword  Rng 1").unwrap();
+        assert_eq!(rare.color, rarity_color(&Rarity::Rare));
+        assert!(texts.iter().any(|text| text.starts_with("Hit 8")));
+        assert_eq!(texts.iter().filter(|text| **text == "None").count(), 2);
+    }
+}
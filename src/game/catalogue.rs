@@ -0,0 +1,38 @@
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+
+use super::assets::WeaponCollection;
+
+/// The weapon catalogue loot is drawn from, shared with the balance tool
+#[derive(Resource, AssetCollection)]
+pub struct AvailableWeapons {
+    #[asset(path="rouge/catalogue.weapons.ron")]
+    pub s: Handle<WeaponCollection>,
+}
+
+/// Every weapon that can drop, as loaded from the catalogue file
+#[derive(Resource, Default, Deref)]
+pub struct WeaponCatalogue(pub sim::WeaponCollection);
+
+pub fn catalogue_plugin(app: &mut App) {
+    app
+        .init_resource::<WeaponCatalogue>()
+        .add_systems(Update, update_catalogue.run_if(resource_exists::<AvailableWeapons>));
+}
+
+/// Copies the catalogue file into the WeaponCatalogue resource, again whenever the file
+/// is edited
+fn update_catalogue(
+    available: Res<AvailableWeapons>,
+    assets: Res<Assets<WeaponCollection>>,
+    mut events: EventReader<AssetEvent<WeaponCollection>>,
+    mut catalogue: ResMut<WeaponCatalogue>,
+) {
+    let reloaded = events.read().count() > 0;
+    if !reloaded && !available.is_added() { return }
+
+    if let Some(collection) = assets.get(&available.s) {
+        catalogue.0 = collection.0.clone();
+        info!("Loaded {} weapons into the catalogue", catalogue.weapons.len());
+    }
+}
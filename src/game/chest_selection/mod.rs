@@ -1,10 +1,84 @@
 use bevy::prelude::*;
+use super::catalogue::WeaponCatalogue;
+use super::map_selection::{enter_node, random_reachable_node, RunMap};
+use super::meta::MetaProgress;
+use super::roster::RunRoster;
+use super::run_stats::RunStats;
+use super::ui::WeaponTooltip;
+use super::units::MAX_WEAPONS;
+use sim::{Rarity, Weapon, WeaponCollection};
 use super::GameState;
 use crate::despawn_screen;
 
+const CHEST_COUNT: usize = 3;
+
 #[derive(Component)]
 struct OnChestSelectionScreen;
 
+/// Root of the chest panel so it can be rebuilt after each pick
+#[derive(Component)]
+struct ChestPanel;
+
+#[derive(Component)]
+enum ChestButton {
+    Chest(usize),
+    Give(usize),
+    Sell,
+    Discard,
+}
+
+/// The chests offered on this screen. Each chest holds one weapon rolled from the
+/// loot table of the chest's rarity.
+#[derive(Default, Resource)]
+pub struct ChestRewards {
+    pub chests: Vec<Weapon>,
+    pub picked: Option<usize>,
+    /// Set once the picked weapon was given away or discarded
+    pub claimed: Option<String>,
+}
+
+impl ChestRewards {
+    /// Rolls a weapon per chest, never below the min rarity
+    pub fn roll(catalogue: &WeaponCollection, count: usize, min: &Rarity) -> Self {
+        Self {
+            chests: (0..count).map(|_| catalogue.get_random_weapon_at_least(min)).collect(),
+            picked: None,
+            claimed: None,
+        }
+    }
+
+    /// Gives the picked weapon to a roster unit, or discards it when unit is None.
+    /// Returns false if nothing was picked or the unit can't carry it.
+    pub fn claim(&mut self, roster: &mut RunRoster, unit: Option<usize>) -> bool {
+        let Some(picked) = self.picked else { return false };
+        let weapon = self.chests[picked].clone();
+
+        match unit {
+            Some(i) => {
+                let Some(unit) = roster.units.get_mut(i) else { return false };
+                if unit.pack.is_full() {
+                    return false;
+                }
+                self.claimed = Some(format!("{} took the {}", unit.stats.name, weapon.get_name()));
+                unit.pack.add(weapon);
+            },
+            None => self.claimed = Some(format!("Discarded the {}", weapon.get_name())),
+        }
+
+        true
+    }
+
+    /// Takes gold for the picked weapon instead of giving it to a unit
+    pub fn sell(&mut self, roster: &mut RunRoster) -> bool {
+        let Some(picked) = self.picked else { return false };
+        let weapon = &self.chests[picked];
+
+        roster.gold += weapon.sell_price();
+        self.claimed = Some(format!("Sold the {} for {}g", weapon.get_name(), weapon.sell_price()));
+        true
+    }
+}
+
 #[derive(Component)]
 enum ConfirmButton {
     Random,
@@ -14,12 +88,24 @@ enum ConfirmButton {
 
 pub fn chest_selection_plugin(app: &mut App) {
     app
+        .init_resource::<ChestRewards>()
         .add_systems(OnEnter(GameState::ChestSelection), init_screen)
-        .add_systems(Update, menu_action)
-        .add_systems(OnExit(GameState::ChestSelection), despawn_screen::<OnChestSelectionScreen>);
+        .add_systems(Update, (menu_action, chest_action).run_if(in_state(GameState::ChestSelection)))
+        .add_systems(OnExit(GameState::ChestSelection), (despawn_screen::<OnChestSelectionScreen>, clear_rewards));
 }
 
-fn init_screen(mut commands: Commands) {
+fn init_screen(
+    mut commands: Commands,
+    mut rewards: ResMut<ChestRewards>,
+    roster: Res<RunRoster>,
+    meta: Res<MetaProgress>,
+    catalogue: Res<WeaponCatalogue>,
+) {
+    // A resumed run brings back the chests it was offered
+    if rewards.chests.is_empty() {
+        *rewards = ChestRewards::roll(&catalogue, CHEST_COUNT, &meta.min_chest_rarity());
+    }
+
     commands.spawn((
         Node {
             width: Val::Percent(100.0),
@@ -31,9 +117,7 @@ fn init_screen(mut commands: Commands) {
         OnChestSelectionScreen
     )).with_children(|parent| {
         parent.spawn((
-            Button {
-                ..default()
-            },
+            Button,
             Node {
                 width: Val::Px(250.0),
                 height: Val::Px(65.0),
@@ -52,9 +136,7 @@ fn init_screen(mut commands: Commands) {
         });
 
         parent.spawn((
-            Button {
-                ..default()
-            },
+            Button,
             Node {
                 width: Val::Px(250.0),
                 height: Val::Px(65.0),
@@ -73,9 +155,7 @@ fn init_screen(mut commands: Commands) {
         });
 
         parent.spawn((
-            Button {
-                ..default()
-            },
+            Button,
             Node {
                 width: Val::Px(250.0),
                 height: Val::Px(65.0),
@@ -94,6 +174,10 @@ fn init_screen(mut commands: Commands) {
         });
     });
 
+    spawn_chest_panel(&mut commands, &rewards, &roster);
+}
+
+fn spawn_chest_panel(commands: &mut Commands, rewards: &ChestRewards, roster: &RunRoster) {
     commands.spawn((
         Node {
             width: Val::Percent(100.0),
@@ -102,36 +186,252 @@ fn init_screen(mut commands: Commands) {
             justify_content: JustifyContent::Center,
             ..default()
         },
+        ChestPanel,
         OnChestSelectionScreen
     )).with_children(|parent| {
         parent.spawn((
             Node {
-                width: Val::Percent(50.0),
-                height: Val::Percent(50.0),
-                ..Default::default()
+                width: Val::Percent(60.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
             },
             BackgroundColor(Color::WHITE),
-            TextColor(Color::BLACK),
-            Text::new("Chest selection goes here")
+        )).with_children(|parent| {
+            if let Some(message) = &rewards.claimed {
+                parent.spawn((
+                    Text::new(message.clone()),
+                    TextColor(Color::BLACK),
+                ));
+                return;
+            }
+
+            parent.spawn((
+                Text::new("Pick a chest"),
+                TextColor(Color::BLACK),
+            ));
+
+            parent.spawn(Node {
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::SpaceEvenly,
+                ..default()
+            }).with_children(|parent| {
+                for (i, weapon) in rewards.chests.iter().enumerate() {
+                    let label = if rewards.picked == Some(i) {
+                        format!("{}\nATK {} HIT {} CRT {} WT {}", weapon.get_name(), weapon.attack, weapon.hit, weapon.crit, weapon.weight)
+                    } else {
+                        format!("{} chest", weapon.rarity.get_name())
+                    };
+                    let color = if rewards.picked == Some(i) { rarity_color(&weapon.rarity) } else { Color::BLACK };
+                    let mut button = create_chest_button(parent, label, color, ChestButton::Chest(i));
+                    if rewards.picked == Some(i) {
+                        button.insert(WeaponTooltip { weapon: weapon.clone(), equipped: None });
+                    }
+                }
+            });
+
+            let Some(picked) = rewards.picked else { return };
+            parent.spawn((
+                Text::new(format!("Who gets the {}?", rewards.chests[picked].get_name())),
+                TextColor(Color::BLACK),
+            ));
+
+            for (i, unit) in roster.units.iter().enumerate() {
+                let label = if unit.pack.is_full() {
+                    format!("{} (full)", unit.stats.name)
+                } else {
+                    format!("{} ({}/{})", unit.stats.name, unit.pack.weapons.len(), MAX_WEAPONS)
+                };
+                // Hovering a unit compares the chest's weapon with what it has equipped
+                create_chest_button(parent, label, Color::BLACK, ChestButton::Give(i)).insert(WeaponTooltip {
+                    weapon: rewards.chests[picked].clone(),
+                    equipped: Some(unit.pack.get_equipped().clone()),
+                });
+            }
+            let gold = rewards.chests[picked].sell_price();
+            create_chest_button(parent, format!("Sell for {}g", gold), Color::BLACK, ChestButton::Sell);
+            create_chest_button(parent, "Discard".to_string(), Color::BLACK, ChestButton::Discard);
+        });
+    });
+}
+
+pub fn rarity_color(rarity: &Rarity) -> Color {
+    match rarity {
+        Rarity::Common => Color::srgb(0.4, 0.4, 0.4),
+        Rarity::Uncommon => Color::srgb(0.1, 0.5, 0.1),
+        Rarity::Rare => Color::srgb(0.1, 0.2, 0.7),
+        Rarity::Legendary => Color::srgb(0.7, 0.5, 0.0),
+    }
+}
+
+fn create_chest_button<'a>(parent: &'a mut ChildBuilder, label: String, color: Color, action: ChestButton) -> EntityCommands<'a> {
+    let mut button = parent.spawn((
+        Button,
+        Node {
+            min_width: Val::Px(200.0),
+            min_height: Val::Px(30.0),
+            margin: UiRect::all(Val::Px(5.0)),
+            padding: UiRect::all(Val::Px(5.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(color),
+        action,
+    ));
+    button.with_children(|parent| {
+        parent.spawn((
+            Text::new(label),
+            TextFont {
+                font_size: 13.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
         ));
     });
+    button
+}
+
+fn chest_action(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &ChestButton), (Changed<Interaction>, With<Button>)>,
+    panel_q: Query<Entity, With<ChestPanel>>,
+    mut rewards: ResMut<ChestRewards>,
+    mut roster: ResMut<RunRoster>,
+    mut run_stats: ResMut<RunStats>,
+) {
+    let mut changed = false;
+
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed || rewards.claimed.is_some() {
+            continue;
+        }
+
+        changed |= match button {
+            ChestButton::Chest(i) => {
+                rewards.picked = Some(*i);
+                true
+            },
+            ChestButton::Give(unit) => {
+                let given = rewards.claim(&mut roster, Some(*unit));
+                if let (true, Some(picked)) = (given, rewards.picked) {
+                    run_stats.found_weapon(&rewards.chests[picked]);
+                }
+                given
+            },
+            ChestButton::Sell => rewards.sell(&mut roster),
+            ChestButton::Discard => rewards.claim(&mut roster, None),
+        };
+    }
+
+    if changed {
+        for entity in panel_q.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        spawn_chest_panel(&mut commands, &rewards, &roster);
+    }
 }
 
+fn clear_rewards(mut rewards: ResMut<ChestRewards>) {
+    *rewards = ChestRewards::default();
+}
+
+#[allow(clippy::too_many_arguments)]
 fn menu_action(
+    mut commands: Commands,
     interaction_query: Query<
         (&Interaction, &ConfirmButton),
         (Changed<Interaction>, With<Button>),
     >,
+    panel_q: Query<Entity, With<ChestPanel>>,
     mut game_state: ResMut<NextState<GameState>>,
+    mut run_map: ResMut<RunMap>,
+    mut roster: ResMut<RunRoster>,
+    mut rewards: ResMut<ChestRewards>,
+    meta: Res<MetaProgress>,
+    catalogue: Res<WeaponCatalogue>,
 ){
     for (interaction, button) in &interaction_query {
         if *interaction == Interaction::Pressed {
             match button {
                 ConfirmButton::Selection => game_state.set(GameState::MapSelection),
-                ConfirmButton::Random => game_state.set(GameState::InBattle),
+                ConfirmButton::Random => {
+                    let next = random_reachable_node(&run_map)
+                        .and_then(|node| enter_node(&mut run_map, &mut roster, node))
+                        .unwrap_or(GameState::Rewards);
+
+                    // Landing on another chest doesn't re-run OnEnter, so roll new chests here
+                    if next == GameState::ChestSelection {
+                        *rewards = ChestRewards::roll(&catalogue, CHEST_COUNT, &meta.min_chest_rarity());
+                        for entity in panel_q.iter() {
+                            commands.entity(entity).despawn_recursive();
+                        }
+                        spawn_chest_panel(&mut commands, &rewards, &roster);
+                    }
+                    game_state.set(next);
+                },
                 ConfirmButton::EndGame => game_state.set(GameState::Rewards),
             }
         }
     }
 }
 
+mod test {
+    #[allow(unused_imports)]
+    use crate::game::roster::{RosterUnit, RunRoster};
+    #[allow(unused_imports)]
+    use crate::game::units::{UnitStats, MAX_WEAPONS};
+    #[allow(unused_imports)]
+    use sim::{Rarity, Weapon, WeaponCollection};
+    #[allow(unused_imports)]
+    use super::ChestRewards;
+
+    #[allow(dead_code)]
+    fn get_roster() -> RunRoster {
+        RunRoster {
+            units: vec![RosterUnit::new(UnitStats(sim::UnitStats { name: "Scooby".to_string(), hp: 10, ..Default::default() }), &WeaponCollection::default())],
+            casual: false,
+            gold: 0,
+        }
+    }
+
+    #[test]
+    fn test_claim_gives_weapon() {
+        let mut roster = get_roster();
+        let mut rewards = ChestRewards::roll(&WeaponCollection::default(), 3, &Rarity::Common);
+        let before = roster.units[0].pack.weapons.len();
+
+        assert!(!rewards.claim(&mut roster, Some(0)));
+        rewards.picked = Some(1);
+        assert!(rewards.claim(&mut roster, Some(0)));
+        assert_eq!(roster.units[0].pack.weapons.len(), before + 1);
+        assert_eq!(roster.units[0].pack.weapons.last(), Some(&rewards.chests[1]));
+    }
+
+    #[test]
+    fn test_claim_full_pack() {
+        let mut roster = get_roster();
+        while !roster.units[0].pack.is_full() {
+            roster.units[0].pack.add(Weapon::default());
+        }
+        let mut rewards = ChestRewards::roll(&WeaponCollection::default(), 3, &Rarity::Common);
+        rewards.picked = Some(0);
+
+        assert!(!rewards.claim(&mut roster, Some(0)));
+        assert!(rewards.claimed.is_none());
+        assert!(rewards.claim(&mut roster, None));
+        assert_eq!(roster.units[0].pack.weapons.len(), MAX_WEAPONS);
+    }
+
+    #[test]
+    fn test_sell_for_gold() {
+        let mut roster = get_roster();
+        let mut rewards = ChestRewards::roll(&WeaponCollection::default(), 3, &Rarity::Common);
+
+        assert!(!rewards.sell(&mut roster));
+        rewards.picked = Some(2);
+        assert!(rewards.sell(&mut roster));
+        assert_eq!(roster.gold, rewards.chests[2].sell_price());
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::{BTreeMap, HashSet};
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::Settings;
+
+/// Everything the player can do with a button. Gameplay reads these from ActionState
+/// instead of checking keys or mouse buttons.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    Confirm,
+    Cancel,
+    Menu,
+    NextUnit,
+    /// Shows every tile an enemy could attack
+    ToggleDanger,
+    Rescue,
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    /// Held while dragging to move the camera
+    PanCamera,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+}
+
+impl Action {
+    pub const ALL: [Action; 17] = [
+        Action::Confirm,
+        Action::Cancel,
+        Action::Menu,
+        Action::NextUnit,
+        Action::ToggleDanger,
+        Action::Rescue,
+        Action::CursorUp,
+        Action::CursorDown,
+        Action::CursorLeft,
+        Action::CursorRight,
+        Action::PanCamera,
+        Action::PanUp,
+        Action::PanDown,
+        Action::PanLeft,
+        Action::PanRight,
+        Action::ZoomIn,
+        Action::ZoomOut,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Action::Confirm => "Confirm",
+            Action::Cancel => "Cancel",
+            Action::Menu => "Menu",
+            Action::NextUnit => "Next unit",
+            Action::ToggleDanger => "Danger zone",
+            Action::Rescue => "Rescue/Drop",
+            Action::CursorUp => "Cursor up",
+            Action::CursorDown => "Cursor down",
+            Action::CursorLeft => "Cursor left",
+            Action::CursorRight => "Cursor right",
+            Action::PanCamera => "Drag camera",
+            Action::PanUp => "Pan up",
+            Action::PanDown => "Pan down",
+            Action::PanLeft => "Pan left",
+            Action::PanRight => "Pan right",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+        }
+    }
+
+    fn default_bindings(&self) -> Vec<Binding> {
+        use Binding::{Gamepad, Key, Mouse};
+        match self {
+            Action::Confirm => vec![Mouse(MouseButton::Left), Key(KeyCode::Enter), Key(KeyCode::Space), Gamepad(GamepadButton::South)],
+            Action::Cancel => vec![Mouse(MouseButton::Right), Key(KeyCode::Backspace), Gamepad(GamepadButton::East)],
+            Action::Menu => vec![Key(KeyCode::Escape), Gamepad(GamepadButton::Start)],
+            Action::NextUnit => vec![Key(KeyCode::Tab), Gamepad(GamepadButton::RightTrigger)],
+            Action::ToggleDanger => vec![Key(KeyCode::KeyQ), Gamepad(GamepadButton::North)],
+            Action::Rescue => vec![Key(KeyCode::KeyR), Gamepad(GamepadButton::West)],
+            Action::CursorUp => vec![Key(KeyCode::ArrowUp), Key(KeyCode::KeyW), Gamepad(GamepadButton::DPadUp)],
+            Action::CursorDown => vec![Key(KeyCode::ArrowDown), Key(KeyCode::KeyS), Gamepad(GamepadButton::DPadDown)],
+            Action::CursorLeft => vec![Key(KeyCode::ArrowLeft), Key(KeyCode::KeyA), Gamepad(GamepadButton::DPadLeft)],
+            Action::CursorRight => vec![Key(KeyCode::ArrowRight), Key(KeyCode::KeyD), Gamepad(GamepadButton::DPadRight)],
+            Action::PanCamera => vec![Mouse(MouseButton::Middle)],
+            Action::PanUp => vec![Key(KeyCode::KeyI)],
+            Action::PanDown => vec![Key(KeyCode::KeyK)],
+            Action::PanLeft => vec![Key(KeyCode::KeyJ)],
+            Action::PanRight => vec![Key(KeyCode::KeyL)],
+            Action::ZoomIn => vec![Key(KeyCode::Equal), Gamepad(GamepadButton::RightTrigger2)],
+            Action::ZoomOut => vec![Key(KeyCode::Minus), Gamepad(GamepadButton::LeftTrigger2)],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+impl Binding {
+    pub fn get_name(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key).trim_start_matches("Key").to_string(),
+            Binding::Mouse(button) => format!("Mouse {:?}", button),
+            Binding::Gamepad(button) => format!("Pad {:?}", button),
+        }
+    }
+
+    /// Both are keys, both mouse buttons or both gamepad buttons
+    fn same_kind(&self, other: &Binding) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// Bindings the player changed. Actions that were never rebound use their defaults,
+/// so new actions show up without touching old config files.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Bindings(BTreeMap<Action, Vec<Binding>>);
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> Vec<Binding> {
+        self.0.get(&action).cloned().unwrap_or_else(|| action.default_bindings())
+    }
+
+    /// Replaces the action's bindings of the same kind, so rebinding a key leaves the
+    /// mouse and gamepad buttons alone
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let mut bindings = self.get(action);
+        bindings.retain(|b| !b.same_kind(&binding));
+        bindings.insert(0, binding);
+        self.0.insert(action, bindings);
+    }
+
+    pub fn reset(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn describe(&self, action: Action) -> String {
+        self.get(action).iter().map(|b| b.get_name()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// Which actions are held and which started this frame
+#[derive(Default, Resource, Debug)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+}
+
+/// Systems reading ActionState in PreUpdate go after this
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActionSystem;
+
+pub fn input_plugin(app: &mut App) {
+    app
+        .init_resource::<ActionState>()
+        .add_systems(PreUpdate, read_actions.in_set(ActionSystem).after(InputSystem));
+}
+
+fn read_actions(
+    mut state: ResMut<ActionState>,
+    settings: Res<Settings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+) {
+    state.pressed.clear();
+    state.just_pressed.clear();
+
+    for action in Action::ALL {
+        for binding in settings.bindings.get(action) {
+            let (pressed, just_pressed) = match binding {
+                Binding::Key(key) => (keys.pressed(key), keys.just_pressed(key)),
+                Binding::Mouse(button) => (mouse.pressed(button), mouse.just_pressed(button)),
+                Binding::Gamepad(button) => (
+                    gamepads.iter().any(|g| g.pressed(button)),
+                    gamepads.iter().any(|g| g.just_pressed(button)),
+                ),
+            };
+            if pressed {
+                state.pressed.insert(action);
+            }
+            if just_pressed {
+                state.just_pressed.insert(action);
+            }
+        }
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use bevy::prelude::*;
+    #[allow(unused_imports)]
+    use crate::game::storage::{from_ron, to_ron};
+    #[allow(unused_imports)]
+    use super::{Action, Binding, Bindings};
+
+    #[test]
+    fn test_rebind_keeps_other_device() {
+        let mut bindings = Bindings::default();
+        bindings.rebind(Action::Confirm, Binding::Key(KeyCode::KeyZ));
+        assert_eq!(bindings.get(Action::Confirm), vec![
+            Binding::Key(KeyCode::KeyZ),
+            Binding::Mouse(MouseButton::Left),
+            Binding::Gamepad(GamepadButton::South),
+        ]);
+
+        bindings.rebind(Action::Confirm, Binding::Gamepad(GamepadButton::West));
+        assert_eq!(bindings.get(Action::Confirm), vec![
+            Binding::Gamepad(GamepadButton::West),
+            Binding::Key(KeyCode::KeyZ),
+            Binding::Mouse(MouseButton::Left),
+        ]);
+
+        // Dragging the camera keeps the middle mouse button when it gets a key
+        bindings.rebind(Action::PanCamera, Binding::Key(KeyCode::KeyG));
+        assert_eq!(bindings.get(Action::PanCamera), vec![Binding::Key(KeyCode::KeyG), Binding::Mouse(MouseButton::Middle)]);
+
+        // Untouched actions keep their defaults, and only changes are saved
+        let loaded: Bindings = from_ron(&to_ron(&bindings).unwrap()).unwrap();
+        assert_eq!(loaded, bindings);
+        assert_eq!(loaded.get(Action::Menu), Action::Menu.default_bindings());
+
+        bindings.reset();
+        assert_eq!(bindings.get(Action::Confirm), Action::Confirm.default_bindings());
+    }
+
+    #[test]
+    fn test_default_bindings_are_unique() {
+        let defaults: Vec<Binding> = Action::ALL.iter().flat_map(|a| a.default_bindings()).collect();
+        for (i, binding) in defaults.iter().enumerate() {
+            assert!(!defaults[i + 1..].contains(binding), "{} is bound twice", binding.get_name());
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// Consumables a unit can carry alongside its weapons.
+#[derive(PartialEq, Clone, Debug, Deserialize, Serialize)]
+pub enum Item {
+    Vulnerary,
+    Elixir,
+}
+
+impl Item {
+    pub fn get_name(&self) -> String {
+        match self {
+            Item::Vulnerary => "vulnerary".to_string(),
+            Item::Elixir => "elixir".to_string(),
+        }
+    }
+
+    pub fn price(&self) -> u32 {
+        match self {
+            Item::Vulnerary => 50,
+            Item::Elixir => 150,
+        }
+    }
+}
@@ -1,21 +1,76 @@
 use bevy::prelude::*;
+use rand::seq::IndexedRandom;
+
+use super::roster::RunRoster;
 use super::GameState;
 use crate::despawn_screen;
 
+mod run_map;
+
+pub use run_map::{NodeType, RunMap};
+
+// Portion of a unit's max hp restored at a rest node
+const REST_HEAL_PERCENT: u32 = 50;
+const PATH_DOTS: usize = 6;
+const NODE_WIDTH: f32 = 70.0;
+const NODE_HEIGHT: f32 = 40.0;
+
 #[derive(Component)]
 struct OnMapSelectionScreen;
 
+/// Root of the drawn map so it can be rebuilt without leaving the screen
+#[derive(Component)]
+struct RunMapView;
+
 #[derive(Component)]
 struct ConfirmMapSelection;
 
+#[derive(Component)]
+struct MapNodeButton(usize);
+
 pub fn map_selection_plugin(app: &mut App) {
     app
+        .init_resource::<RunMap>()
         .add_systems(OnEnter(GameState::MapSelection), init_screen)
-        .add_systems(Update, menu_action)
+        .add_systems(Update, (menu_action, node_action).run_if(in_state(GameState::MapSelection)))
         .add_systems(OnExit(GameState::MapSelection), despawn_screen::<OnMapSelectionScreen>);
 }
 
-fn init_screen(mut commands: Commands) {
+/// Travels to a reachable node and returns the state the node leads to.
+/// Rest nodes are handled right away, so they lead back to the map.
+pub fn enter_node(run_map: &mut RunMap, roster: &mut RunRoster, index: usize) -> Option<GameState> {
+    if !run_map.visit(index) {
+        return None;
+    }
+
+    let node_type = match run_map.nodes[index].node_type {
+        NodeType::Event => run_map.resolve_event(index),
+        node_type => node_type,
+    };
+    info!("Travelling to {:?} node {}", node_type, index);
+
+    let next = match node_type {
+        NodeType::Battle | NodeType::EliteBattle => GameState::InBattle,
+        NodeType::Chest => GameState::ChestSelection,
+        NodeType::Shop => GameState::Shop,
+        NodeType::Rest | NodeType::Event => {
+            for unit in roster.units.iter_mut() {
+                let heal = unit.max_hp * REST_HEAL_PERCENT / 100;
+                unit.stats.hp = (unit.stats.hp + heal).min(unit.max_hp);
+            }
+            if run_map.is_finished() { GameState::Rewards } else { GameState::MapSelection }
+        },
+    };
+
+    Some(next)
+}
+
+/// Picks any node reachable from where the player is
+pub fn random_reachable_node(run_map: &RunMap) -> Option<usize> {
+    run_map.reachable().choose(&mut rand::rng()).copied()
+}
+
+fn init_screen(mut commands: Commands, run_map: Res<RunMap>, roster: Res<RunRoster>) {
     commands.spawn((
         Node {
             width: Val::Percent(100.0),
@@ -27,9 +82,17 @@ fn init_screen(mut commands: Commands) {
         OnMapSelectionScreen
     )).with_children(|parent| {
         parent.spawn((
-            Button {
+            Node {
+                margin: UiRect::all(Val::Px(20.0)),
+                align_self: AlignSelf::Center,
                 ..default()
             },
+            Text::new(format!("Gold: {}", roster.gold)),
+            TextColor(Color::WHITE),
+        ));
+
+        parent.spawn((
+            Button,
             Node {
                 width: Val::Px(250.0),
                 height: Val::Px(65.0),
@@ -42,12 +105,16 @@ fn init_screen(mut commands: Commands) {
             ConfirmMapSelection,
         )).with_children(|parent| {
             parent.spawn((
-                Text::new("Confirm"),
+                Text::new("End Run"),
                 TextColor(Color::WHITE),
             ));
         });
     });
 
+    spawn_run_map(&mut commands, &run_map);
+}
+
+fn spawn_run_map(commands: &mut Commands, run_map: &RunMap) {
     commands.spawn((
         Node {
             width: Val::Percent(100.0),
@@ -56,21 +123,137 @@ fn init_screen(mut commands: Commands) {
             justify_content: JustifyContent::Center,
             ..default()
         },
+        RunMapView,
         OnMapSelectionScreen
     )).with_children(|parent| {
         parent.spawn((
             Node {
-                width: Val::Percent(50.0),
-                height: Val::Percent(50.0),
+                width: Val::Percent(80.0),
+                height: Val::Percent(70.0),
                 ..Default::default()
             },
-            BackgroundColor(Color::WHITE),
-            TextColor(Color::BLACK),
-            Text::new("TO BE DEVELOPED")
-        ));
+            BackgroundColor(Color::srgb(0.15, 0.15, 0.2)),
+        )).with_children(|parent| {
+            let reachable = run_map.reachable();
+
+            for (i, node) in run_map.nodes.iter().enumerate() {
+                let start = node_position(run_map, i);
+                for next in node.next.iter() {
+                    let end = node_position(run_map, *next);
+                    let travelled = run_map.visited.contains(&i) && run_map.visited.contains(next);
+                    spawn_path(parent, start, end, travelled);
+                }
+            }
+
+            for (i, node) in run_map.nodes.iter().enumerate() {
+                let (left, top) = node_position(run_map, i);
+                let (background, text_color) = if run_map.current == Some(i) {
+                    (Color::srgb(0.9, 0.8, 0.2), Color::BLACK)
+                } else if reachable.contains(&i) {
+                    (Color::WHITE, Color::BLACK)
+                } else if run_map.visited.contains(&i) {
+                    (Color::srgb(0.4, 0.4, 0.4), Color::BLACK)
+                } else {
+                    (Color::BLACK, Color::srgb(0.6, 0.6, 0.6))
+                };
+
+                parent.spawn((
+                    Button,
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(left),
+                        top: Val::Percent(top),
+                        width: Val::Px(NODE_WIDTH),
+                        height: Val::Px(NODE_HEIGHT),
+                        // Centers the node on its position
+                        margin: UiRect {
+                            left: Val::Px(-NODE_WIDTH / 2.0),
+                            top: Val::Px(-NODE_HEIGHT / 2.0),
+                            ..default()
+                        },
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BackgroundColor(background),
+                    MapNodeButton(i),
+                )).with_children(|parent| {
+                    parent.spawn((
+                        Text::new(node.node_type.get_name()),
+                        TextColor(text_color),
+                    ));
+                });
+            }
+        });
     });
 }
 
+// Percent position of a node inside the map area. Layers go left to right.
+fn node_position(run_map: &RunMap, index: usize) -> (f32, f32) {
+    let node = &run_map.nodes[index];
+    let layers = run_map.number_of_layers().max(2);
+    let layer = run_map.layer(node.layer);
+    let row = layer.iter().position(|i| *i == index).unwrap_or(0);
+
+    let left = 5.0 + 90.0 * node.layer as f32 / (layers - 1) as f32;
+    let top = 100.0 * (row + 1) as f32 / (layer.len() + 1) as f32;
+    (left, top)
+}
+
+// UI nodes can't be rotated, so paths are drawn as a line of dots
+fn spawn_path(parent: &mut ChildBuilder, start: (f32, f32), end: (f32, f32), travelled: bool) {
+    let color = if travelled { Color::srgb(0.9, 0.8, 0.2) } else { Color::srgb(0.5, 0.5, 0.5) };
+
+    for dot in 1..=PATH_DOTS {
+        let t = dot as f32 / (PATH_DOTS + 1) as f32;
+        parent.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(start.0 + (end.0 - start.0) * t),
+                top: Val::Percent(start.1 + (end.1 - start.1) * t),
+                width: Val::Px(4.0),
+                height: Val::Px(4.0),
+                margin: UiRect {
+                    left: Val::Px(-2.0),
+                    top: Val::Px(-2.0),
+                    ..default()
+                },
+                ..default()
+            },
+            BackgroundColor(color),
+        ));
+    }
+}
+
+fn node_action(
+    mut commands: Commands,
+    interaction_query: Query<
+        (&Interaction, &MapNodeButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+    view_q: Query<Entity, With<RunMapView>>,
+    mut run_map: ResMut<RunMap>,
+    mut roster: ResMut<RunRoster>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed { continue }
+
+        match enter_node(&mut run_map, &mut roster, button.0) {
+            // Staying on the map doesn't re-run OnEnter, so redraw it here
+            Some(GameState::MapSelection) => {
+                for entity in view_q.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+                spawn_run_map(&mut commands, &run_map);
+            },
+            Some(next) => game_state.set(next),
+            None => info!("Node {} can't be reached from here", button.0),
+        }
+        return;
+    }
+}
+
 fn menu_action(
     interaction_query: Query<
         &Interaction,
@@ -80,8 +263,7 @@ fn menu_action(
 ){
     for interaction in &interaction_query {
         if *interaction == Interaction::Pressed {
-            game_state.set(GameState::InBattle);
+            game_state.set(GameState::Rewards);
         }
     }
 }
-
@@ -0,0 +1,238 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const NUMBER_OF_LAYERS: usize = 7;
+const MIN_NODES_PER_LAYER: usize = 2;
+const MAX_NODES_PER_LAYER: usize = 4;
+// Chance for a node to get a second path into the next layer
+const EXTRA_PATH_CHANCE: f64 = 0.35;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeType {
+    Battle,
+    EliteBattle,
+    Chest,
+    Rest,
+    Shop,
+    Event,
+}
+
+impl NodeType {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            NodeType::Battle => "Battle",
+            NodeType::EliteBattle => "Elite",
+            NodeType::Chest => "Chest",
+            NodeType::Rest => "Rest",
+            NodeType::Shop => "Shop",
+            NodeType::Event => "?",
+        }
+    }
+
+    /// Weighted pick for nodes in the middle of the map
+    fn random<R: Rng + ?Sized>(rng: &mut R) -> NodeType {
+        match rng.random_range(0..100) {
+            0..=39 => NodeType::Battle,
+            40..=49 => NodeType::EliteBattle,
+            50..=61 => NodeType::Chest,
+            62..=71 => NodeType::Shop,
+            72..=84 => NodeType::Rest,
+            _ => NodeType::Event,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MapNode {
+    pub node_type: NodeType,
+    pub layer: usize,
+    /// Indices of the nodes in the next layer this node has a path to
+    pub next: Vec<usize>,
+}
+
+/// The node graph for a run, and how far the player has gotten through it
+#[derive(Default, Resource, Debug)]
+pub struct RunMap {
+    pub seed: u64,
+    pub nodes: Vec<MapNode>,
+    pub current: Option<usize>,
+    pub visited: Vec<usize>,
+}
+
+impl RunMap {
+    /// Generates layers of nodes connected by paths. The first layer is always battles
+    /// and the last layer is a single elite battle.
+    pub fn generate(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut nodes: Vec<MapNode> = Vec::new();
+        let mut layers: Vec<Vec<usize>> = Vec::new();
+
+        for layer in 0..NUMBER_OF_LAYERS {
+            let count = if layer == NUMBER_OF_LAYERS - 1 {
+                1
+            } else {
+                rng.random_range(MIN_NODES_PER_LAYER..=MAX_NODES_PER_LAYER)
+            };
+
+            let mut indices = Vec::new();
+            for _ in 0..count {
+                let node_type = if layer == 0 {
+                    NodeType::Battle
+                } else if layer == NUMBER_OF_LAYERS - 1 {
+                    NodeType::EliteBattle
+                } else {
+                    NodeType::random(&mut rng)
+                };
+
+                indices.push(nodes.len());
+                nodes.push(MapNode { node_type, layer, next: Vec::new() });
+            }
+            layers.push(indices);
+        }
+
+        for window in layers.windows(2) {
+            let (current, next) = (&window[0], &window[1]);
+
+            // Every node leads to the node at the same relative height in the next layer
+            for (i, node) in current.iter().enumerate() {
+                let target = scale_index(i, current.len(), next.len());
+                nodes[*node].next.push(next[target]);
+
+                if rng.random_bool(EXTRA_PATH_CHANCE) {
+                    let neighbor = if target + 1 < next.len() { target + 1 } else { target.saturating_sub(1) };
+                    if !nodes[*node].next.contains(&next[neighbor]) {
+                        nodes[*node].next.push(next[neighbor]);
+                    }
+                }
+            }
+
+            // Every node in the next layer needs at least one way in
+            for (j, next_node) in next.iter().enumerate() {
+                let has_path = current.iter().any(|n| nodes[*n].next.contains(next_node));
+                if !has_path {
+                    let source = scale_index(j, next.len(), current.len());
+                    nodes[current[source]].next.push(*next_node);
+                }
+            }
+        }
+
+        Self {
+            seed,
+            nodes,
+            current: None,
+            visited: Vec::new(),
+        }
+    }
+
+    pub fn number_of_layers(&self) -> usize {
+        self.nodes.iter().map(|n| n.layer + 1).max().unwrap_or(0)
+    }
+
+    pub fn layer(&self, layer: usize) -> Vec<usize> {
+        (0..self.nodes.len()).filter(|i| self.nodes[*i].layer == layer).collect()
+    }
+
+    /// Nodes the player can travel to next
+    pub fn reachable(&self) -> Vec<usize> {
+        match self.current {
+            Some(current) => self.nodes[current].next.clone(),
+            None => self.layer(0),
+        }
+    }
+
+    pub fn visit(&mut self, index: usize) -> bool {
+        if !self.reachable().contains(&index) {
+            return false;
+        }
+
+        self.current = Some(index);
+        self.visited.push(index);
+        true
+    }
+
+    pub fn current_node_type(&self) -> Option<NodeType> {
+        self.current.map(|i| self.nodes[i].node_type)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.nodes.is_empty() && self.current.is_some() && self.reachable().is_empty()
+    }
+
+    /// Event nodes turn into one of the other encounters once visited
+    pub fn resolve_event(&self, index: usize) -> NodeType {
+        let mut rng = StdRng::seed_from_u64(self.seed ^ index as u64);
+        match rng.random_range(0..3) {
+            0 => NodeType::Battle,
+            1 => NodeType::Chest,
+            _ => NodeType::Rest,
+        }
+    }
+}
+
+fn scale_index(index: usize, from_len: usize, to_len: usize) -> usize {
+    if from_len <= 1 {
+        return to_len / 2;
+    }
+    (index * (to_len - 1) + (from_len - 1) / 2) / (from_len - 1)
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::{NodeType, RunMap, NUMBER_OF_LAYERS};
+
+    #[test]
+    fn test_generate_is_seeded() {
+        let lhs = RunMap::generate(42);
+        let rhs = RunMap::generate(42);
+
+        assert_eq!(lhs.nodes.len(), rhs.nodes.len());
+        for (l, r) in lhs.nodes.iter().zip(rhs.nodes.iter()) {
+            assert_eq!(l.node_type, r.node_type);
+            assert_eq!(l.next, r.next);
+        }
+    }
+
+    #[test]
+    fn test_every_node_is_connected() {
+        for seed in 0..50 {
+            let map = RunMap::generate(seed);
+            assert_eq!(map.number_of_layers(), NUMBER_OF_LAYERS);
+
+            for (i, node) in map.nodes.iter().enumerate() {
+                if node.layer + 1 < NUMBER_OF_LAYERS {
+                    assert!(!node.next.is_empty(), "seed {} node {} is a dead end", seed, i);
+                }
+                if node.layer > 0 {
+                    assert!(map.nodes.iter().any(|n| n.next.contains(&i)), "seed {} node {} is unreachable", seed, i);
+                }
+                for next in node.next.iter() {
+                    assert_eq!(map.nodes[*next].layer, node.layer + 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_first_and_last_layers() {
+        let map = RunMap::generate(7);
+
+        assert!(map.layer(0).iter().all(|i| map.nodes[*i].node_type == NodeType::Battle));
+        let last = map.layer(NUMBER_OF_LAYERS - 1);
+        assert_eq!(last.len(), 1);
+        assert_eq!(map.nodes[last[0]].node_type, NodeType::EliteBattle);
+    }
+
+    #[test]
+    fn test_visit_only_reachable() {
+        let mut map = RunMap::generate(3);
+        let first = map.reachable()[0];
+        let last = map.nodes.len() - 1;
+
+        assert!(!map.visit(last));
+        assert!(map.visit(first));
+        assert_eq!(map.current, Some(first));
+        assert_eq!(map.reachable(), map.nodes[first].next);
+        assert!(!map.is_finished());
+    }
+}
@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::run_stats::RunStats;
+use super::storage;
+use sim::Rarity;
+
+const META_FILE: &str = "meta.ron";
+
+/// Progress that has to be made across runs to earn an unlock
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Milestone {
+    BattlesWon(u32),
+    Kills(u32),
+    RunsPlayed(u32),
+    RunsCleared(u32),
+    /// Clear a run without any unit falling for good
+    FlawlessClear,
+}
+
+impl Milestone {
+    pub fn hint(&self) -> String {
+        match self {
+            Milestone::BattlesWon(n) => format!("Win {} battles", n),
+            Milestone::Kills(n) => format!("Defeat {} enemies", n),
+            Milestone::RunsPlayed(n) => format!("Play {} runs", n),
+            Milestone::RunsCleared(1) => "Clear a run".to_string(),
+            Milestone::RunsCleared(n) => format!("Clear {} runs", n),
+            Milestone::FlawlessClear => "Clear a run without losing a unit".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reward {
+    /// A unit from available.units.ron that can be picked in unit selection
+    Unit(&'static str),
+    /// Every unit starts the run with an extra weapon of this rarity
+    StartingWeapon(Rarity),
+    /// Chests never roll below this rarity
+    ChestRarity(Rarity),
+    StartingGold(u32),
+}
+
+impl Reward {
+    pub fn get_name(&self) -> String {
+        match self {
+            Reward::Unit(name) => name.to_string(),
+            Reward::StartingWeapon(rarity) => format!("Starting {} weapon", rarity.get_name()),
+            Reward::ChestRarity(rarity) => format!("{} or better chests", rarity.get_name()),
+            Reward::StartingGold(gold) => format!("{} starting gold", gold),
+        }
+    }
+}
+
+pub struct Unlock {
+    pub id: &'static str,
+    pub reward: Reward,
+    pub milestone: Milestone,
+}
+
+pub const UNLOCKS: [Unlock; 8] = [
+    Unlock { id: "slow_loris", reward: Reward::Unit("Slow Loris"), milestone: Milestone::BattlesWon(5) },
+    Unlock { id: "tom", reward: Reward::Unit("Tom"), milestone: Milestone::RunsPlayed(3) },
+    Unlock { id: "orangutan", reward: Reward::Unit("Orangutan"), milestone: Milestone::Kills(30) },
+    Unlock { id: "chipmanzee", reward: Reward::Unit("Chipmanzee"), milestone: Milestone::RunsCleared(1) },
+    Unlock { id: "double_d", reward: Reward::Unit("Double D"), milestone: Milestone::FlawlessClear },
+    Unlock { id: "starting_gold", reward: Reward::StartingGold(100), milestone: Milestone::RunsPlayed(2) },
+    Unlock { id: "starting_weapon", reward: Reward::StartingWeapon(Rarity::Uncommon), milestone: Milestone::BattlesWon(15) },
+    Unlock { id: "better_chests", reward: Reward::ChestRarity(Rarity::Uncommon), milestone: Milestone::RunsCleared(2) },
+];
+
+/// Totals carried between runs, saved in the data directory. Missing fields fall back
+/// to their defaults so older files still load.
+#[derive(Default, Resource, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct MetaProgress {
+    pub runs_played: u32,
+    pub runs_cleared: u32,
+    pub flawless_clears: u32,
+    pub battles_won: u32,
+    pub kills: u32,
+    /// Ids from UNLOCKS
+    pub unlocked: Vec<String>,
+}
+
+impl MetaProgress {
+    pub fn is_unlocked(&self, unlock: &Unlock) -> bool {
+        self.unlocked.iter().any(|id| id == unlock.id)
+    }
+
+    fn reached(&self, milestone: &Milestone) -> bool {
+        match milestone {
+            Milestone::BattlesWon(n) => self.battles_won >= *n,
+            Milestone::Kills(n) => self.kills >= *n,
+            Milestone::RunsPlayed(n) => self.runs_played >= *n,
+            Milestone::RunsCleared(n) => self.runs_cleared >= *n,
+            Milestone::FlawlessClear => self.flawless_clears > 0,
+        }
+    }
+
+    /// How to unlock a unit, or None if the unit can already be picked
+    pub fn unit_lock_hint(&self, name: &str) -> Option<String> {
+        UNLOCKS.iter()
+            .find(|u| matches!(u.reward, Reward::Unit(n) if n == name))
+            .filter(|u| !self.is_unlocked(u))
+            .map(|u| u.milestone.hint())
+    }
+
+    fn rewards(&self) -> impl Iterator<Item = &'static Reward> + '_ {
+        UNLOCKS.iter().filter(|u| self.is_unlocked(u)).map(|u| &u.reward)
+    }
+
+    pub fn starting_gold(&self) -> u32 {
+        self.rewards().map(|r| match r {
+            Reward::StartingGold(gold) => *gold,
+            _ => 0,
+        }).sum()
+    }
+
+    pub fn starting_weapons(&self) -> Vec<Rarity> {
+        self.rewards().filter_map(|r| match r {
+            Reward::StartingWeapon(rarity) => Some(rarity.clone()),
+            _ => None,
+        }).collect()
+    }
+
+    pub fn min_chest_rarity(&self) -> Rarity {
+        self.rewards().filter_map(|r| match r {
+            Reward::ChestRarity(rarity) => Some(rarity.clone()),
+            _ => None,
+        }).max().unwrap_or(Rarity::Common)
+    }
+
+    /// Adds a finished run to the totals and returns the names of anything it unlocked
+    pub fn record_run(&mut self, stats: &RunStats, cleared: bool) -> Vec<String> {
+        self.runs_played += 1;
+        self.battles_won += stats.battles_won;
+        self.kills += stats.units.values().map(|u| u.kills).sum::<u32>();
+        if cleared {
+            self.runs_cleared += 1;
+            if stats.units_lost.is_empty() {
+                self.flawless_clears += 1;
+            }
+        }
+
+        let mut earned = Vec::new();
+        for unlock in UNLOCKS.iter() {
+            if !self.is_unlocked(unlock) && self.reached(&unlock.milestone) {
+                self.unlocked.push(unlock.id.to_string());
+                earned.push(unlock.reward.get_name());
+            }
+        }
+        earned
+    }
+
+    pub fn save(&self) {
+        match storage::save(META_FILE, self) {
+            Ok(path) => info!("Saved meta progress to {}", path.display()),
+            Err(e) => error!("Unable to save meta progress. {}", e),
+        }
+    }
+}
+
+pub fn meta_plugin(app: &mut App) {
+    app.insert_resource(load_meta_progress());
+}
+
+fn load_meta_progress() -> MetaProgress {
+    match storage::load(META_FILE) {
+        Ok(meta) => meta.unwrap_or_default(),
+        Err(e) => {
+            // Starts fresh, keeping the broken file aside so the next save doesn't wipe the unlocks
+            error!("Unable to load meta progress. {}", e);
+            match storage::backup(META_FILE) {
+                Ok(path) => warn!("Moved the unreadable meta progress to {}", path.display()),
+                Err(e) => error!("Unable to back up meta progress. {}", e),
+            }
+            MetaProgress::default()
+        }
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use crate::game::run_stats::RunStats;
+    #[allow(unused_imports)]
+    use sim::Rarity;
+    #[allow(unused_imports)]
+    use crate::game::storage::from_ron;
+    #[allow(unused_imports)]
+    use super::{MetaProgress, Reward, UNLOCKS};
+
+    #[test]
+    fn test_unlock_ids_are_unique() {
+        for (i, unlock) in UNLOCKS.iter().enumerate() {
+            assert!(UNLOCKS[i + 1..].iter().all(|u| u.id != unlock.id), "{} is used twice", unlock.id);
+        }
+    }
+
+    #[test]
+    fn test_record_run_unlocks_once() {
+        let mut meta = MetaProgress::default();
+        let mut stats = RunStats { battles_won: 5, ..Default::default() };
+        stats.record_kill("Scooby");
+
+        assert!(meta.unit_lock_hint("Slow Loris").is_some());
+        assert_eq!(meta.record_run(&stats, false), vec!["Slow Loris".to_string()]);
+        assert_eq!(meta.unit_lock_hint("Slow Loris"), None);
+        assert_eq!(meta.kills, 1);
+
+        // Second run unlocks the starting gold, but not the unit again
+        let earned = meta.record_run(&RunStats::default(), false);
+        assert_eq!(earned, vec![Reward::StartingGold(100).get_name()]);
+        assert_eq!(meta.starting_gold(), 100);
+    }
+
+    #[test]
+    fn test_clear_rewards() {
+        let mut meta = MetaProgress::default();
+        let lost = RunStats { units_lost: vec!["Cat".to_string()], ..Default::default() };
+
+        meta.record_run(&lost, true);
+        assert!(meta.unit_lock_hint("Double D").is_some());
+        assert_eq!(meta.unit_lock_hint("Chipmanzee"), None);
+        assert_eq!(meta.min_chest_rarity(), Rarity::Common);
+
+        meta.record_run(&RunStats::default(), true);
+        assert_eq!(meta.unit_lock_hint("Double D"), None);
+        assert_eq!(meta.min_chest_rarity(), Rarity::Uncommon);
+        // Units that were never locked have no hint
+        assert_eq!(meta.unit_lock_hint("Scooby"), None);
+    }
+
+    #[test]
+    fn test_missing_fields_use_defaults() {
+        let meta: MetaProgress = from_ron("(runs_played: 4, unlocked: [\"tom\"])").unwrap();
+        assert_eq!(meta.runs_played, 4);
+        assert_eq!(meta.kills, 0);
+        assert_eq!(meta.unlocked, vec!["tom".to_string()]);
+    }
+}
@@ -1,25 +1,52 @@
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 use bevy_asset_loader::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::AppState;
 mod units;
-mod weapon;
+mod item;
 mod ui;
 mod unit_selection;
 mod map_selection;
 mod rewards;
 mod chest_selection;
+mod shop;
 mod assets;
 mod battle_scene;
+mod support;
+mod roster;
+mod run_stats;
+mod storage;
+mod meta;
+mod save;
+mod settings;
+mod input;
+mod rules;
+mod catalogue;
 
 use units::*;
 use unit_selection::unit_selection_plugin;
 use map_selection::map_selection_plugin;
 use rewards::rewards_plugin;
 use chest_selection::chest_selection_plugin;
+use shop::shop_plugin;
 use assets::*;
 use battle_scene::battle_scene_plugin;
+use support::support_plugin;
+use roster::RunRoster;
+use run_stats::run_stats_plugin;
+use meta::meta_plugin;
+use save::save_plugin;
+use settings::settings_plugin;
+use input::input_plugin;
+use rules::{rules_plugin, Rulesets};
+use catalogue::{catalogue_plugin, AvailableWeapons};
+use ui::weapon_card_plugin;
+
+pub use save::{RunSave, ResumeRun};
+pub use settings::{Setting, Settings};
+pub use input::{Action, ActionState, ActionSystem, Binding};
 
 const GRID_SIZE: i32 = 16;
 const GRID_SIZE_VEC: IVec2 = IVec2 {
@@ -35,10 +62,6 @@ struct Player;
 #[derive(Default, Component)]
 struct Enemy;
 
-/// Tracks the Units that were selected for a run.
-#[derive(Resource)]
-struct SelectedUnits(Vec<UnitStats>);
-
 #[derive(Resource, AssetCollection)]
 pub struct AvailableUnits {
     #[asset(path="rouge/available.units.ron")]
@@ -48,7 +71,7 @@ pub struct AvailableUnits {
 #[derive(Component)]
 struct OnLevelScreen;
 
-#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, SubStates)]
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, SubStates, Deserialize, Serialize)]
 #[source(AppState = AppState::Game)]
 // TODO: Create a top level State and per turn state.
 pub enum GameState {
@@ -58,6 +81,7 @@ pub enum GameState {
     MapSelection,
     InBattle,
     ChestSelection,
+    Shop,
     Rewards
 }
 
@@ -67,16 +91,27 @@ pub fn game_plugin(app: &mut App) {
     app
         .add_plugins(LdtkPlugin)
         .add_plugins(GameAssetPlugin)
-        .insert_resource(LevelSelection::index(0))
-        .insert_resource(SelectedUnits(Vec::new()))
+        .init_resource::<RunRoster>()
         .add_sub_state::<GameState>()
         .add_loading_state(LoadingState::new(GameState::Loading)
             .continue_to_state(GameState::UnitSelection)
             .load_collection::<AvailableUnits>()
+            .load_collection::<Rulesets>()
+            .load_collection::<AvailableWeapons>()
         )
         .add_plugins(unit_selection_plugin)
         .add_plugins(map_selection_plugin)
         .add_plugins(rewards_plugin)
         .add_plugins(chest_selection_plugin)
-        .add_plugins(battle_scene_plugin);
+        .add_plugins(shop_plugin)
+        .add_plugins(battle_scene_plugin)
+        .add_plugins(support_plugin)
+        .add_plugins(run_stats_plugin)
+        .add_plugins(meta_plugin)
+        .add_plugins(save_plugin)
+        .add_plugins(settings_plugin)
+        .add_plugins(rules_plugin)
+        .add_plugins(catalogue_plugin)
+        .add_plugins(input_plugin)
+        .add_plugins(weapon_card_plugin);
 }
@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::run_stats::RunStats;
+use crate::game::storage::{self, StorageError};
+
+const HISTORY_FILE: &str = "run_history.ron";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UnitSummary {
+    pub name: String,
+    pub kills: u32,
+    pub damage: u32,
+}
+
+/// What gets written to the run history once a run is over
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RunSummary {
+    pub seed: u64,
+    pub cleared: bool,
+    pub battles_won: u32,
+    pub turns: u32,
+    pub units: Vec<UnitSummary>,
+    pub units_lost: Vec<String>,
+    pub best_weapon: Option<String>,
+    pub time_played_secs: f32,
+    pub unlocks: Vec<String>,
+}
+
+impl RunSummary {
+    pub fn new(stats: &RunStats, seed: u64, cleared: bool) -> Self {
+        Self {
+            seed,
+            cleared,
+            battles_won: stats.battles_won,
+            turns: stats.turns,
+            units: stats.units.iter()
+                .map(|(name, s)| UnitSummary { name: name.clone(), kills: s.kills, damage: s.damage })
+                .collect(),
+            units_lost: stats.units_lost.clone(),
+            best_weapon: stats.best_weapon.as_ref().map(|w| w.get_name()),
+            time_played_secs: stats.time_played,
+            unlocks: stats.unlocks.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct RunHistory {
+    pub runs: Vec<RunSummary>,
+}
+
+impl RunHistory {
+    /// Appends the run to the history file, creating it if needed.
+    /// A history that can't be parsed is left alone rather than overwritten.
+    pub fn append(summary: RunSummary) -> Result<(), StorageError> {
+        let mut history: RunHistory = storage::load(HISTORY_FILE)?.unwrap_or_default();
+        history.runs.push(summary);
+
+        let path = storage::save(HISTORY_FILE, &history)?;
+        info!("Saved run to {}", path.display());
+        Ok(())
+    }
+}
+
+/// Formats seconds as h:mm:ss, or m:ss for runs under an hour
+pub fn format_time(secs: f32) -> String {
+    let total = secs.max(0.0) as u64;
+    let (hours, minutes, seconds) = (total / 3600, (total / 60) % 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use crate::game::storage::{from_ron, to_ron};
+    #[allow(unused_imports)]
+    use super::{format_time, RunHistory, RunSummary, UnitSummary};
+
+    #[test]
+    fn test_history_round_trip() {
+        let history = RunHistory {
+            runs: vec![RunSummary {
+                seed: 42,
+                cleared: true,
+                battles_won: 5,
+                turns: 31,
+                units: vec![UnitSummary { name: "Scooby".to_string(), kills: 4, damage: 37 }],
+                units_lost: vec!["Courage".to_string()],
+                best_weapon: Some("rare-sword".to_string()),
+                time_played_secs: 812.5,
+                unlocks: vec![],
+            }],
+        };
+
+        let text = to_ron(&history).unwrap();
+        assert_eq!(from_ron::<RunHistory>(&text).unwrap(), history);
+        assert!(from_ron::<RunHistory>("not a history").is_err());
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0.0), "0:00");
+        assert_eq!(format_time(75.9), "1:15");
+        assert_eq!(format_time(3725.0), "1:02:05");
+    }
+}
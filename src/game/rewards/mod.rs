@@ -1,7 +1,15 @@
 use bevy::prelude::*;
+use super::map_selection::RunMap;
+use super::meta::MetaProgress;
+use super::roster::RunRoster;
+use super::run_stats::RunStats;
 use super::GameState;
 use crate::{despawn_screen, AppState};
 
+mod history;
+
+use history::{format_time, RunHistory, RunSummary};
+
 #[derive(Component)]
 struct OnRewardsScreen;
 
@@ -10,12 +18,19 @@ struct ConfirmButton;
 
 pub fn rewards_plugin(app: &mut App) {
     app
-        .add_systems(OnEnter(GameState::Rewards), init_screen)
-        .add_systems(Update, menu_action)
+        .add_systems(OnEnter(GameState::Rewards), (record_unlocks, init_screen, save_run).chain())
+        .add_systems(Update, menu_action.run_if(in_state(GameState::Rewards)))
         .add_systems(OnExit(GameState::Rewards), despawn_screen::<OnRewardsScreen>);
 }
 
-fn init_screen(mut commands: Commands) {
+fn init_screen(
+    mut commands: Commands,
+    run_stats: Res<RunStats>,
+    run_map: Res<RunMap>,
+    roster: Res<RunRoster>,
+) {
+    let summary = summary_lines(&RunSummary::new(&run_stats, run_map.seed, is_cleared(&run_map, &roster)));
+
     commands.spawn((
         Node {
             width: Val::Percent(100.0),
@@ -27,9 +42,7 @@ fn init_screen(mut commands: Commands) {
         OnRewardsScreen
     )).with_children(|parent| {
         parent.spawn((
-            Button {
-                ..default()
-            },
+            Button,
             Node {
                 width: Val::Px(250.0),
                 height: Val::Px(65.0),
@@ -61,16 +74,67 @@ fn init_screen(mut commands: Commands) {
         parent.spawn((
             Node {
                 width: Val::Percent(50.0),
-                height: Val::Percent(50.0),
+                min_height: Val::Percent(50.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(10.0)),
                 ..Default::default()
             },
             BackgroundColor(Color::WHITE),
-            TextColor(Color::BLACK),
-            Text::new("WHERE THE REWARD CHOICE SHOULD GO")
-        ));
+        )).with_children(|parent| {
+            for line in summary {
+                parent.spawn((
+                    Text::new(line),
+                    TextColor(Color::BLACK),
+                ));
+            }
+        });
     });
 }
 
+// A run is cleared when the last node was reached with someone still standing
+fn is_cleared(run_map: &RunMap, roster: &RunRoster) -> bool {
+    run_map.is_finished() && !roster.units.is_empty()
+}
+
+fn summary_lines(summary: &RunSummary) -> Vec<String> {
+    let mut lines = vec![
+        if summary.cleared { "Run cleared!".to_string() } else { "Run over".to_string() },
+        format!("Battles won: {}", summary.battles_won),
+        format!("Turns taken: {}", summary.turns),
+    ];
+
+    for unit in summary.units.iter() {
+        lines.push(format!("  {}: {} kills, {} damage", unit.name, unit.kills, unit.damage));
+    }
+
+    let lost = if summary.units_lost.is_empty() { "None".to_string() } else { summary.units_lost.join(", ") };
+    lines.push(format!("Units lost: {}", lost));
+    lines.push(format!("Best weapon: {}", summary.best_weapon.as_deref().unwrap_or("None")));
+    let unlocks = if summary.unlocks.is_empty() { "None".to_string() } else { summary.unlocks.join(", ") };
+    lines.push(format!("Unlocks: {}", unlocks));
+    lines.push(format!("Seed: {}", summary.seed));
+    lines.push(format!("Time played: {}", format_time(summary.time_played_secs)));
+    lines
+}
+
+fn record_unlocks(
+    mut meta: ResMut<MetaProgress>,
+    mut run_stats: ResMut<RunStats>,
+    run_map: Res<RunMap>,
+    roster: Res<RunRoster>,
+) {
+    let earned = meta.record_run(&run_stats, is_cleared(&run_map, &roster));
+    run_stats.unlocks.extend(earned);
+    meta.save();
+}
+
+fn save_run(run_stats: Res<RunStats>, run_map: Res<RunMap>, roster: Res<RunRoster>) {
+    let summary = RunSummary::new(&run_stats, run_map.seed, is_cleared(&run_map, &roster));
+    if let Err(e) = RunHistory::append(summary) {
+        error!("Unable to save the run history. {}", e);
+    }
+}
+
 fn menu_action(
     interaction_query: Query<
         &Interaction,
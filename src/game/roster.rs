@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use sim::WeaponCollection;
+
+use super::item::Item;
+use super::run_stats::RunStats;
+use super::units::{Inventory, UnitStats, WeaponPack};
+
+pub const STARTING_GOLD: u32 = 100;
+
+/// A unit in the run, along with everything it carries between battles
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RosterUnit {
+    pub stats: UnitStats,
+    pub max_hp: u32,
+    pub pack: WeaponPack,
+    pub inventory: Inventory,
+}
+
+impl RosterUnit {
+    pub fn new(stats: UnitStats, catalogue: &WeaponCollection) -> Self {
+        Self {
+            max_hp: stats.hp,
+            stats,
+            pack: WeaponPack::new(catalogue),
+            inventory: Inventory {
+                items: vec![Item::Vulnerary],
+            },
+        }
+    }
+
+    pub fn is_fallen(&self) -> bool {
+        self.stats.hp == 0
+    }
+}
+
+/// Tracks the Units that were selected for a run and their state between battles.
+#[derive(Default, Resource, Clone, Deserialize, Serialize)]
+pub struct RunRoster {
+    pub units: Vec<RosterUnit>,
+    /// Casual mode revives fallen units after a battle instead of removing them
+    pub casual: bool,
+    /// Shared by the whole party
+    pub gold: u32,
+}
+
+impl RunRoster {
+    pub fn clear(&mut self) {
+        self.units.clear();
+        self.casual = false;
+        self.gold = STARTING_GOLD;
+    }
+
+    /// Takes the gold if there is enough of it
+    pub fn spend(&mut self, amount: u32) -> bool {
+        if self.gold < amount {
+            return false;
+        }
+        self.gold -= amount;
+        true
+    }
+
+    pub fn fall(&mut self, index: usize) {
+        if let Some(unit) = self.units.get_mut(index) {
+            unit.stats.hp = 0;
+        }
+    }
+
+    /// Removes fallen units, or revives them at full health in casual mode
+    pub fn resolve_fallen(&mut self) {
+        if self.casual {
+            for unit in self.units.iter_mut().filter(|u| u.is_fallen()) {
+                unit.stats.hp = unit.max_hp;
+            }
+        } else {
+            self.units.retain(|u| !u.is_fallen());
+        }
+    }
+}
+
+/// Links a unit on the map back to its place in the RunRoster
+#[derive(Component)]
+pub struct RosterIndex(pub usize);
+
+/// Writes the battle results of every surviving unit back to the roster
+pub fn write_back_roster(
+    mut roster: ResMut<RunRoster>,
+    mut run_stats: ResMut<RunStats>,
+    units_q: Query<(&RosterIndex, &UnitStats, &WeaponPack, &Inventory)>,
+) {
+    for (index, stats, pack, inventory) in units_q.iter() {
+        if let Some(unit) = roster.units.get_mut(index.0) {
+            unit.stats = stats.clone();
+            unit.pack = pack.clone();
+            unit.inventory = inventory.clone();
+        }
+    }
+
+    if !roster.casual {
+        let fallen = roster.units.iter().filter(|u| u.is_fallen()).map(|u| u.stats.name.clone());
+        run_stats.units_lost.extend(fallen);
+    }
+    roster.resolve_fallen();
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use crate::game::units::UnitStats;
+    #[allow(unused_imports)]
+    use sim::WeaponCollection;
+    #[allow(unused_imports)]
+    use super::{RosterUnit, RunRoster};
+
+    #[allow(dead_code)]
+    fn get_roster(casual: bool) -> RunRoster {
+        RunRoster {
+            units: vec![
+                RosterUnit::new(UnitStats(sim::UnitStats { name: "Scooby".to_string(), hp: 10, ..Default::default() }), &WeaponCollection::default()),
+                RosterUnit::new(UnitStats(sim::UnitStats { name: "Courage".to_string(), hp: 12, ..Default::default() }), &WeaponCollection::default()),
+            ],
+            casual,
+            gold: 0,
+        }
+    }
+
+    #[test]
+    fn test_fallen_units_removed() {
+        let mut roster = get_roster(false);
+        roster.fall(0);
+        roster.resolve_fallen();
+
+        assert_eq!(roster.units.len(), 1);
+        assert_eq!(roster.units[0].stats.name, "Courage");
+    }
+
+    #[test]
+    fn test_casual_revives_fallen_units() {
+        let mut roster = get_roster(true);
+        roster.fall(1);
+        roster.resolve_fallen();
+
+        assert_eq!(roster.units.len(), 2);
+        assert_eq!(roster.units[1].stats.hp, 12);
+    }
+}
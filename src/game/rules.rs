@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use sim::Ruleset;
+use super::settings::Settings;
+
+/// Which rules.ron preset battles are fought with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum RulesPreset {
+    #[default]
+    Classic,
+    Hardcore,
+}
+
+impl RulesPreset {
+    pub fn cycle(&self) -> Self {
+        match self {
+            RulesPreset::Classic => RulesPreset::Hardcore,
+            RulesPreset::Hardcore => RulesPreset::Classic,
+        }
+    }
+}
+
+/// A rules.ron file as loaded by the asset server
+#[derive(Asset, TypePath, Debug, Deserialize)]
+#[serde(transparent)]
+pub struct RulesetAsset(pub Ruleset);
+
+/// The rules battles are currently fought with
+#[derive(Resource, Default, Deref, PartialEq)]
+pub struct CombatRules(pub Ruleset);
+
+#[derive(Resource, AssetCollection)]
+pub struct Rulesets {
+    #[asset(path="rouge/classic.rules.ron")]
+    pub classic: Handle<RulesetAsset>,
+    #[asset(path="rouge/hardcore.rules.ron")]
+    pub hardcore: Handle<RulesetAsset>,
+}
+
+impl Rulesets {
+    pub fn get(&self, preset: RulesPreset) -> &Handle<RulesetAsset> {
+        match preset {
+            RulesPreset::Classic => &self.classic,
+            RulesPreset::Hardcore => &self.hardcore,
+        }
+    }
+}
+
+pub fn rules_plugin(app: &mut App) {
+    app
+        // Classic until the presets finish loading
+        .init_resource::<CombatRules>()
+        .add_systems(Update, select_ruleset.run_if(resource_exists::<Rulesets>));
+}
+
+/// Copies the chosen preset into the CombatRules resource, again whenever the setting
+/// changes or the file is edited
+fn select_ruleset(
+    settings: Res<Settings>,
+    rulesets: Res<Rulesets>,
+    assets: Res<Assets<RulesetAsset>>,
+    mut events: EventReader<AssetEvent<RulesetAsset>>,
+    mut rules: ResMut<CombatRules>,
+) {
+    let reloaded = events.read().count() > 0;
+    if !reloaded && !settings.is_changed() && !rulesets.is_added() { return }
+
+    if let Some(preset) = assets.get(rulesets.get(settings.rules)) {
+        if rules.set_if_neq(CombatRules(preset.0.clone())) {
+            info!("Using the {:?} rules", settings.rules);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use sim::Weapon;
+use super::GameState;
+use crate::AppState;
+
+#[derive(Default, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct UnitRunStats {
+    pub kills: u32,
+    pub damage: u32,
+}
+
+/// Everything the results screen reports about the current run
+#[derive(Default, Resource, Clone, Debug, Deserialize, Serialize)]
+pub struct RunStats {
+    pub battles_won: u32,
+    pub turns: u32,
+    /// Keyed by unit name
+    pub units: BTreeMap<String, UnitRunStats>,
+    pub units_lost: Vec<String>,
+    pub best_weapon: Option<Weapon>,
+    pub time_played: f32,
+    pub unlocks: Vec<String>,
+}
+
+impl RunStats {
+    pub fn record_damage(&mut self, name: &str, damage: u32) {
+        self.units.entry(name.to_string()).or_default().damage += damage;
+    }
+
+    pub fn record_kill(&mut self, name: &str) {
+        self.units.entry(name.to_string()).or_default().kills += 1;
+    }
+
+    /// Keeps the weapon if it beats the best one so far, by rarity and then attack
+    pub fn found_weapon(&mut self, weapon: &Weapon) {
+        let is_better = match &self.best_weapon {
+            Some(best) => (&weapon.rarity, weapon.attack) > (&best.rarity, best.attack),
+            None => true,
+        };
+        if is_better {
+            self.best_weapon = Some(weapon.clone());
+        }
+    }
+}
+
+pub fn run_stats_plugin(app: &mut App) {
+    app
+        .init_resource::<RunStats>()
+        .add_systems(Update, track_time_played.run_if(
+            in_state(AppState::Game).and(not(in_state(GameState::Rewards)))
+        ));
+}
+
+fn track_time_played(time: Res<Time>, mut run_stats: ResMut<RunStats>) {
+    run_stats.time_played += time.delta_secs();
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use sim::{Rarity, Weapon};
+    #[allow(unused_imports)]
+    use super::RunStats;
+
+    #[test]
+    fn test_best_weapon_by_rarity() {
+        let mut stats = RunStats::default();
+        let strong = Weapon { attack: 12, ..Default::default() };
+        let rare = Weapon { attack: 5, rarity: Rarity::Rare, ..Default::default() };
+
+        stats.found_weapon(&strong);
+        stats.found_weapon(&rare);
+        stats.found_weapon(&Weapon { attack: 20, ..Default::default() });
+        assert_eq!(stats.best_weapon, Some(rare));
+    }
+
+    #[test]
+    fn test_record_per_unit() {
+        let mut stats = RunStats::default();
+        stats.record_damage("Scooby", 4);
+        stats.record_damage("Scooby", 3);
+        stats.record_kill("Courage");
+
+        assert_eq!(stats.units["Scooby"].damage, 7);
+        assert_eq!(stats.units["Scooby"].kills, 0);
+        assert_eq!(stats.units["Courage"].kills, 1);
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::battle_scene::{BattleSave, PendingBattle};
+use super::chest_selection::ChestRewards;
+use super::shop::ShopStock;
+use super::map_selection::RunMap;
+use super::roster::RunRoster;
+use super::run_stats::RunStats;
+use super::storage::{self, StorageError};
+use super::support::Supports;
+use sim::Weapon;
+use super::GameState;
+use crate::AppState;
+
+const SAVE_FILE: &str = "run.ron";
+/// Bump whenever RunSave changes in a way older saves can't be read
+pub const SAVE_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum SaveError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("There is no saved run")]
+    NoSave,
+    #[error("The save is from version {0}, expected version {SAVE_VERSION}")]
+    Version(u32),
+}
+
+// Read before the rest of the save so saves from other versions are refused
+// instead of failing somewhere in the middle
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+/// The run map is rebuilt from its seed, so only the path taken is saved
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SavedMap {
+    pub seed: u64,
+    pub current: Option<usize>,
+    pub visited: Vec<usize>,
+}
+
+/// Everything needed to pick a run back up after quitting
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RunSave {
+    pub version: u32,
+    /// The screen the run resumes on
+    pub state: GameState,
+    pub roster: RunRoster,
+    pub map: SavedMap,
+    pub stats: RunStats,
+    pub supports: HashMap<(String, String), u32>,
+    /// Only set when the run was suspended mid-battle
+    #[serde(default)]
+    pub battle: Option<BattleSave>,
+    /// The rolled chests or shop stock, so resuming doesn't roll them again
+    #[serde(default)]
+    pub chests: Vec<Weapon>,
+    #[serde(default)]
+    pub shop: Vec<Weapon>,
+}
+
+/// Inserted by the main menu's Continue button. The run is restored once the game is loaded.
+#[derive(Resource)]
+pub struct ResumeRun(pub RunSave);
+
+impl RunSave {
+    pub fn new(state: GameState, roster: &RunRoster, run_map: &RunMap, stats: &RunStats, supports: &Supports) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            state,
+            roster: roster.clone(),
+            map: SavedMap {
+                seed: run_map.seed,
+                current: run_map.current,
+                visited: run_map.visited.clone(),
+            },
+            stats: stats.clone(),
+            supports: supports.points().clone(),
+            battle: None,
+            chests: Vec::new(),
+            shop: Vec::new(),
+        }
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, SaveError> {
+        let header: SaveHeader = storage::from_ron(text)?;
+        if header.version != SAVE_VERSION {
+            return Err(SaveError::Version(header.version));
+        }
+        Ok(storage::from_ron(text)?)
+    }
+
+    pub fn exists() -> bool {
+        storage::exists(SAVE_FILE)
+    }
+
+    pub fn load() -> Result<Self, SaveError> {
+        let text = storage::read(SAVE_FILE)?.ok_or(SaveError::NoSave)?;
+        Self::from_ron(&text)
+    }
+
+    pub fn save(&self) -> Result<(), SaveError> {
+        storage::save(SAVE_FILE, self)?;
+        Ok(())
+    }
+
+    pub fn delete() {
+        if let Err(e) = storage::remove(SAVE_FILE) {
+            error!("Unable to delete the saved run. {}", e);
+        }
+    }
+
+    /// Puts the saved run back into the run resources and returns the state to resume on
+    pub fn restore(
+        self,
+        roster: &mut RunRoster,
+        run_map: &mut RunMap,
+        stats: &mut RunStats,
+        supports: &mut Supports,
+    ) -> GameState {
+        *roster = self.roster;
+        *run_map = RunMap::generate(self.map.seed);
+        run_map.current = self.map.current;
+        run_map.visited = self.map.visited;
+        *stats = self.stats;
+        supports.set_points(self.supports);
+        self.state
+    }
+}
+
+pub fn save_plugin(app: &mut App) {
+    app
+        .add_systems(Update, autosave.run_if(in_state(AppState::Game).and(run_changed)))
+        .add_systems(Update, resume_run.run_if(
+            in_state(GameState::UnitSelection).and(resource_exists::<ResumeRun>)
+        ));
+}
+
+// Checked together so neither change is missed or seen late. With `.or` the second
+// condition is skipped whenever the first is true, and then fires frames later.
+fn run_changed(game_state: Res<State<GameState>>, run_map: Res<RunMap>) -> bool {
+    game_state.is_changed() || run_map.is_changed()
+}
+
+// Runs on every GameState transition, and when the map changes without one (like resting)
+#[allow(clippy::too_many_arguments)]
+fn autosave(
+    game_state: Res<State<GameState>>,
+    pending: Option<Res<PendingBattle>>,
+    roster: Res<RunRoster>,
+    run_map: Res<RunMap>,
+    stats: Res<RunStats>,
+    supports: Res<Supports>,
+    rewards: Res<ChestRewards>,
+    stock: Res<ShopStock>,
+) {
+    match game_state.get() {
+        // Nothing to save until the party is picked
+        GameState::Loading | GameState::UnitSelection => (),
+        GameState::Rewards => RunSave::delete(),
+        // Keeps the suspended battle in the save until it's back on the map
+        GameState::InBattle if pending.is_some() => (),
+        state => {
+            let mut save = RunSave::new(*state, &roster, &run_map, &stats, &supports);
+            match state {
+                GameState::ChestSelection => save.chests = rewards.chests.clone(),
+                GameState::Shop => save.shop = stock.weapons.clone(),
+                _ => (),
+            }
+            match save.save() {
+                Ok(()) => info!("Autosaved the run on {:?}", state),
+                Err(e) => error!("Unable to autosave the run. {}", e),
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn resume_run(
+    mut commands: Commands,
+    resume: Res<ResumeRun>,
+    mut roster: ResMut<RunRoster>,
+    mut run_map: ResMut<RunMap>,
+    mut stats: ResMut<RunStats>,
+    mut supports: ResMut<Supports>,
+    mut rewards: ResMut<ChestRewards>,
+    mut stock: ResMut<ShopStock>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let mut save = resume.0.clone();
+    if let Some(battle) = save.battle.take() {
+        commands.insert_resource(PendingBattle(battle));
+    }
+    *rewards = ChestRewards { chests: std::mem::take(&mut save.chests), ..default() };
+    *stock = ShopStock { weapons: std::mem::take(&mut save.shop), ..default() };
+    let state = save.restore(&mut roster, &mut run_map, &mut stats, &mut supports);
+    info!("Resuming the run on {:?}", state);
+    game_state.set(state);
+    commands.remove_resource::<ResumeRun>();
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use std::collections::HashMap;
+    #[allow(unused_imports)]
+    use crate::game::map_selection::RunMap;
+    #[allow(unused_imports)]
+    use crate::game::roster::{RosterUnit, RunRoster};
+    #[allow(unused_imports)]
+    use crate::game::run_stats::RunStats;
+    #[allow(unused_imports)]
+    use crate::game::storage::to_ron;
+    #[allow(unused_imports)]
+    use crate::game::support::Supports;
+    #[allow(unused_imports)]
+    use crate::game::units::UnitStats;
+    #[allow(unused_imports)]
+    use sim::{Weapon, WeaponCollection};
+    #[allow(unused_imports)]
+    use crate::game::GameState;
+    #[allow(unused_imports)]
+    use super::{RunSave, SaveError};
+
+    #[test]
+    fn test_save_round_trip() {
+        let mut roster = RunRoster { gold: 340, ..Default::default() };
+        roster.units.push(RosterUnit::new(UnitStats(sim::UnitStats { name: "Scooby".to_string(), hp: 10, ..Default::default() }), &WeaponCollection::default()));
+        let mut run_map = RunMap::generate(9);
+        let first = run_map.reachable()[1];
+        run_map.visit(first);
+        let mut stats = RunStats { battles_won: 2, ..Default::default() };
+        stats.record_kill("Scooby");
+        let mut supports = Supports::default();
+        supports.set_points(HashMap::from([(("Scooby".to_string(), "Courage".to_string()), 3)]));
+
+        let save = RunSave::new(GameState::Shop, &roster, &run_map, &stats, &supports);
+        let loaded = RunSave::from_ron(&to_ron(&save).unwrap()).unwrap();
+
+        let (mut new_roster, mut new_map, mut new_stats, mut new_supports) =
+            (RunRoster::default(), RunMap::default(), RunStats::default(), Supports::default());
+        let state = loaded.restore(&mut new_roster, &mut new_map, &mut new_stats, &mut new_supports);
+
+        assert_eq!(state, GameState::Shop);
+        assert_eq!(new_roster.gold, 340);
+        assert_eq!(new_roster.units[0].pack.weapons, roster.units[0].pack.weapons);
+        assert_eq!(new_map.current, Some(first));
+        assert_eq!(new_map.reachable(), run_map.reachable());
+        assert_eq!(new_stats.units["Scooby"].kills, 1);
+        assert_eq!(new_supports.points(), supports.points());
+    }
+
+    #[test]
+    fn test_refuses_other_versions() {
+        let save = RunSave::new(GameState::MapSelection, &RunRoster::default(), &RunMap::generate(1), &RunStats::default(), &Supports::default());
+        let text = to_ron(&RunSave { version: 0, ..save }).unwrap();
+
+        assert!(matches!(RunSave::from_ron(&text), Err(SaveError::Version(0))));
+    }
+
+    #[test]
+    fn test_rolled_rewards_are_saved() {
+        let save = RunSave::new(GameState::ChestSelection, &RunRoster::default(), &RunMap::generate(4), &RunStats::default(), &Supports::default());
+        let chests = vec![Weapon::default(), Weapon { attack: 9, ..Default::default() }];
+        let loaded = RunSave::from_ron(&to_ron(&RunSave { chests: chests.clone(), ..save }).unwrap()).unwrap();
+
+        assert_eq!(loaded.chests, chests);
+        assert!(loaded.shop.is_empty());
+    }
+}
@@ -0,0 +1,268 @@
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use bevy::window::{MonitorSelection, PrimaryWindow, WindowMode, WindowResolution};
+use serde::{Deserialize, Serialize};
+
+use super::input::Bindings;
+use super::rules::RulesPreset;
+use super::storage;
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+pub const EDGE_SCROLL_SPEED: f32 = 5.;
+pub const ZOOM_SPEED: f32 = 0.02;
+pub const RESOLUTIONS: [(u32, u32); 4] = [(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "Windowed",
+            DisplayMode::Borderless => "Borderless",
+            DisplayMode::Fullscreen => "Fullscreen",
+        }
+    }
+
+    fn window_mode(&self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+            DisplayMode::Fullscreen => WindowMode::Fullscreen(MonitorSelection::Current),
+        }
+    }
+
+    fn cycle(&self, step: i32) -> Self {
+        let modes = [DisplayMode::Windowed, DisplayMode::Borderless, DisplayMode::Fullscreen];
+        let index = modes.iter().position(|m| m == self).unwrap_or(0) as i32;
+        modes[(index + step).rem_euclid(modes.len() as i32) as usize]
+    }
+}
+
+/// How much the combat forecast shows when hovering an enemy
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ForecastDetail {
+    /// HP, damage and hit chance
+    Compact,
+    #[default]
+    Full,
+}
+
+/// Player settings, saved in the config directory. Missing fields fall back to
+/// their defaults so older config files still load.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub display_mode: DisplayMode,
+    pub resolution: (u32, u32),
+    pub edge_scroll_speed: f32,
+    pub zoom_speed: f32,
+    /// Multiplier on how fast units move and animate
+    pub animation_speed: f32,
+    /// Resolve fights without lunges, flashes or damage numbers
+    pub skip_combat_animations: bool,
+    pub forecast: ForecastDetail,
+    /// Which rules.ron preset battles use
+    pub rules: RulesPreset,
+    /// Only the actions the player rebound, see Bindings
+    pub bindings: Bindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            display_mode: DisplayMode::Windowed,
+            resolution: RESOLUTIONS[0],
+            edge_scroll_speed: EDGE_SCROLL_SPEED,
+            zoom_speed: ZOOM_SPEED,
+            animation_speed: 1.0,
+            skip_combat_animations: false,
+            forecast: ForecastDetail::Full,
+            rules: RulesPreset::Classic,
+            bindings: Bindings::default(),
+        }
+    }
+}
+
+/// A single row on the settings screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Setting {
+    MasterVolume,
+    DisplayMode,
+    Resolution,
+    EdgeScrollSpeed,
+    ZoomSpeed,
+    AnimationSpeed,
+    CombatAnimations,
+    Forecast,
+    Rules,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 9] = [
+        Setting::MasterVolume,
+        Setting::DisplayMode,
+        Setting::Resolution,
+        Setting::EdgeScrollSpeed,
+        Setting::ZoomSpeed,
+        Setting::AnimationSpeed,
+        Setting::CombatAnimations,
+        Setting::Forecast,
+        Setting::Rules,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Setting::MasterVolume => "Master volume",
+            Setting::DisplayMode => "Window mode",
+            Setting::Resolution => "Resolution",
+            Setting::EdgeScrollSpeed => "Edge scroll speed",
+            Setting::ZoomSpeed => "Zoom speed",
+            Setting::AnimationSpeed => "Animation speed",
+            Setting::CombatAnimations => "Combat animations",
+            Setting::Forecast => "Combat forecast",
+            Setting::Rules => "Combat rules",
+        }
+    }
+}
+
+// Rounds to two decimals so repeated steps don't drift
+fn step_value(value: f32, delta: f32, min: f32, max: f32) -> f32 {
+    ((value + delta).clamp(min, max) * 100.0).round() / 100.0
+}
+
+impl Settings {
+    /// Reads the config file, falling back to the defaults if it's missing or broken
+    pub fn load() -> Self {
+        match storage::load_config(SETTINGS_FILE) {
+            Ok(settings) => settings.unwrap_or_default(),
+            Err(e) => {
+                error!("Unable to load settings. {}", e);
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        match storage::save_config(SETTINGS_FILE, self) {
+            Ok(path) => info!("Saved settings to {}", path.display()),
+            Err(e) => error!("Unable to save settings. {}", e),
+        }
+    }
+
+    /// The primary window as the settings describe it. Used by DefaultPlugins at startup.
+    pub fn window(&self) -> Window {
+        Window {
+            mode: self.display_mode.window_mode(),
+            resolution: WindowResolution::new(self.resolution.0 as f32, self.resolution.1 as f32),
+            ..default()
+        }
+    }
+
+    /// Moves a setting one step up (positive) or down (negative)
+    pub fn adjust(&mut self, setting: Setting, step: i32) {
+        let dir = step.signum() as f32;
+        match setting {
+            Setting::MasterVolume => self.master_volume = step_value(self.master_volume, dir * 0.1, 0.0, 1.0),
+            Setting::DisplayMode => self.display_mode = self.display_mode.cycle(step.signum()),
+            Setting::Resolution => {
+                let index = RESOLUTIONS.iter().position(|r| *r == self.resolution).unwrap_or(0) as i32;
+                self.resolution = RESOLUTIONS[(index + step.signum()).rem_euclid(RESOLUTIONS.len() as i32) as usize];
+            },
+            Setting::EdgeScrollSpeed => self.edge_scroll_speed = step_value(self.edge_scroll_speed, dir, 1.0, 20.0),
+            Setting::ZoomSpeed => self.zoom_speed = step_value(self.zoom_speed, dir * 0.01, 0.01, 0.1),
+            Setting::AnimationSpeed => self.animation_speed = step_value(self.animation_speed, dir * 0.25, 0.5, 4.0),
+            Setting::CombatAnimations => self.skip_combat_animations = !self.skip_combat_animations,
+            Setting::Forecast => self.forecast = match self.forecast {
+                ForecastDetail::Compact => ForecastDetail::Full,
+                ForecastDetail::Full => ForecastDetail::Compact,
+            },
+            Setting::Rules => self.rules = self.rules.cycle(),
+        }
+    }
+
+    pub fn value_text(&self, setting: Setting) -> String {
+        match setting {
+            Setting::MasterVolume => format!("{:.0}%", self.master_volume * 100.0),
+            Setting::DisplayMode => self.display_mode.get_name().to_string(),
+            Setting::Resolution => format!("{}x{}", self.resolution.0, self.resolution.1),
+            Setting::EdgeScrollSpeed => format!("{:.0}", self.edge_scroll_speed),
+            Setting::ZoomSpeed => format!("{:.2}", self.zoom_speed),
+            Setting::AnimationSpeed => format!("x{:.2}", self.animation_speed),
+            Setting::CombatAnimations => if self.skip_combat_animations { "Skip" } else { "Play" }.to_string(),
+            Setting::Forecast => format!("{:?}", self.forecast),
+            Setting::Rules => format!("{:?}", self.rules),
+        }
+    }
+}
+
+pub fn settings_plugin(app: &mut App) {
+    app
+        // main inserts the loaded settings before the window is made, this only covers running without it
+        .init_resource::<Settings>()
+        .add_systems(Update, apply_settings.run_if(resource_changed::<Settings>));
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut volume: ResMut<GlobalVolume>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+) {
+    volume.volume = Volume::new(settings.master_volume);
+
+    let mode = settings.display_mode.window_mode();
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    let (width, height) = (settings.resolution.0 as f32, settings.resolution.1 as f32);
+    if window.resolution.width() != width || window.resolution.height() != height {
+        window.resolution.set(width, height);
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use crate::game::storage::from_ron;
+    #[allow(unused_imports)]
+    use super::{DisplayMode, ForecastDetail, Setting, Settings, RESOLUTIONS};
+    #[allow(unused_imports)]
+    use crate::game::rules::RulesPreset;
+
+    #[test]
+    fn test_adjust_clamps_and_cycles() {
+        let mut settings = Settings::default();
+        settings.adjust(Setting::MasterVolume, 1);
+        assert_eq!(settings.master_volume, 1.0);
+        for _ in 0..3 {
+            settings.adjust(Setting::MasterVolume, -1);
+        }
+        assert_eq!(settings.value_text(Setting::MasterVolume), "70%");
+
+        settings.adjust(Setting::DisplayMode, -1);
+        assert_eq!(settings.display_mode, DisplayMode::Fullscreen);
+        settings.adjust(Setting::Resolution, -1);
+        assert_eq!(settings.resolution, RESOLUTIONS[RESOLUTIONS.len() - 1]);
+        settings.adjust(Setting::Forecast, 1);
+        assert_eq!(settings.forecast, ForecastDetail::Compact);
+        settings.adjust(Setting::Rules, -1);
+        assert_eq!(settings.rules, RulesPreset::Hardcore);
+        assert_eq!(settings.value_text(Setting::Rules), "Hardcore");
+    }
+
+    #[test]
+    fn test_missing_fields_use_defaults() {
+        // Older files can still have the music and SFX volumes
+        let settings: Settings = from_ron("(zoom_speed: 0.05, display_mode: Borderless, music_volume: 0.5)").unwrap();
+        assert_eq!(settings.zoom_speed, 0.05);
+        assert_eq!(settings.display_mode, DisplayMode::Borderless);
+        assert_eq!(settings.edge_scroll_speed, Settings::default().edge_scroll_speed);
+    }
+}
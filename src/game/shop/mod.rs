@@ -0,0 +1,466 @@
+use bevy::prelude::*;
+use thiserror::Error;
+
+use super::catalogue::WeaponCatalogue;
+use super::chest_selection::rarity_color;
+use super::item::Item;
+use super::roster::RunRoster;
+use super::ui::WeaponTooltip;
+use sim::{Weapon, WeaponCollection};
+use super::GameState;
+use crate::despawn_screen;
+
+const SHOP_WEAPON_COUNT: usize = 4;
+// Gold to bring one unit back to full health
+const HEAL_PRICE: u32 = 80;
+
+#[derive(Component)]
+struct OnShopScreen;
+
+/// Root of the shop panel so it can be rebuilt after each purchase
+#[derive(Component)]
+struct ShopPanel;
+
+#[derive(Component)]
+enum ShopButton {
+    Unit(usize),
+    BuyWeapon(usize),
+    BuyItem(Item),
+    Heal,
+    Sell(usize),
+}
+
+#[derive(Component)]
+enum ConfirmButton {
+    Selection,
+    EndGame,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ShopError {
+    #[error("Not enough gold")]
+    NotEnoughGold,
+    #[error("Pick a unit first")]
+    NoUnit,
+    #[error("That's no longer for sale")]
+    SoldOut,
+    #[error("{0} can't carry any more")]
+    Full(String),
+    #[error("{0} is already at full health")]
+    FullHealth(String),
+    #[error("{0} can't sell their last weapon")]
+    LastWeapon(String),
+}
+
+/// The weapons for sale on this visit and the unit that is shopping
+#[derive(Default, Resource)]
+pub struct ShopStock {
+    pub weapons: Vec<Weapon>,
+    pub unit: usize,
+    /// Result of the last purchase or sale
+    pub message: Option<String>,
+}
+
+impl ShopStock {
+    pub fn roll(catalogue: &WeaponCollection, count: usize) -> Self {
+        Self {
+            weapons: (0..count).map(|_| catalogue.get_random_weapon()).collect(),
+            unit: 0,
+            message: None,
+        }
+    }
+
+    pub fn buy_weapon(&mut self, roster: &mut RunRoster, index: usize) -> Result<String, ShopError> {
+        let price = self.weapons.get(index).ok_or(ShopError::SoldOut)?.price();
+        let unit = roster.units.get(self.unit).ok_or(ShopError::NoUnit)?;
+        if unit.pack.is_full() {
+            return Err(ShopError::Full(unit.stats.name.clone()));
+        }
+        if !roster.spend(price) {
+            return Err(ShopError::NotEnoughGold);
+        }
+
+        let weapon = self.weapons.remove(index);
+        let unit = &mut roster.units[self.unit];
+        let message = format!("{} bought the {} for {}g", unit.stats.name, weapon.get_name(), price);
+        unit.pack.add(weapon);
+        Ok(message)
+    }
+
+    pub fn buy_item(&self, roster: &mut RunRoster, item: Item) -> Result<String, ShopError> {
+        let unit = roster.units.get(self.unit).ok_or(ShopError::NoUnit)?;
+        if unit.inventory.is_full() {
+            return Err(ShopError::Full(unit.stats.name.clone()));
+        }
+        if !roster.spend(item.price()) {
+            return Err(ShopError::NotEnoughGold);
+        }
+
+        let unit = &mut roster.units[self.unit];
+        let message = format!("{} bought a {} for {}g", unit.stats.name, item.get_name(), item.price());
+        unit.inventory.items.push(item);
+        Ok(message)
+    }
+
+    pub fn heal(&self, roster: &mut RunRoster) -> Result<String, ShopError> {
+        let unit = roster.units.get(self.unit).ok_or(ShopError::NoUnit)?;
+        if unit.stats.hp >= unit.max_hp {
+            return Err(ShopError::FullHealth(unit.stats.name.clone()));
+        }
+        if !roster.spend(HEAL_PRICE) {
+            return Err(ShopError::NotEnoughGold);
+        }
+
+        let unit = &mut roster.units[self.unit];
+        unit.stats.hp = unit.max_hp;
+        Ok(format!("{} was healed for {}g", unit.stats.name, HEAL_PRICE))
+    }
+
+    pub fn sell_weapon(&self, roster: &mut RunRoster, index: usize) -> Result<String, ShopError> {
+        let unit = roster.units.get_mut(self.unit).ok_or(ShopError::NoUnit)?;
+        let weapon = unit.pack.take(index).ok_or(ShopError::LastWeapon(unit.stats.name.clone()))?;
+
+        let message = format!("{} sold the {} for {}g", unit.stats.name, weapon.get_name(), weapon.sell_price());
+        roster.gold += weapon.sell_price();
+        Ok(message)
+    }
+}
+
+pub fn shop_plugin(app: &mut App) {
+    app
+        .init_resource::<ShopStock>()
+        .add_systems(OnEnter(GameState::Shop), init_screen)
+        .add_systems(Update, (menu_action, shop_action).run_if(in_state(GameState::Shop)))
+        .add_systems(OnExit(GameState::Shop), (despawn_screen::<OnShopScreen>, clear_stock));
+}
+
+fn init_screen(
+    mut commands: Commands,
+    mut stock: ResMut<ShopStock>,
+    roster: Res<RunRoster>,
+    catalogue: Res<WeaponCatalogue>,
+) {
+    // A resumed run brings back the stock it was offered
+    if stock.weapons.is_empty() {
+        *stock = ShopStock::roll(&catalogue, SHOP_WEAPON_COUNT);
+    }
+
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            align_items: AlignItems::End,
+            justify_content: JustifyContent::End,
+            ..default()
+        },
+        OnShopScreen
+    )).with_children(|parent| {
+        parent.spawn((
+            Button,
+            Node {
+                width: Val::Px(250.0),
+                height: Val::Px(65.0),
+                margin: UiRect::all(Val::Px(20.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::BLACK),
+            ConfirmButton::Selection,
+        )).with_children(|parent| {
+            parent.spawn((
+                Text::new("Map Selection"),
+                TextColor(Color::WHITE),
+            ));
+        });
+
+        parent.spawn((
+            Button,
+            Node {
+                width: Val::Px(250.0),
+                height: Val::Px(65.0),
+                margin: UiRect::all(Val::Px(20.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::BLACK),
+            ConfirmButton::EndGame,
+        )).with_children(|parent| {
+            parent.spawn((
+                Text::new("End Game"),
+                TextColor(Color::WHITE),
+            ));
+        });
+    });
+
+    spawn_shop_panel(&mut commands, &stock, &roster);
+}
+
+fn spawn_shop_panel(commands: &mut Commands, stock: &ShopStock, roster: &RunRoster) {
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        ShopPanel,
+        OnShopScreen
+    )).with_children(|parent| {
+        parent.spawn((
+            Node {
+                width: Val::Percent(70.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::WHITE),
+        )).with_children(|parent| {
+            parent.spawn((
+                Text::new(format!("Gold: {}", roster.gold)),
+                TextColor(Color::BLACK),
+            ));
+            if let Some(message) = &stock.message {
+                parent.spawn((
+                    Text::new(message.clone()),
+                    TextFont {
+                        font_size: 13.0,
+                        ..default()
+                    },
+                    TextColor(Color::BLACK),
+                ));
+            }
+
+            spawn_row(parent, |parent| {
+                for (i, unit) in roster.units.iter().enumerate() {
+                    let color = if stock.unit == i { Color::srgb(0.9, 0.8, 0.2) } else { Color::BLACK };
+                    let label = format!("{} {}/{}", unit.stats.name, unit.stats.hp, unit.max_hp);
+                    create_shop_button(parent, label, color, ShopButton::Unit(i));
+                }
+            });
+
+            // Weapons for sale are compared with what the shopping unit has equipped
+            let equipped = roster.units.get(stock.unit).map(|unit| unit.pack.get_equipped());
+            parent.spawn((
+                Text::new("Buy"),
+                TextColor(Color::BLACK),
+            ));
+            spawn_row(parent, |parent| {
+                for (i, weapon) in stock.weapons.iter().enumerate() {
+                    let label = format!(
+                        "{} - {}g\nATK {} HIT {} CRT {} WT {}",
+                        weapon.get_name(), weapon.price(), weapon.attack, weapon.hit, weapon.crit, weapon.weight
+                    );
+                    create_shop_button(parent, label, rarity_color(&weapon.rarity), ShopButton::BuyWeapon(i)).insert(WeaponTooltip {
+                        weapon: weapon.clone(),
+                        equipped: equipped.cloned(),
+                    });
+                }
+            });
+            spawn_row(parent, |parent| {
+                for item in [Item::Vulnerary, Item::Elixir] {
+                    let label = format!("{} - {}g", item.get_name(), item.price());
+                    create_shop_button(parent, label, Color::BLACK, ShopButton::BuyItem(item));
+                }
+                create_shop_button(parent, format!("Heal - {}g", HEAL_PRICE), Color::BLACK, ShopButton::Heal);
+            });
+
+            let Some(unit) = roster.units.get(stock.unit) else { return };
+            parent.spawn((
+                Text::new(format!("Sell {}'s weapons", unit.stats.name)),
+                TextColor(Color::BLACK),
+            ));
+            spawn_row(parent, |parent| {
+                for (i, weapon) in unit.pack.weapons.iter().enumerate() {
+                    let equipped = if unit.pack.equipped_index() == i { " (E)" } else { "" };
+                    let label = format!("{}{} - {}g", weapon.get_name(), equipped, weapon.sell_price());
+                    let compare = (unit.pack.equipped_index() != i).then(|| unit.pack.get_equipped().clone());
+                    create_shop_button(parent, label, rarity_color(&weapon.rarity), ShopButton::Sell(i)).insert(WeaponTooltip {
+                        weapon: weapon.clone(),
+                        equipped: compare,
+                    });
+                }
+            });
+        });
+    });
+}
+
+fn spawn_row(parent: &mut ChildBuilder, children: impl FnOnce(&mut ChildBuilder)) {
+    parent.spawn(Node {
+        width: Val::Percent(100.0),
+        flex_wrap: FlexWrap::Wrap,
+        justify_content: JustifyContent::SpaceEvenly,
+        ..default()
+    }).with_children(children);
+}
+
+fn create_shop_button<'a>(parent: &'a mut ChildBuilder, label: String, color: Color, action: ShopButton) -> EntityCommands<'a> {
+    let mut button = parent.spawn((
+        Button,
+        Node {
+            min_width: Val::Px(120.0),
+            min_height: Val::Px(30.0),
+            margin: UiRect::all(Val::Px(5.0)),
+            padding: UiRect::all(Val::Px(5.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(color),
+        action,
+    ));
+    button.with_children(|parent| {
+        parent.spawn((
+            Text::new(label),
+            TextFont {
+                font_size: 13.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        ));
+    });
+    button
+}
+
+fn clear_stock(mut stock: ResMut<ShopStock>) {
+    *stock = ShopStock::default();
+}
+
+fn shop_action(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &ShopButton), (Changed<Interaction>, With<Button>)>,
+    panel_q: Query<Entity, With<ShopPanel>>,
+    mut stock: ResMut<ShopStock>,
+    mut roster: ResMut<RunRoster>,
+) {
+    let mut changed = false;
+
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let result = match button {
+            ShopButton::Unit(i) => {
+                stock.unit = *i;
+                stock.message = None;
+                changed = true;
+                continue;
+            },
+            ShopButton::BuyWeapon(i) => stock.buy_weapon(&mut roster, *i),
+            ShopButton::BuyItem(item) => stock.buy_item(&mut roster, item.clone()),
+            ShopButton::Heal => stock.heal(&mut roster),
+            ShopButton::Sell(i) => stock.sell_weapon(&mut roster, *i),
+        };
+
+        stock.message = Some(match result {
+            Ok(message) => message,
+            Err(e) => e.to_string(),
+        });
+        changed = true;
+    }
+
+    if changed {
+        for entity in panel_q.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        spawn_shop_panel(&mut commands, &stock, &roster);
+    }
+}
+
+fn menu_action(
+    interaction_query: Query<
+        (&Interaction, &ConfirmButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut game_state: ResMut<NextState<GameState>>,
+){
+    for (interaction, button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            match button {
+                ConfirmButton::Selection => game_state.set(GameState::MapSelection),
+                ConfirmButton::EndGame => game_state.set(GameState::Rewards),
+            }
+        }
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use crate::game::item::Item;
+    #[allow(unused_imports)]
+    use crate::game::roster::{RosterUnit, RunRoster};
+    #[allow(unused_imports)]
+    use crate::game::units::UnitStats;
+    #[allow(unused_imports)]
+    use sim::{Rarity, Weapon, WeaponCollection};
+    #[allow(unused_imports)]
+    use super::{ShopError, ShopStock, HEAL_PRICE};
+
+    #[allow(dead_code)]
+    fn get_roster(gold: u32) -> RunRoster {
+        RunRoster {
+            units: vec![RosterUnit::new(UnitStats(sim::UnitStats { name: "Scooby".to_string(), hp: 10, ..Default::default() }), &WeaponCollection::default())],
+            casual: false,
+            gold,
+        }
+    }
+
+    #[allow(dead_code)]
+    fn get_stock() -> ShopStock {
+        ShopStock {
+            weapons: vec![
+                Weapon { rarity: Rarity::Common, ..Default::default() },
+                Weapon { rarity: Rarity::Legendary, ..Default::default() },
+            ],
+            unit: 0,
+            message: None,
+        }
+    }
+
+    #[test]
+    fn test_buy_weapon() {
+        let mut roster = get_roster(300);
+        let mut stock = get_stock();
+        let before = roster.units[0].pack.weapons.len();
+
+        assert_eq!(stock.buy_weapon(&mut roster, 1), Err(ShopError::NotEnoughGold));
+        assert!(stock.buy_weapon(&mut roster, 0).is_ok());
+        assert_eq!(roster.gold, 300 - Rarity::Common.price());
+        assert_eq!(roster.units[0].pack.weapons.len(), before + 1);
+        // Bought weapons leave the stock
+        assert_eq!(stock.weapons.len(), 1);
+        assert_eq!(stock.buy_weapon(&mut roster, 1), Err(ShopError::SoldOut));
+    }
+
+    #[test]
+    fn test_buy_item_and_heal() {
+        let mut roster = get_roster(Item::Elixir.price() + HEAL_PRICE);
+        let stock = get_stock();
+
+        assert_eq!(stock.heal(&mut roster), Err(ShopError::FullHealth("Scooby".to_string())));
+        roster.units[0].stats.hp = 1;
+        assert!(stock.buy_item(&mut roster, Item::Elixir).is_ok());
+        assert!(stock.heal(&mut roster).is_ok());
+        assert_eq!(roster.units[0].stats.hp, 10);
+        assert_eq!(roster.units[0].inventory.items.last(), Some(&Item::Elixir));
+        assert_eq!(roster.gold, 0);
+    }
+
+    #[test]
+    fn test_sell_keeps_last_weapon() {
+        let mut roster = get_roster(0);
+        let stock = get_stock();
+        let mut expected = 0;
+
+        while roster.units[0].pack.weapons.len() > 1 {
+            expected += roster.units[0].pack.weapons[0].sell_price();
+            assert!(stock.sell_weapon(&mut roster, 0).is_ok());
+        }
+        assert_eq!(stock.sell_weapon(&mut roster, 0), Err(ShopError::LastWeapon("Scooby".to_string())));
+        assert_eq!(roster.gold, expected);
+    }
+}
@@ -0,0 +1,105 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+
+// Folder inside the platform's data and config directories that holds everything the game saves
+const DATA_DIR: &str = "rouge-like";
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("No data directory on this platform")]
+    NoDataDir,
+    #[error("No config directory on this platform")]
+    NoConfigDir,
+    #[error("Unable to read or write the file. {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Unable to parse the file. {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("Unable to serialize. {0}")]
+    Serialize(#[from] ron::Error),
+}
+
+pub fn data_file(name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(DATA_DIR).join(name))
+}
+
+pub fn config_file(name: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(DATA_DIR).join(name))
+}
+
+pub fn from_ron<T: DeserializeOwned>(text: &str) -> Result<T, StorageError> {
+    Ok(ron::from_str(text)?)
+}
+
+pub fn to_ron<T: Serialize>(value: &T) -> Result<String, StorageError> {
+    Ok(ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?)
+}
+
+/// Reads a file from the data directory. A missing file is Ok(None).
+pub fn read(name: &str) -> Result<Option<String>, StorageError> {
+    read_file(&data_file(name).ok_or(StorageError::NoDataDir)?)
+}
+
+pub fn load<T: DeserializeOwned>(name: &str) -> Result<Option<T>, StorageError> {
+    read(name)?.map(|text| from_ron(&text)).transpose()
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) -> Result<PathBuf, StorageError> {
+    let path = data_file(name).ok_or(StorageError::NoDataDir)?;
+    write_file(&path, value)?;
+    Ok(path)
+}
+
+/// Same as load, but from the config directory
+pub fn load_config<T: DeserializeOwned>(name: &str) -> Result<Option<T>, StorageError> {
+    read_file(&config_file(name).ok_or(StorageError::NoConfigDir)?)?
+        .map(|text| from_ron(&text))
+        .transpose()
+}
+
+pub fn save_config<T: Serialize>(name: &str, value: &T) -> Result<PathBuf, StorageError> {
+    let path = config_file(name).ok_or(StorageError::NoConfigDir)?;
+    write_file(&path, value)?;
+    Ok(path)
+}
+
+fn read_file(path: &Path) -> Result<Option<String>, StorageError> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_file<T: Serialize>(path: &Path, value: &T) -> Result<(), StorageError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, to_ron(value)?)?;
+    Ok(())
+}
+
+pub fn exists(name: &str) -> bool {
+    data_file(name).is_some_and(|path| path.exists())
+}
+
+/// Renames a file in the data directory to <name>.bak, so a broken save isn't lost
+/// when it gets written again
+pub fn backup(name: &str) -> Result<PathBuf, StorageError> {
+    let path = data_file(name).ok_or(StorageError::NoDataDir)?;
+    let backup = path.with_file_name(format!("{}.bak", name));
+    fs::rename(&path, &backup)?;
+    Ok(backup)
+}
+
+/// Deletes a file from the data directory. Removing a missing file is fine.
+pub fn remove(name: &str) -> Result<(), StorageError> {
+    let path = data_file(name).ok_or(StorageError::NoDataDir)?;
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::GridCoords;
+use serde::{Deserialize, Serialize};
+use sim::SupportBonus;
+
+use crate::util::manhattan_dist;
+
+/// Allies within this many tiles grant their support bonus
+pub const SUPPORT_RANGE: u32 = 3;
+/// Allies within this many tiles of a fight build support points
+const SUPPORT_GAIN_RANGE: u32 = 1;
+
+/// A pair of units that builds support ranks by fighting next to each other.
+/// Defined in the unit data alongside the units themselves.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SupportPair {
+    pub units: (String, String),
+    /// Support points needed for each rank up
+    pub points_per_rank: u32,
+    /// Bonus granted at each rank, starting at the first rank
+    pub ranks: Vec<SupportBonus>,
+    /// Conversation shown when reaching each rank
+    #[serde(default)]
+    pub conversations: Vec<String>,
+}
+
+impl SupportPair {
+    fn partner_of(&self, name: &str) -> Option<&str> {
+        if self.units.0 == name {
+            Some(&self.units.1)
+        } else if self.units.1 == name {
+            Some(&self.units.0)
+        } else {
+            None
+        }
+    }
+
+    fn rank_for(&self, points: u32) -> usize {
+        if self.points_per_rank == 0 {
+            return self.ranks.len();
+        }
+        ((points / self.points_per_rank) as usize).min(self.ranks.len())
+    }
+}
+
+/// Sent when a support pair reaches a new rank. Hook for playing the rank-up conversation.
+#[derive(Event, Debug)]
+pub struct SupportRankUp {
+    pub units: (String, String),
+    pub rank: usize,
+    pub conversation: Option<String>,
+}
+
+/// Support pairs for the run and the points each pair has built up
+#[derive(Default, Resource, Debug)]
+pub struct Supports {
+    pairs: Vec<SupportPair>,
+    points: HashMap<(String, String), u32>,
+}
+
+impl Supports {
+    pub fn new(pairs: Vec<SupportPair>) -> Self {
+        Self {
+            pairs,
+            points: HashMap::new(),
+        }
+    }
+
+    pub fn points(&self) -> &HashMap<(String, String), u32> {
+        &self.points
+    }
+
+    /// Puts back the points from a saved run
+    pub fn set_points(&mut self, points: HashMap<(String, String), u32>) {
+        self.points = points;
+    }
+
+    pub fn rank(&self, pair: &SupportPair) -> usize {
+        pair.rank_for(self.points.get(&pair.units).copied().unwrap_or(0))
+    }
+
+    /// Total bonus for the named unit at coords from supported allies within SUPPORT_RANGE
+    pub fn bonus<'a>(
+        &self,
+        name: &str,
+        coords: GridCoords,
+        allies: impl Iterator<Item = (&'a str, GridCoords)> + Clone,
+    ) -> SupportBonus {
+        let mut bonus = SupportBonus::default();
+
+        for pair in self.pairs.iter() {
+            let Some(partner) = pair.partner_of(name) else { continue };
+            let rank = self.rank(pair);
+            if rank == 0 { continue }
+
+            let in_range = allies.clone()
+                .any(|(ally, ally_coords)| ally == partner && manhattan_dist(coords, ally_coords) <= SUPPORT_RANGE);
+            if in_range {
+                bonus.add(&pair.ranks[rank - 1]);
+            }
+        }
+
+        bonus
+    }
+
+    /// Adds a point to every pair with the named unit fighting next to its partner.
+    /// Returns the pairs that ranked up.
+    pub fn gain<'a>(
+        &mut self,
+        name: &str,
+        coords: GridCoords,
+        allies: impl Iterator<Item = (&'a str, GridCoords)> + Clone,
+    ) -> Vec<SupportRankUp> {
+        let mut rank_ups = Vec::new();
+
+        for pair in self.pairs.iter() {
+            let Some(partner) = pair.partner_of(name) else { continue };
+            let next_to_partner = allies.clone()
+                .any(|(ally, ally_coords)| ally == partner && manhattan_dist(coords, ally_coords) <= SUPPORT_GAIN_RANGE);
+            if !next_to_partner { continue }
+
+            let points = self.points.entry(pair.units.clone()).or_insert(0);
+            let old_rank = pair.rank_for(*points);
+            *points += 1;
+            let new_rank = pair.rank_for(*points);
+
+            if new_rank > old_rank {
+                rank_ups.push(SupportRankUp {
+                    units: pair.units.clone(),
+                    rank: new_rank,
+                    conversation: pair.conversations.get(new_rank - 1).cloned(),
+                });
+            }
+        }
+
+        rank_ups
+    }
+}
+
+pub fn support_plugin(app: &mut App) {
+    app
+        .init_resource::<Supports>()
+        .add_event::<SupportRankUp>()
+        .add_systems(Update, show_support_conversation);
+}
+
+// TODO: Show the conversation on screen instead of logging it
+fn show_support_conversation(mut rank_ups: EventReader<SupportRankUp>) {
+    for ev in rank_ups.read() {
+        info!("{} and {} reached support rank {}", ev.units.0, ev.units.1, ev.rank);
+        if let Some(conversation) = &ev.conversation {
+            info!("{}", conversation);
+        }
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use bevy_ecs_ldtk::GridCoords;
+    #[allow(unused_imports)]
+    use super::{SupportBonus, SupportPair, Supports};
+
+    #[allow(dead_code)]
+    fn get_supports() -> Supports {
+        Supports::new(vec![SupportPair {
+            units: ("Scooby".to_string(), "Courage".to_string()),
+            points_per_rank: 2,
+            ranks: vec![
+                SupportBonus { hit: 5, avoid: 5, crit: 0 },
+                SupportBonus { hit: 10, avoid: 10, crit: 5 },
+            ],
+            conversations: vec!["C".to_string(), "B".to_string()],
+        }])
+    }
+
+    #[test]
+    fn test_no_bonus_without_rank() {
+        let supports = get_supports();
+        let allies = [("Courage", GridCoords::new(1, 0))];
+
+        let bonus = supports.bonus("Scooby", GridCoords::new(0, 0), allies.iter().copied());
+        assert!(bonus.is_empty());
+    }
+
+    #[test]
+    fn test_gain_ranks_up() {
+        let mut supports = get_supports();
+        let allies = [("Courage", GridCoords::new(1, 0))];
+
+        assert!(supports.gain("Scooby", GridCoords::new(0, 0), allies.iter().copied()).is_empty());
+        let rank_ups = supports.gain("Scooby", GridCoords::new(0, 0), allies.iter().copied());
+        assert_eq!(rank_ups.len(), 1);
+        assert_eq!(rank_ups[0].rank, 1);
+        assert_eq!(rank_ups[0].conversation, Some("C".to_string()));
+    }
+
+    #[test]
+    fn test_no_gain_when_apart() {
+        let mut supports = get_supports();
+        let allies = [("Courage", GridCoords::new(2, 0))];
+
+        supports.gain("Scooby", GridCoords::new(0, 0), allies.iter().copied());
+        supports.gain("Scooby", GridCoords::new(0, 0), allies.iter().copied());
+        assert_eq!(supports.rank(&supports.pairs[0]), 0);
+    }
+
+    #[test]
+    fn test_bonus_within_range() {
+        let mut supports = get_supports();
+        let next_to = [("Courage", GridCoords::new(0, 1))];
+        for _ in 0..4 {
+            supports.gain("Courage", GridCoords::new(0, 0), [("Scooby", GridCoords::new(0, 1))].iter().copied());
+        }
+
+        let bonus = supports.bonus("Scooby", GridCoords::new(0, 0), next_to.iter().copied());
+        assert_eq!(bonus, SupportBonus { hit: 10, avoid: 10, crit: 5 });
+
+        let far = [("Courage", GridCoords::new(4, 0))];
+        let bonus = supports.bonus("Scooby", GridCoords::new(0, 0), far.iter().copied());
+        assert!(bonus.is_empty());
+    }
+}
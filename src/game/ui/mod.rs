@@ -1,5 +1,235 @@
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 
-// TODO: Cretae a weapons detail view
+use super::chest_selection::rarity_color;
+use sim::{Weapon, WeaponType};
+
+// Gap between the cursor and a tooltip, in pixels
+const TOOLTIP_OFFSET: f32 = 16.0;
+const CARD_WIDTH: f32 = 180.0;
+
+const BETTER_COLOR: Color = Color::srgb(0.1, 0.55, 0.1);
+const WORSE_COLOR: Color = Color::srgb(0.75, 0.1, 0.1);
+
+/// A weapon card. Despawned when the button that shows it is no longer hovered.
 #[derive(Debug, Component)]
-pub struct Weapons;
+pub struct Weapons {
+    source: Entity,
+}
+
+/// Shows a weapon card while the button is hovered, compared against the equipped weapon if there is one
+#[derive(Component, Clone)]
+pub struct WeaponTooltip {
+    pub weapon: Weapon,
+    pub equipped: Option<Weapon>,
+}
+
+/// One line of a weapon card
+#[derive(Debug, PartialEq)]
+pub struct CardLine {
+    pub text: String,
+    pub color: Color,
+}
+
+impl CardLine {
+    fn new(text: String) -> Self {
+        Self { text, color: Color::BLACK }
+    }
+}
+
+pub fn weapon_card_plugin(app: &mut App) {
+    app.add_systems(Update, (
+        show_weapon_card,
+        remove_orphaned_cards,
+        place_weapon_card,
+    ).chain());
+}
+
+/// Puts a tooltip beside the anchor, flipping to the other side when it would
+/// run off the window. Everything is in logical pixels.
+pub fn tooltip_position(anchor: Vec2, size: Vec2, window: Vec2) -> Vec2 {
+    let mut position = anchor + Vec2::splat(TOOLTIP_OFFSET);
+    if position.x + size.x > window.x {
+        position.x = anchor.x - TOOLTIP_OFFSET - size.x;
+    }
+    if position.y + size.y > window.y {
+        position.y = anchor.y - TOOLTIP_OFFSET - size.y;
+    }
+    position.clamp(Vec2::ZERO, (window - size).max(Vec2::ZERO))
+}
+
+/// A stat with how it changes from the equipped weapon. Weight is better lower.
+fn stat_line(label: &str, value: u32, equipped: Option<u32>, lower_is_better: bool) -> CardLine {
+    let Some(old) = equipped.filter(|old| *old != value) else {
+        return CardLine::new(format!("{} {}", label, value));
+    };
+
+    let diff = value as i64 - old as i64;
+    let better = (diff > 0) != lower_is_better;
+    CardLine {
+        text: format!("{} {} ({:+})", label, value, diff),
+        color: if better { BETTER_COLOR } else { WORSE_COLOR },
+    }
+}
+
+fn type_list(types: &[WeaponType]) -> String {
+    types.iter().map(|t| t.get_name()).collect::<Vec<_>>().join(", ")
+}
+
+/// Everything a weapon card shows, top to bottom. Stats show the change from equipped.
+pub fn weapon_card_lines(weapon: &Weapon, equipped: Option<&Weapon>) -> Vec<CardLine> {
+    let mut lines = vec![
+        CardLine { text: weapon.get_name(), color: rarity_color(&weapon.rarity) },
+        CardLine::new(format!("{} {}", weapon.rarity.get_name(), weapon.weapon_type.get_name())),
+        stat_line("ATK", weapon.attack, equipped.map(|w| w.attack), false),
+        stat_line("HIT", weapon.hit, equipped.map(|w| w.hit), false),
+        stat_line("CRT", weapon.crit, equipped.map(|w| w.crit), false),
+        stat_line("WT", weapon.weight, equipped.map(|w| w.weight), true),
+        CardLine::new(format!("Range {}", weapon.range.get_name())),
+    ];
+
+    let strong = weapon.weapon_type.strong_against();
+    let weak = weapon.weapon_type.weak_against();
+    if strong.is_empty() && weak.is_empty() {
+        lines.push(CardLine::new("Outside the weapon triangle".to_string()));
+    } else {
+        lines.push(CardLine::new(format!("Strong vs {}", type_list(&strong))));
+        lines.push(CardLine::new(format!("Weak vs {}", type_list(&weak))));
+    }
+
+    lines.push(CardLine::new(match &weapon.weapon_effect {
+        Some(effect) => format!("Effect: {}", effect.get_name()),
+        None => "No effect".to_string(),
+    }));
+
+    lines
+}
+
+fn spawn_card_column(parent: &mut ChildBuilder, heading: &str, lines: Vec<CardLine>) {
+    parent.spawn(Node {
+        flex_direction: FlexDirection::Column,
+        width: Val::Px(CARD_WIDTH),
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn((
+            Text::new(heading),
+            TextFont {
+                font_size: 15.0,
+                ..default()
+            },
+            TextColor(Color::BLACK),
+        ));
+        for line in lines {
+            parent.spawn((
+                Text::new(line.text),
+                TextFont {
+                    font_size: 13.0,
+                    ..default()
+                },
+                TextColor(line.color),
+            ));
+        }
+    });
+}
+
+fn show_weapon_card(
+    mut commands: Commands,
+    interaction_q: Query<(Entity, &Interaction, &WeaponTooltip), Changed<Interaction>>,
+    card_q: Query<(Entity, &Weapons)>,
+) {
+    for (source, interaction, tooltip) in interaction_q.iter() {
+        for (card, weapons) in card_q.iter() {
+            if *interaction != Interaction::None || weapons.source == source {
+                commands.entity(card).despawn_recursive();
+            }
+        }
+        if *interaction == Interaction::None { continue }
+
+        commands.spawn((
+            Weapons { source },
+            Node {
+                position_type: PositionType::Absolute,
+                column_gap: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(Color::WHITE),
+            GlobalZIndex(10),
+        )).with_children(|parent| {
+            let equipped = tooltip.equipped.as_ref();
+            spawn_card_column(parent, "Weapon", weapon_card_lines(&tooltip.weapon, equipped));
+            if let Some(equipped) = equipped {
+                spawn_card_column(parent, "Equipped", weapon_card_lines(equipped, None));
+            }
+        });
+    }
+}
+
+/// Screens rebuild their buttons, which would leave the card up without anything to hover
+fn remove_orphaned_cards(
+    mut commands: Commands,
+    card_q: Query<(Entity, &Weapons)>,
+    source_q: Query<(), With<WeaponTooltip>>,
+) {
+    for (card, weapons) in card_q.iter() {
+        if source_q.get(weapons.source).is_err() {
+            commands.entity(card).despawn_recursive();
+        }
+    }
+}
+
+fn place_weapon_card(
+    mut card_q: Query<(&mut Node, &ComputedNode), With<Weapons>>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+) {
+    let Ok(window) = window_q.get_single() else { return };
+    let Some(cursor) = window.cursor_position() else { return };
+
+    for (mut node, computed) in card_q.iter_mut() {
+        let size = computed.size() * computed.inverse_scale_factor();
+        let position = tooltip_position(cursor, size, Vec2::new(window.width(), window.height()));
+        if node.left != Val::Px(position.x) || node.top != Val::Px(position.y) {
+            node.left = Val::Px(position.x);
+            node.top = Val::Px(position.y);
+        }
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use bevy::prelude::*;
+    #[allow(unused_imports)]
+    use sim::{Weapon, WeaponType};
+    #[allow(unused_imports)]
+    use super::{tooltip_position, weapon_card_lines, BETTER_COLOR, TOOLTIP_OFFSET, WORSE_COLOR};
+
+    #[test]
+    fn test_tooltip_stays_on_screen() {
+        let window = Vec2::new(800., 600.);
+        let size = Vec2::new(200., 300.);
+        let near = Vec2::new(100., 100.);
+        assert_eq!(tooltip_position(near, size, window), near + TOOLTIP_OFFSET);
+        // Flips left and up in the bottom right corner
+        let corner = Vec2::new(750., 550.);
+        assert_eq!(tooltip_position(corner, size, window), corner - TOOLTIP_OFFSET - size);
+        // Too tall to fit either way
+        assert_eq!(tooltip_position(near, Vec2::new(200., 700.), window).y, 0.);
+    }
+
+    #[test]
+    fn test_weapon_card_comparison() {
+        let equipped = Weapon { attack: 5, hit: 90, weight: 5, ..Default::default() };
+        let hovered = Weapon { attack: 7, hit: 90, weight: 8, ..Default::default() };
+        let lines = weapon_card_lines(&hovered, Some(&equipped));
+
+        let find = |text: &str| lines.iter().find(|line| line.text == text).unwrap().color;
+        assert_eq!(find("ATK 7 (+2)"), BETTER_COLOR);
+        assert_eq!(find("WT 8 (+3)"), WORSE_COLOR);
+        assert_eq!(find("HIT 90"), Color::BLACK);
+        assert!(lines.iter().any(|line| line.text == "Strong vs axe"));
+        assert!(lines.iter().any(|line| line.text == "Weak vs lance"));
+
+        let bow = Weapon { weapon_type: WeaponType::Bow, ..Default::default() };
+        assert!(weapon_card_lines(&bow, None).iter().any(|line| line.text == "Outside the weapon triangle"));
+    }
+}
@@ -1,11 +1,17 @@
 use bevy::prelude::*;
 
 use super::assets::UnitCollection;
+use super::support::Supports;
+use super::roster::{RosterUnit, RunRoster};
+use super::map_selection::RunMap;
+use super::run_stats::RunStats;
+use super::meta::MetaProgress;
+use super::catalogue::WeaponCatalogue;
 // TODO: Be consistent. Choose either crate or super
-use super::{AvailableUnits, GameState, SelectedUnits};
+use super::{AvailableUnits, GameState};
 use crate::{despawn_screen, AppState};
 
-const MAX_NUMBER_OF_UNITS: usize = 3;
+pub const MAX_NUMBER_OF_UNITS: usize = 3;
 
 #[derive(Component)]
 struct OnUnitSelectionScreen;
@@ -24,9 +30,13 @@ enum MenuAction {
 #[derive(Component)]
 enum Selection { 
     Confirm,
+    ToggleCasual,
     Unit(usize),
 }
 
+#[derive(Component)]
+struct CasualLabel;
+
 pub fn unit_selection_plugin(app: &mut App) {
     app
         .add_systems(OnEnter(GameState::UnitSelection), init_screen)
@@ -36,11 +46,18 @@ pub fn unit_selection_plugin(app: &mut App) {
 
 fn init_screen(
     mut commands: Commands, 
-    mut selected_units: ResMut<SelectedUnits>,
+    mut roster: ResMut<RunRoster>,
+    mut supports: ResMut<Supports>,
+    mut run_stats: ResMut<RunStats>,
+    meta: Res<MetaProgress>,
     unit_handle: Res<AvailableUnits>,
     unit_collection: Res<Assets<UnitCollection>>,
 ) {
-    selected_units.0.clear();
+    roster.clear();
+    *run_stats = RunStats::default();
+    if let Some(unit_asset) = unit_collection.get(unit_handle.s.id()) {
+        *supports = Supports::new(unit_asset.supports.clone());
+    }
 
     commands.spawn((
         UnitsSelectedForMap{selected: Vec::new()},
@@ -76,7 +93,7 @@ fn init_screen(
     )).with_children(|parent| {
 
         if let Some(unit_asset) = unit_collection.get(unit_handle.s.id()) {
-            create_unit_selection_dialog(parent, unit_asset);
+            create_unit_selection_dialog(parent, unit_asset, &meta);
         } else {
             error!("Unable to create Unit Selection buttons. Asset not properly loaded.")
         }
@@ -99,6 +116,27 @@ fn init_screen(
             parent
         );
 
+        parent.spawn((
+            Button,
+            Node {
+                width: Val::Px(125.0),
+                height: Val::Px(65.0),
+                margin: UiRect::all(Val::Px(20.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                align_self: AlignSelf::Center,
+                ..default()
+            },
+            BackgroundColor(Color::BLACK),
+            Selection::ToggleCasual,
+        )).with_children(|parent| {
+            parent.spawn((
+                Text::new(casual_label(false)),
+                TextColor(Color::WHITE),
+                CasualLabel,
+            ));
+        });
+
         parent.spawn((
             Button,
             Node {
@@ -123,7 +161,8 @@ fn init_screen(
 
 fn create_unit_selection_dialog(
     parent: &mut ChildBuilder, 
-    units_available: &UnitCollection
+    units_available: &UnitCollection,
+    meta: &MetaProgress,
 ) {
     parent.spawn((
         Node {
@@ -139,6 +178,11 @@ fn create_unit_selection_dialog(
     )).with_children(|p| {
         // TODO: Create buttons for units to select
         for (i, unit) in units_available.units.iter().enumerate() {
+            if let Some(hint) = meta.unit_lock_hint(&unit.name) {
+                create_locked_unit(p, &unit.name, hint);
+                continue;
+            }
+
             p.spawn((
                 Button,
                 Node {
@@ -162,6 +206,44 @@ fn create_unit_selection_dialog(
     });
 }
 
+// Locked units aren't buttons, so they can't be selected
+fn create_locked_unit(parent: &mut ChildBuilder, name: &str, hint: String) {
+    parent.spawn((
+        Node {
+            width: Val::Percent(15.0),
+            height: Val::Percent(30.0),
+            margin: UiRect::vertical(Val::Px(15.0)),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            align_self: AlignSelf::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.3, 0.3, 0.3)),
+    )).with_children(|parent| {
+        parent.spawn((
+            Text::new(name),
+            TextColor(Color::srgb(0.6, 0.6, 0.6)),
+        ));
+        parent.spawn((
+            Text::new(hint),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.6, 0.6, 0.6)),
+        ));
+    });
+}
+
+fn casual_label(casual: bool) -> String {
+    if casual {
+        "Casual: On".to_string()
+    } else {
+        "Casual: Off".to_string()
+    }
+}
+
 fn create_temp_button(action: MenuAction, label: &'static str, p: &mut ChildBuilder) {
     p.spawn((
         Button,
@@ -184,6 +266,7 @@ fn create_temp_button(action: MenuAction, label: &'static str, p: &mut ChildBuil
     });
 }
 
+#[allow(clippy::too_many_arguments)]
 fn selection_action(
     interaction_query: Query<
         (&Interaction, &Selection),
@@ -191,20 +274,38 @@ fn selection_action(
     >,
     mut game_state: ResMut<NextState<GameState>>,
     mut units_query: Query<&mut UnitsSelectedForMap>,
-    mut selected_units: ResMut<SelectedUnits>,
+    mut casual_label_q: Query<&mut Text, With<CasualLabel>>,
+    mut roster: ResMut<RunRoster>,
+    mut run_map: ResMut<RunMap>,
+    meta: Res<MetaProgress>,
     unit_handle: Res<AvailableUnits>,
-    unit_collection: Res<Assets<UnitCollection>>
+    unit_collection: Res<Assets<UnitCollection>>,
+    catalogue: Res<WeaponCatalogue>,
 ) {
     for (interaction, action) in &interaction_query {
         if *interaction == Interaction::Pressed {
             match action {
                 Selection::Confirm => {
-                    game_state.set(GameState::InBattle);
+                    *run_map = RunMap::generate(rand::random());
+                    game_state.set(GameState::MapSelection);
                     let units = units_query.single();
 
                     for i in units.selected.iter() {
                         let units_available = unit_collection.get(unit_handle.s.id()).unwrap();
-                        selected_units.0.push(units_available.units[*i].clone());
+                        let mut unit = RosterUnit::new(units_available.units[*i].clone(), &catalogue);
+                        for rarity in meta.starting_weapons() {
+                            if !unit.pack.is_full() {
+                                unit.pack.add(catalogue.get_random_weapon_by_rarity(rarity));
+                            }
+                        }
+                        roster.units.push(unit);
+                    }
+                    roster.gold += meta.starting_gold();
+                }
+                Selection::ToggleCasual => {
+                    roster.casual = !roster.casual;
+                    for mut text in casual_label_q.iter_mut() {
+                        **text = casual_label(roster.casual);
                     }
                 }
                 Selection::Unit(i) => {
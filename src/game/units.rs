@@ -2,10 +2,15 @@ use bevy::prelude::*;
 use bevy::utils::hashbrown::HashSet;
 use bevy_ecs_ldtk::prelude::*;
 use serde::{Deserialize, Serialize};
+use sim::{Weapon, WeaponCollection};
 
 use super::Player;
 use super::battle_scene::BattleState;
-use super::weapon::Weapon;
+use super::item::Item;
+
+pub const MAX_WEAPONS: usize = 5;
+pub const MAX_ITEMS: usize = 5;
+pub const XP_PER_LEVEL: u32 = 100;
 
 // TODO: This should have a different name
 #[derive(Default, Component)]
@@ -28,10 +33,6 @@ impl Teams {
         self.moved_player_units.contains(entity)
     }
 
-    pub fn count(&self) -> usize {
-        self.moved_player_units.len()
-    }
-
     pub fn clear(&mut self) {
         self.moved_player_units.clear();
     }
@@ -43,104 +44,68 @@ impl Teams {
 // This should be called something else lol
 pub fn check_for_team_refresh(
     team_q: Query<&Teams>,
-    player_q: Query<&Player>,
+    // Carried units can't act, so they don't hold up the turn
+    player_q: Query<Entity, (With<Player>, Without<Carried>)>,
     mut active_game_state: ResMut<NextState<BattleState>>,
 ) {
     let team = team_q.single();
-    let num_of_players = player_q.iter().len();
 
-    if num_of_players == team.count() {
+    if player_q.iter().all(|e| team.contains(&e)) {
         // should I send an event or just queue the stuff here?
         active_game_state.set(BattleState::ToEnemyTurn);
     }
 }
 
-#[derive(Component, Debug, Clone, TypePath, Deserialize, Serialize)]
-pub struct UnitStats {
-    pub name: String,
-    pub hp: u32,
-    pub def: u32,
-    pub atk: u32,
-    pub spd: u32,
-    pub skill: u32,
-    pub mov: u32,
-}
-
-impl Default for UnitStats {
-    fn default() -> Self {
-        UnitStats {
-            name: "".to_string(),
-            hp: 10,
-            def: 0,
-            atk: 3,
-            spd: 2,
-            skill: 3,
-            mov: 8,
-        }
-    }
-}
+/// A unit's stats as a component. UnitStats::hp is the current HP.
+#[derive(Component, Debug, Clone, Default, Deref, DerefMut, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct UnitStats(pub sim::UnitStats);
 
 impl UnitStats {
-    pub fn enemy() -> Self {
-        UnitStats {
-            name: "Enemy".to_string(),
-            hp: 3,
-            def: 0,
-            atk: 1,
-            spd: 2,
-            skill: 3,
-            mov: 1,
-        }
-    }
-
-    pub fn player() -> Self {
-        UnitStats {
-            name: "Player".to_string(),
-            hp: 10,
-            def: 1,
-            atk: 3,
-            spd: 3,
-            skill: 3,
-            mov: 8,
-        }
+    pub fn level(&self) -> u32 {
+        1 + self.xp / XP_PER_LEVEL
     }
+}
 
-    pub fn accuracy(&self) -> u32 {
-        2 * self.skill
+impl From<sim::UnitStats> for UnitStats {
+    fn from(stats: sim::UnitStats) -> Self {
+        UnitStats(stats)
     }
+}
 
-    pub fn attack_speed(&self) -> u32 {
-        2 * self.spd
-    }
+/// Placed on a unit that is carrying the given ally
+#[derive(Component)]
+pub struct Carrying(pub Entity);
 
-    pub fn crit(&self) -> u32 {
-        self.accuracy()
-    }
+/// Placed on a unit that is being carried. Carried units are hidden and not in UnitsOnMap
+#[derive(Default, Component)]
+pub struct Carried;
 
-    pub fn dodge(&self) -> u32 {
-        self.attack_speed()
-    }
-}
+/// HP the unit is at when fully healed. UnitStats::hp is the current HP.
+#[derive(Default, Component, Clone, Copy, Debug)]
+pub struct MaxHp(pub u32);
 
 #[derive(Default, Bundle, LdtkEntity)]
 pub struct UnitBundle {
     pub stats: UnitStats,
+    pub max_hp: MaxHp,
     pub pack: WeaponPack,
+    pub inventory: Inventory,
     #[grid_coords]
     pub grid_coords: GridCoords,
 }
 
-#[derive(Default, Component)]
+#[derive(Default, Component, Clone, Deserialize, Serialize)]
 pub struct WeaponPack {
     pub weapons: Vec<Weapon>,
     equipped: usize,
 }
 
 impl WeaponPack {
-    pub fn new() -> Self {
+    pub fn new(catalogue: &WeaponCollection) -> Self {
         let mut weapons = Vec::new();
         for _ in 0..3 {
-            weapons.push(Weapon::get_random_weapon());
+            weapons.push(catalogue.get_random_weapon());
         }
 
         Self {
@@ -152,4 +117,108 @@ impl WeaponPack {
     pub fn get_equipped(&self) -> &Weapon {
         &self.weapons[self.equipped]
     }
+
+    pub fn equipped_index(&self) -> usize {
+        self.equipped
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.weapons.len() >= MAX_WEAPONS
+    }
+
+    pub fn add(&mut self, weapon: Weapon) {
+        self.weapons.push(weapon);
+    }
+
+    /// Removes the weapon at index, keeping the equipped index pointing at the same weapon.
+    /// A unit always keeps at least one weapon, so taking the last one returns None.
+    pub fn take(&mut self, index: usize) -> Option<Weapon> {
+        if index >= self.weapons.len() || self.weapons.len() <= 1 {
+            return None;
+        }
+
+        let weapon = self.weapons.remove(index);
+        if index < self.equipped {
+            self.equipped -= 1;
+        } else if index == self.equipped {
+            self.equipped = 0;
+        }
+
+        Some(weapon)
+    }
+}
+
+#[derive(Default, Component, Clone, Deserialize, Serialize)]
+pub struct Inventory {
+    pub items: Vec<Item>,
+}
+
+impl Inventory {
+    pub fn is_full(&self) -> bool {
+        self.items.len() >= MAX_ITEMS
+    }
+
+    pub fn take(&mut self, index: usize) -> Option<Item> {
+        if index < self.items.len() {
+            Some(self.items.remove(index))
+        } else {
+            None
+        }
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use sim::{Weapon, WeaponType};
+    #[allow(unused_imports)]
+    use super::WeaponPack;
+
+    #[allow(dead_code)]
+    fn get_pack(equipped: usize) -> WeaponPack {
+        WeaponPack {
+            weapons: vec![
+                Weapon { weapon_type: WeaponType::Sword, ..Default::default() },
+                Weapon { weapon_type: WeaponType::Lance, ..Default::default() },
+                Weapon { weapon_type: WeaponType::Axe, ..Default::default() },
+            ],
+            equipped,
+        }
+    }
+
+    #[test]
+    fn test_take_before_equipped() {
+        let mut pack = get_pack(2);
+        let taken = pack.take(0).unwrap();
+
+        assert_eq!(taken.weapon_type, WeaponType::Sword);
+        assert_eq!(pack.get_equipped().weapon_type, WeaponType::Axe);
+    }
+
+    #[test]
+    fn test_take_after_equipped() {
+        let mut pack = get_pack(0);
+        pack.take(2);
+
+        assert_eq!(pack.equipped_index(), 0);
+        assert_eq!(pack.get_equipped().weapon_type, WeaponType::Sword);
+    }
+
+    #[test]
+    fn test_take_equipped() {
+        let mut pack = get_pack(2);
+        pack.take(2);
+
+        assert_eq!(pack.equipped_index(), 0);
+        assert_eq!(pack.weapons.len(), 2);
+    }
+
+    #[test]
+    fn test_take_last_weapon() {
+        let mut pack = get_pack(0);
+        pack.take(0);
+        pack.take(0);
+
+        assert!(pack.take(0).is_none());
+        assert_eq!(pack.weapons.len(), 1);
+    }
 }
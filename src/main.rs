@@ -1,3 +1,6 @@
+// Bevy queries are naturally verbose, so this lint mostly adds noise
+#![allow(clippy::type_complexity)]
+
 use bevy::prelude::*;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
@@ -15,8 +18,17 @@ pub enum AppState {
 }
 
 fn main() {
+    // Loaded up front so the window opens with the saved mode and resolution
+    let settings = game::Settings::load();
     App::new()
-        .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
+        .add_plugins(DefaultPlugins
+            .set(ImagePlugin::default_nearest())
+            .set(WindowPlugin {
+                primary_window: Some(settings.window()),
+                ..default()
+            })
+        )
+        .insert_resource(settings)
         .add_plugins(WorldInspectorPlugin::default())
         .init_state::<AppState>()
         .add_systems(Startup, setup)
@@ -27,7 +39,7 @@ fn main() {
 }
 
 fn setup(mut commands: Commands) {
-    commands.spawn(Camera2d::default());
+    commands.spawn(Camera2d);
 }
 
 fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
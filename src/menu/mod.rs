@@ -1,5 +1,6 @@
 use bevy::{app::AppExit, prelude::*};
 
+use crate::game::{Action, Binding, ResumeRun, RunSave, Setting, Settings};
 use crate::AppState;
 
 use super::despawn_screen;
@@ -11,7 +12,20 @@ pub fn menu_plugin(app: &mut App) {
         .add_systems(OnEnter(MenuState::Main), main_menu_setup)
         .add_systems(OnExit(MenuState::Main), despawn_screen::<OnMainMenuScreen>)
         .add_systems(OnEnter(MenuState::Settings), settings_menu_setup)
-        .add_systems(OnExit(MenuState::Settings), despawn_screen::<OnSettingsMenuScreen>)
+        .add_systems(OnExit(MenuState::Settings), (despawn_screen::<OnSettingsMenuScreen>, save_settings))
+        .add_systems(Update, (
+            setting_button_action,
+            update_setting_values.run_if(resource_changed::<Settings>),
+        ).run_if(in_state(MenuState::Settings)))
+        .init_resource::<Rebinding>()
+        .add_systems(OnEnter(MenuState::Controls), controls_menu_setup)
+        .add_systems(OnExit(MenuState::Controls), (despawn_screen::<OnControlsMenuScreen>, save_settings, stop_rebinding))
+        .add_systems(Update, (
+            // Before the buttons, so the click on Rebind isn't taken as the new binding
+            capture_binding,
+            rebind_button_action,
+            update_binding_values.run_if(resource_changed::<Settings>.or(resource_changed::<Rebinding>)),
+        ).chain().run_if(in_state(MenuState::Controls)))
         .add_systems(Update, (menu_action, button_system).run_if(in_state(AppState::Menu)));
 }
 
@@ -20,6 +34,7 @@ pub fn menu_plugin(app: &mut App) {
 enum MenuState {
     Main,
     Settings,
+    Controls,
     #[default]
     Disabled
 }
@@ -31,6 +46,9 @@ struct OnMainMenuScreen;
 #[derive(Component)]
 struct OnSettingsMenuScreen;
 
+#[derive(Component)]
+struct OnControlsMenuScreen;
+
 // Examples have srgb, but that's missing now. Using rgb instead 
 const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
@@ -41,10 +59,40 @@ const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
 #[derive(Component)]
 struct SelectedOption;
 
+/// Buttons that only make sense while there is a saved run
+#[derive(Component)]
+struct SavedRunButton;
+
+/// Moves a setting up or down one step
+#[derive(Component)]
+struct SettingButton {
+    setting: Setting,
+    step: i32,
+}
+
+#[derive(Component)]
+struct SettingValue(Setting);
+
+/// Waits for the next key or button and binds it to the action
+#[derive(Component)]
+struct RebindButton(Action);
+
+#[derive(Component)]
+struct BindingValue(Action);
+
+/// The action waiting for a new binding on the controls screen
+#[derive(Default, Resource)]
+struct Rebinding(Option<Action>);
+
 #[derive(Component)]
 enum MenuButtonAction {
+    Continue,
+    AbandonRun,
     Play,
     Settings,
+    Controls,
+    ResetBindings,
+    BackToSettings,
     BackToMainMenu,
     Quit,
 }
@@ -128,10 +176,32 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         ));
                     });
 
+                    if RunSave::exists() {
+                        parent.spawn((
+                            Button,
+                            button_style.clone(),
+                            TextColor(NORMAL_BUTTON),
+                            MenuButtonAction::Continue,
+                            SavedRunButton,
+                        ))
+                        .with_children(|parent| {
+                            let icon = asset_server.load("right.png");
+                            parent.spawn((
+                                ImageNode {
+                                    image: icon,
+                                    ..default()
+                                },
+                                button_icon_style.clone(),
+                            ));
+                            parent.spawn((
+                                Text::new("Continue"),
+                                button_text_style.clone()
+                            ));
+                        });
+                    }
+
                     parent.spawn((
-                        Button {
-                            ..default()
-                        },
+                        Button,
                         button_style.clone(),
                         TextColor(NORMAL_BUTTON),
                         MenuButtonAction::Play,
@@ -152,9 +222,7 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                     });
 
                     parent.spawn((
-                        Button {
-                            ..default()
-                        },
+                        Button,
                         button_style.clone(),
                         TextColor(NORMAL_BUTTON),
                         MenuButtonAction::Settings,
@@ -174,10 +242,24 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         ));
                     });
 
+                    if RunSave::exists() {
+                        parent.spawn((
+                            Button,
+                            button_style.clone(),
+                            TextColor(NORMAL_BUTTON),
+                            MenuButtonAction::AbandonRun,
+                            SavedRunButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new("Abandon run"),
+                                button_text_style.clone()
+                            ));
+                        });
+                    }
+
                     parent.spawn((
-                        Button {
-                            ..default()
-                        },
+                        Button,
                         button_style.clone(),
                         TextColor(NORMAL_BUTTON),
                         MenuButtonAction::Quit,
@@ -197,7 +279,7 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         });
 }
 
-fn settings_menu_setup(mut commands: Commands) {
+fn settings_menu_setup(mut commands: Commands, settings: Res<Settings>) {
     let button_style = Node {
         width: Val::Px(200.0),
         height: Val::Px(65.0),
@@ -206,11 +288,23 @@ fn settings_menu_setup(mut commands: Commands) {
         align_items: AlignItems::Center,
         ..default()
     };
+    let step_button_style = Node {
+        width: Val::Px(40.0),
+        height: Val::Px(40.0),
+        margin: UiRect::horizontal(Val::Px(10.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
 
     let button_text_style = TextFont {
         font_size: 40.0,
         ..default()
     };
+    let setting_text_style = TextFont {
+        font_size: 28.0,
+        ..default()
+    };
 
     commands
         .spawn((
@@ -232,40 +326,289 @@ fn settings_menu_setup(mut commands: Commands) {
                     ..default()
                 })
                 .with_children(|parent| {
-                    for (action, text) in [
-                        (MenuButtonAction::BackToMainMenu, "Back"),
-                    ] {
+                    for setting in Setting::ALL {
                         parent
-                            .spawn((
-                                Button {
-                                    ..default()
-                                },
-                                button_style.clone(),
-                                action,
-                            ))
+                            .spawn(Node {
+                                flex_direction: FlexDirection::Row,
+                                align_items: AlignItems::Center,
+                                margin: UiRect::vertical(Val::Px(4.0)),
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    Text::new(setting.get_name()),
+                                    setting_text_style.clone(),
+                                    TextColor(TEXT_COLOR),
+                                    Node { width: Val::Px(260.0), ..default() },
+                                ));
+                                for (step, label) in [(-1, "<"), (1, ">")] {
+                                    if step == 1 {
+                                        parent.spawn((
+                                            Text::new(settings.value_text(setting)),
+                                            setting_text_style.clone(),
+                                            TextColor(TEXT_COLOR),
+                                            TextLayout::new_with_justify(JustifyText::Center),
+                                            Node { width: Val::Px(180.0), ..default() },
+                                            SettingValue(setting),
+                                        ));
+                                    }
+                                    parent
+                                        .spawn((
+                                            Button,
+                                            step_button_style.clone(),
+                                            SettingButton { setting, step },
+                                        ))
+                                        .with_children(|parent| {
+                                            parent.spawn((
+                                                Text::new(label),
+                                                setting_text_style.clone(),
+                                            ));
+                                        });
+                                }
+                            });
+                    }
+
+                    parent
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (action, label) in [
+                                (MenuButtonAction::Controls, "Controls"),
+                                (MenuButtonAction::BackToMainMenu, "Back"),
+                            ] {
+                                parent
+                                    .spawn((
+                                        Button,
+                                        button_style.clone(),
+                                        action,
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            Text::new(label),
+                                            button_text_style.clone(),
+                                        ));
+                                    });
+                            }
+                        });
+                });
+        });
+}
+
+fn controls_menu_setup(mut commands: Commands, settings: Res<Settings>) {
+    let button_style = Node {
+        width: Val::Px(200.0),
+        height: Val::Px(65.0),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let rebind_button_style = Node {
+        width: Val::Px(110.0),
+        height: Val::Px(32.0),
+        margin: UiRect::horizontal(Val::Px(10.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    let button_text_style = TextFont {
+        font_size: 40.0,
+        ..default()
+    };
+    let binding_text_style = TextFont {
+        font_size: 22.0,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            OnControlsMenuScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for action in Action::ALL {
+                        parent
+                            .spawn(Node {
+                                flex_direction: FlexDirection::Row,
+                                align_items: AlignItems::Center,
+                                margin: UiRect::vertical(Val::Px(2.0)),
+                                ..default()
+                            })
                             .with_children(|parent| {
                                 parent.spawn((
-                                    Text::new(text),
-                                    button_text_style.clone(),
+                                    Text::new(action.get_name()),
+                                    binding_text_style.clone(),
+                                    TextColor(TEXT_COLOR),
+                                    Node { width: Val::Px(180.0), ..default() },
+                                ));
+                                parent.spawn((
+                                    Text::new(settings.bindings.describe(action)),
+                                    binding_text_style.clone(),
+                                    TextColor(TEXT_COLOR),
+                                    Node { width: Val::Px(520.0), ..default() },
+                                    BindingValue(action),
                                 ));
+                                parent
+                                    .spawn((
+                                        Button,
+                                        rebind_button_style.clone(),
+                                        RebindButton(action),
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            Text::new("Rebind"),
+                                            binding_text_style.clone(),
+                                        ));
+                                    });
                             });
                     }
+
+                    parent
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (action, label) in [
+                                (MenuButtonAction::ResetBindings, "Reset"),
+                                (MenuButtonAction::BackToSettings, "Back"),
+                            ] {
+                                parent
+                                    .spawn((
+                                        Button,
+                                        button_style.clone(),
+                                        action,
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            Text::new(label),
+                                            button_text_style.clone(),
+                                        ));
+                                    });
+                            }
+                        });
                 });
         });
 }
 
+fn setting_button_action(
+    interaction_query: Query<(&Interaction, &SettingButton), (Changed<Interaction>, With<Button>)>,
+    mut settings: ResMut<Settings>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            settings.adjust(button.setting, button.step);
+        }
+    }
+}
+
+fn update_setting_values(
+    mut value_q: Query<(&mut Text, &SettingValue)>,
+    settings: Res<Settings>,
+) {
+    for (mut text, value) in value_q.iter_mut() {
+        text.0 = settings.value_text(value.0);
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    settings.save();
+}
+
+fn rebind_button_action(
+    interaction_query: Query<(&Interaction, &RebindButton), (Changed<Interaction>, With<Button>)>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            rebinding.0 = Some(button.0);
+        }
+    }
+}
+
+/// Binds the first key, mouse button or gamepad button pressed while waiting
+fn capture_binding(
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+) {
+    let Some(action) = rebinding.0 else { return };
+
+    let binding = keys.get_just_pressed().next().map(|k| Binding::Key(*k))
+        .or_else(|| mouse.get_just_pressed().next().map(|b| Binding::Mouse(*b)))
+        .or_else(|| gamepads.iter().find_map(|g| g.get_just_pressed().next().map(|b| Binding::Gamepad(*b))));
+
+    if let Some(binding) = binding {
+        settings.bindings.rebind(action, binding);
+        rebinding.0 = None;
+    }
+}
+
+fn update_binding_values(
+    mut value_q: Query<(&mut Text, &BindingValue)>,
+    settings: Res<Settings>,
+    rebinding: Res<Rebinding>,
+) {
+    for (mut text, value) in value_q.iter_mut() {
+        text.0 = if rebinding.0 == Some(value.0) {
+            "Press a key or button...".to_string()
+        } else {
+            settings.bindings.describe(value.0)
+        };
+    }
+}
+
+fn stop_rebinding(mut rebinding: ResMut<Rebinding>) {
+    rebinding.0 = None;
+}
+
 fn menu_action(
+    mut commands: Commands,
     interaction_query: Query<
         (&Interaction, &MenuButtonAction),
         (Changed<Interaction>, With<Button>),
     >,
+    saved_run_q: Query<Entity, With<SavedRunButton>>,
     mut app_exit_events: EventWriter<AppExit>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut game_state: ResMut<NextState<AppState>>,
+    mut settings: ResMut<Settings>,
 ){
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
             match menu_button_action {
+                MenuButtonAction::Continue => match RunSave::load() {
+                    Ok(save) => {
+                        commands.insert_resource(ResumeRun(save));
+                        menu_state.set(MenuState::Disabled);
+                        game_state.set(AppState::Game);
+                    },
+                    Err(e) => error!("Unable to continue the run. {}", e),
+                },
+                MenuButtonAction::AbandonRun => {
+                    RunSave::delete();
+                    for entity in saved_run_q.iter() {
+                        commands.entity(entity).despawn_recursive();
+                    }
+                },
                 MenuButtonAction::Play => {
                     menu_state.set(MenuState::Disabled);
                     game_state.set(AppState::Game);
@@ -273,6 +616,15 @@ fn menu_action(
                 MenuButtonAction::Settings => {
                     menu_state.set(MenuState::Settings);
                 },
+                MenuButtonAction::Controls => {
+                    menu_state.set(MenuState::Controls);
+                },
+                MenuButtonAction::ResetBindings => {
+                    settings.bindings.reset();
+                },
+                MenuButtonAction::BackToSettings => {
+                    menu_state.set(MenuState::Settings);
+                },
                 MenuButtonAction::BackToMainMenu => {
                     menu_state.set(MenuState::Main);
                 },
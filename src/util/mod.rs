@@ -1,5 +1,28 @@
 use bevy_ecs_ldtk::GridCoords;
+use sim::Coords;
 
 pub fn manhattan_dist(start: GridCoords, end: GridCoords) -> u32 {
     (end.x - start.x).unsigned_abs() + (end.y - start.y).unsigned_abs()
 }
+
+/// GridCoords as the simulation's Coords
+pub trait ToSim {
+    fn to_sim(&self) -> Coords;
+}
+
+impl ToSim for GridCoords {
+    fn to_sim(&self) -> Coords {
+        Coords::new(self.x, self.y)
+    }
+}
+
+/// The simulation's Coords as GridCoords
+pub trait ToGrid {
+    fn to_grid(&self) -> GridCoords;
+}
+
+impl ToGrid for Coords {
+    fn to_grid(&self) -> GridCoords {
+        GridCoords::new(self.x, self.y)
+    }
+}